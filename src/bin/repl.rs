@@ -0,0 +1,64 @@
+use std::io::{self, BufRead, Write};
+
+use r_python::repl::Repl;
+
+fn main() -> io::Result<()> {
+    run(io::stdin().lock(), io::stdout())
+}
+
+/* Reads lines until the input is closed, prompting with '>>> ' for a
+ * new input and '... ' while a block is still open. */
+fn run(mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut repl = Repl::new();
+
+    loop {
+        let prompt = if repl.is_pending() { "... " } else { ">>> " };
+        write!(output, "{}", prompt)?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            if let Some(output_line) = repl.finish() {
+                writeln!(output, "{}", output_line)?;
+            }
+            writeln!(output)?;
+            return Ok(());
+        }
+        if let Some(output_line) = repl.feed(line.trim_end_matches(['\n', '\r'])) {
+            writeln!(output, "{}", output_line)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runaway_recursion_reports_an_error() {
+        /*
+         * > def f(n: int) -> int:
+         * >     return f(n + 1)
+         * >
+         * > f(0)
+         * > 1 + 1
+         *
+         * The call stops at the default depth limit, and the session goes
+         * on to the next input. The recursive calls are reported once,
+         * with a count.
+         */
+        let source = "def f(n: int) -> int:\n    return f(n + 1)\n\nf(0)\n1 + 1\n";
+        let mut output = Vec::new();
+
+        assert!(run(source.as_bytes(), &mut output).is_ok());
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[0],
+            ">>> ... ... >>> Error: f called at line 1, col 1 generated an error: \
+             f called at line 2, col 12 [repeated 998 more times] generated an error: \
+             maximum recursion depth exceeded at line 2, col 12"
+        );
+        assert_eq!(lines[1], ">>> 2");
+    }
+}
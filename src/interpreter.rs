@@ -1 +1,4 @@
+pub mod builtins;
+#[allow(clippy::module_inception)]
 pub mod interpreter;
+pub mod stepper;
@@ -0,0 +1,747 @@
+use std::cmp::Ordering;
+use std::num::IntErrorKind;
+use std::rc::Rc;
+
+use crate::interpreter::interpreter::{
+    compare, eval_add, eval_pow, into_iter_items, is_truthy, range, range_inclusive, values_equal,
+    Dict, EvalResult, HashKey, InterpreterError, Set,
+};
+
+pub fn is_builtin(name: &str) -> bool {
+    matches!(
+        name,
+        "str"
+            | "append"
+            | "set"
+            | "min"
+            | "max"
+            | "memoize"
+            | "annotations"
+            | "abs"
+            | "sum"
+            | "len"
+            | "fsum"
+            | "zip"
+            | "any"
+            | "all"
+            | "pow"
+            | "join"
+            | "range"
+            | "range_inclusive"
+            | "int"
+            | "float"
+            | "bool"
+            | "floor"
+            | "ceil"
+            | "round"
+            | "sorted"
+            | "reversed"
+            | "enumerate"
+            | "map"
+            | "filter"
+            | "count"
+            | "index"
+            | "upper"
+            | "lower"
+            | "strip"
+            | "split"
+    )
+}
+
+pub fn call_builtin(name: &str, args: Vec<EvalResult>) -> Result<EvalResult, InterpreterError> {
+    match name {
+        "str" => match args.as_slice() {
+            [] => Ok(EvalResult::CString(String::new())),
+            [value] => Ok(EvalResult::CString(value.to_string())),
+            _ => Err(InterpreterError::TypeError(format!(
+                "str expected at most 1 argument, got {}",
+                args.len()
+            ))),
+        },
+        /* A line calling append is run by the interpreter, which puts the
+         * grown list back under its name. Anywhere else there is no name
+         * to put it under. */
+        "append" => Err(InterpreterError::TypeError(String::from(
+            "append can only be called as a statement",
+        ))),
+        "set" => match args.as_slice() {
+            [] => Ok(EvalResult::Set(Box::new(Set::new()))),
+            [EvalResult::List(items) | EvalResult::Tuple(items)] => {
+                let mut set = Set::new();
+                for item in items {
+                    set.insert(HashKey::from_value(item)?);
+                }
+                Ok(EvalResult::Set(Box::new(set)))
+            }
+            [EvalResult::Set(set)] => Ok(EvalResult::Set(set.clone())),
+            [value] => Err(InterpreterError::TypeError(format!(
+                "'{}' object is not iterable",
+                value.type_name()
+            ))),
+            _ => Err(InterpreterError::TypeError(format!(
+                "set expected at most 1 argument, got {}",
+                args.len()
+            ))),
+        },
+        "abs" => match args.as_slice() {
+            [EvalResult::CInt(v)] => v
+                .checked_abs()
+                .map(EvalResult::CInt)
+                .ok_or(InterpreterError::IntegerOverflow),
+            [EvalResult::CReal(v)] => Ok(EvalResult::CReal(v.abs())),
+            [EvalResult::Bool(v)] => Ok(EvalResult::CInt(*v as i32)),
+            [value] => Err(InterpreterError::TypeError(format!(
+                "bad operand type for abs(): '{}'",
+                value.type_name()
+            ))),
+            _ => Err(InterpreterError::TypeError(format!(
+                "abs expected 1 argument, got {}",
+                args.len()
+            ))),
+        },
+        "int" => match args.as_slice() {
+            [] => Ok(EvalResult::CInt(0)),
+            [value] => to_int(value),
+            _ => Err(InterpreterError::TypeError(format!(
+                "int expected at most 1 argument, got {}",
+                args.len()
+            ))),
+        },
+        "float" => match args.as_slice() {
+            [] => Ok(EvalResult::CReal(0.0)),
+            [value] => to_float(value),
+            _ => Err(InterpreterError::TypeError(format!(
+                "float expected at most 1 argument, got {}",
+                args.len()
+            ))),
+        },
+        "floor" | "ceil" | "round" => match args.as_slice() {
+            [value] => to_integral(
+                value,
+                match name {
+                    "floor" => f32::floor,
+                    "ceil" => f32::ceil,
+                    _ => f32::round_ties_even,
+                },
+            ),
+            _ => Err(InterpreterError::TypeError(format!(
+                "{} expected 1 argument, got {}",
+                name,
+                args.len()
+            ))),
+        },
+        "bool" => match args.as_slice() {
+            [] => Ok(EvalResult::Bool(false)),
+            [value] => Ok(EvalResult::Bool(is_truthy(value))),
+            _ => Err(InterpreterError::TypeError(format!(
+                "bool expected at most 1 argument, got {}",
+                args.len()
+            ))),
+        },
+        "len" => {
+            let len = match args.as_slice() {
+                [EvalResult::CString(v)] => v.chars().count(),
+                [EvalResult::List(items) | EvalResult::Tuple(items)] => items.len(),
+                [EvalResult::Dict(dict)] => dict.len(),
+                [EvalResult::Set(set)] => set.len(),
+                [value] => {
+                    return Err(InterpreterError::TypeError(format!(
+                        "object of type '{}' has no len()",
+                        value.type_name()
+                    )))
+                }
+                _ => {
+                    return Err(InterpreterError::TypeError(format!(
+                        "len expected 1 argument, got {}",
+                        args.len()
+                    )))
+                }
+            };
+            i32::try_from(len)
+                .map(EvalResult::CInt)
+                .map_err(|_| InterpreterError::IntegerOverflow)
+        }
+        "sum" => match args.as_slice() {
+            [EvalResult::List(items) | EvalResult::Tuple(items)] => {
+                total(EvalResult::CInt(0), items)
+            }
+            [EvalResult::List(items) | EvalResult::Tuple(items), start] => {
+                total(start.clone(), items)
+            }
+            [set @ EvalResult::Set(_)] => {
+                total(EvalResult::CInt(0), &into_iter_items(set.clone())?)
+            }
+            [set @ EvalResult::Set(_), start] => {
+                total(start.clone(), &into_iter_items(set.clone())?)
+            }
+            [value] | [value, _] => Err(InterpreterError::TypeError(format!(
+                "'{}' object is not iterable",
+                value.type_name()
+            ))),
+            _ => Err(InterpreterError::TypeError(format!(
+                "sum expected 1 or 2 arguments, got {}",
+                args.len()
+            ))),
+        },
+        "fsum" => match args.as_slice() {
+            [EvalResult::List(items) | EvalResult::Tuple(items)] => fsum(items),
+            [set @ EvalResult::Set(_)] => fsum(&into_iter_items(set.clone())?),
+            [value] => Err(InterpreterError::TypeError(format!(
+                "'{}' object is not iterable",
+                value.type_name()
+            ))),
+            _ => Err(InterpreterError::TypeError(format!(
+                "fsum expected 1 argument, got {}",
+                args.len()
+            ))),
+        },
+        "pow" => match args.as_slice() {
+            [base, exp] => eval_pow(base.clone(), exp.clone()),
+            [EvalResult::CInt(base), EvalResult::CInt(exp), EvalResult::CInt(modulus)] => {
+                mod_pow(*base, *exp, *modulus)
+            }
+            [_, _, _] => Err(InterpreterError::TypeError(String::from(
+                "pow() 3rd argument not allowed unless all arguments are integers",
+            ))),
+            _ => Err(InterpreterError::TypeError(format!(
+                "pow expected 2 or 3 arguments, got {}",
+                args.len()
+            ))),
+        },
+        "range" | "range_inclusive" => {
+            let range = match name {
+                "range" => range,
+                _ => range_inclusive,
+            };
+            match args.as_slice() {
+                [end] => range(EvalResult::CInt(0), end.clone(), EvalResult::CInt(1)),
+                [start, end] => range(start.clone(), end.clone(), EvalResult::CInt(1)),
+                [start, end, step] => range(start.clone(), end.clone(), step.clone()),
+                _ => Err(InterpreterError::TypeError(format!(
+                    "{} expected 1 to 3 arguments, got {}",
+                    name,
+                    args.len()
+                ))),
+            }
+        }
+        "join" => match args.as_slice() {
+            [EvalResult::CString(sep), EvalResult::List(items) | EvalResult::Tuple(items)] => {
+                join(sep, items)
+            }
+            [EvalResult::CString(_), value] => Err(InterpreterError::TypeError(format!(
+                "'{}' object is not iterable",
+                value.type_name()
+            ))),
+            [value, _] => Err(InterpreterError::TypeError(format!(
+                "join separator must be str, not {}",
+                value.type_name()
+            ))),
+            _ => Err(InterpreterError::TypeError(format!(
+                "join expected 2 arguments, got {}",
+                args.len()
+            ))),
+        },
+        /* Without a separator, runs of whitespace split the string and
+         * leading or trailing whitespace gives no empty strings. */
+        "split" => {
+            let pieces: Vec<&str> = match args.as_slice() {
+                [EvalResult::CString(v)] => v.split_whitespace().collect(),
+                [EvalResult::CString(_), EvalResult::CString(sep)] if sep.is_empty() => {
+                    return Err(InterpreterError::ValueError(String::from(
+                        "empty separator",
+                    )))
+                }
+                [EvalResult::CString(v), EvalResult::CString(sep)] => {
+                    v.split(sep.as_str()).collect()
+                }
+                [value] | [value, _] if !matches!(value, EvalResult::CString(_)) => {
+                    return Err(InterpreterError::TypeError(format!(
+                        "split expected str, got {}",
+                        value.type_name()
+                    )))
+                }
+                [_, sep] => {
+                    return Err(InterpreterError::TypeError(format!(
+                        "must be str, not {}",
+                        sep.type_name()
+                    )))
+                }
+                _ => {
+                    return Err(InterpreterError::TypeError(format!(
+                        "split expected 1 or 2 arguments, got {}",
+                        args.len()
+                    )))
+                }
+            };
+            Ok(EvalResult::List(
+                pieces
+                    .into_iter()
+                    .map(|piece| EvalResult::CString(piece.to_string()))
+                    .collect(),
+            ))
+        }
+        "upper" | "lower" | "strip" => match args.as_slice() {
+            [EvalResult::CString(v)] => Ok(EvalResult::CString(match name {
+                "upper" => v.to_uppercase(),
+                "lower" => v.to_lowercase(),
+                _ => v.trim().to_string(),
+            })),
+            [value] => Err(InterpreterError::TypeError(format!(
+                "{} expected str, got {}",
+                name,
+                value.type_name()
+            ))),
+            _ => Err(InterpreterError::TypeError(format!(
+                "{} expected 1 argument, got {}",
+                name,
+                args.len()
+            ))),
+        },
+        "zip" => zip(args),
+        "enumerate" => match args.as_slice() {
+            [iterable] => enumerate(iterable.clone(), 0),
+            [iterable, EvalResult::CInt(start)] => enumerate(iterable.clone(), *start),
+            [_, start] => Err(InterpreterError::TypeError(format!(
+                "'{}' object cannot be interpreted as an integer",
+                start.type_name()
+            ))),
+            _ => Err(InterpreterError::TypeError(format!(
+                "enumerate expected 1 or 2 arguments, got {}",
+                args.len()
+            ))),
+        },
+        "any" | "all" => match <[EvalResult; 1]>::try_from(args) {
+            Ok([iterable]) => {
+                let items = into_iter_items(iterable)?;
+                let found = if name == "any" {
+                    items.iter().any(is_truthy)
+                } else {
+                    items.iter().all(is_truthy)
+                };
+                Ok(EvalResult::Bool(found))
+            }
+            Err(args) => Err(InterpreterError::TypeError(format!(
+                "{} expected 1 argument, got {}",
+                name,
+                args.len()
+            ))),
+        },
+        /* Both look items up with the equality of '==', so 1 matches 1.0. */
+        "count" | "index" => match args.as_slice() {
+            [EvalResult::List(items) | EvalResult::Tuple(items), value] => {
+                let mut found = items
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, item)| values_equal(item, value));
+                if name == "count" {
+                    return Ok(EvalResult::CInt(found.count() as i32));
+                }
+                match found.next() {
+                    Some((position, _)) => Ok(EvalResult::CInt(position as i32)),
+                    None => Err(InterpreterError::ValueError(format!(
+                        "{} is not in list",
+                        value.repr()
+                    ))),
+                }
+            }
+            [target, _] => Err(InterpreterError::TypeError(format!(
+                "{} expected a list, got {}",
+                name,
+                target.type_name()
+            ))),
+            _ => Err(InterpreterError::TypeError(format!(
+                "{} expected 2 arguments, got {}",
+                name,
+                args.len()
+            ))),
+        },
+        "min" => extreme(name, args, Ordering::Less),
+        "max" => extreme(name, args, Ordering::Greater),
+        /* There are no keyword arguments, so Python's 'reverse=True' is
+         * passed as a second positional argument. */
+        "sorted" => match <[EvalResult; 1]>::try_from(args) {
+            Ok([iterable]) => sorted(iterable, false),
+            Err(args) => match <[EvalResult; 2]>::try_from(args) {
+                Ok([iterable, reverse]) => sorted(iterable, is_truthy(&reverse)),
+                Err(args) => Err(InterpreterError::TypeError(format!(
+                    "sorted expected 1 or 2 arguments, got {}",
+                    args.len()
+                ))),
+            },
+        },
+        /* Python hands back an iterator; here a list is built right away,
+         * and a string stays a string. */
+        "reversed" => match <[EvalResult; 1]>::try_from(args) {
+            Ok([EvalResult::List(mut items) | EvalResult::Tuple(mut items)]) => {
+                items.reverse();
+                Ok(EvalResult::List(items))
+            }
+            Ok([EvalResult::CString(v)]) => Ok(EvalResult::CString(v.chars().rev().collect())),
+            Ok(_) => Err(InterpreterError::TypeError(String::from(
+                "argument to reversed() must be a sequence",
+            ))),
+            Err(args) => Err(InterpreterError::TypeError(format!(
+                "reversed expected 1 argument, got {}",
+                args.len()
+            ))),
+        },
+        "memoize" => match args.as_slice() {
+            [EvalResult::Func(func)] => Ok(EvalResult::Func(Rc::new(func.memoized()))),
+            [value] => Err(InterpreterError::TypeError(format!(
+                "memoize expected a function, got {}",
+                value.type_name()
+            ))),
+            _ => Err(InterpreterError::TypeError(format!(
+                "memoize expected 1 argument, got {}",
+                args.len()
+            ))),
+        },
+        /* Maps each parameter name, and "return", to its declared type
+         * written as a string, in declaration order. */
+        "annotations" => match args.as_slice() {
+            [EvalResult::Func(func)] => {
+                let mut dict = Dict::new();
+                for (param, kind) in func.params.iter() {
+                    dict.insert(
+                        HashKey::CString(param.clone()),
+                        EvalResult::CString(kind.to_string()),
+                    );
+                }
+                dict.insert(
+                    HashKey::CString(String::from("return")),
+                    EvalResult::CString(func.kind.to_string()),
+                );
+                Ok(EvalResult::Dict(Box::new(dict)))
+            }
+            [value] => Err(InterpreterError::TypeError(format!(
+                "annotations expected a function, got {}",
+                value.type_name()
+            ))),
+            _ => Err(InterpreterError::TypeError(format!(
+                "annotations expected 1 argument, got {}",
+                args.len()
+            ))),
+        },
+        _ => Err(InterpreterError::UndefinedFunction(name.to_string())),
+    }
+}
+
+/* int(x): reals are truncated towards zero and strings are read as a
+ * whole number, surrounding whitespace aside. */
+fn to_int(value: &EvalResult) -> Result<EvalResult, InterpreterError> {
+    match value {
+        EvalResult::CInt(v) => Ok(EvalResult::CInt(*v)),
+        EvalResult::Bool(v) => Ok(EvalResult::CInt(*v as i32)),
+        EvalResult::CReal(v) if v.is_nan() => Err(InterpreterError::ValueError(String::from(
+            "cannot convert float NaN to integer",
+        ))),
+        EvalResult::CReal(v) => {
+            let v = v.trunc();
+            if v < i32::MIN as f32 || v >= i32::MAX as f32 {
+                return Err(InterpreterError::IntegerOverflow);
+            }
+            Ok(EvalResult::CInt(v as i32))
+        }
+        EvalResult::CString(text) => match text.trim().parse::<i32>() {
+            Ok(v) => Ok(EvalResult::CInt(v)),
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    IntErrorKind::PosOverflow | IntErrorKind::NegOverflow
+                ) =>
+            {
+                Err(InterpreterError::IntegerOverflow)
+            }
+            Err(_) => Err(InterpreterError::ValueError(format!(
+                "invalid literal for int(): {}",
+                value.repr()
+            ))),
+        },
+        _ => Err(InterpreterError::TypeError(format!(
+            "int() argument must be a string or a number, not '{}'",
+            value.type_name()
+        ))),
+    }
+}
+
+/* float(x): ints and bools are promoted and strings are read as a
+ * number, surrounding whitespace aside. */
+fn to_float(value: &EvalResult) -> Result<EvalResult, InterpreterError> {
+    match value {
+        EvalResult::CReal(v) => Ok(EvalResult::CReal(*v)),
+        EvalResult::CInt(v) => Ok(EvalResult::CReal(*v as f32)),
+        EvalResult::Bool(v) => Ok(EvalResult::CReal(*v as i32 as f32)),
+        EvalResult::CString(text) => {
+            text.trim()
+                .parse::<f32>()
+                .map(EvalResult::CReal)
+                .map_err(|_| {
+                    InterpreterError::ValueError(format!(
+                        "invalid literal for float(): {}",
+                        value.repr()
+                    ))
+                })
+        }
+        _ => Err(InterpreterError::TypeError(format!(
+            "float() argument must be a string or a number, not '{}'",
+            value.type_name()
+        ))),
+    }
+}
+
+/* floor(x), ceil(x) and round(x): reals are rounded to an integral value
+ * and converted like int(x); ints and bools are already integral. round
+ * sends halves to the even neighbour, as Python does. */
+fn to_integral(
+    value: &EvalResult,
+    rounding: fn(f32) -> f32,
+) -> Result<EvalResult, InterpreterError> {
+    match value {
+        EvalResult::CReal(v) => to_int(&EvalResult::CReal(rounding(*v))),
+        EvalResult::CInt(_) | EvalResult::Bool(_) => to_int(value),
+        _ => Err(InterpreterError::TypeError(format!(
+            "must be real number, not '{}'",
+            value.type_name()
+        ))),
+    }
+}
+
+/* base ** exp % modulus by squaring, reducing after every step so the
+ * intermediate values never outgrow the modulus. The result takes the
+ * sign of the modulus, as '%' does in Python. */
+fn mod_pow(base: i32, exp: i32, modulus: i32) -> Result<EvalResult, InterpreterError> {
+    if modulus == 0 {
+        return Err(InterpreterError::ValueError(String::from(
+            "pow() 3rd argument cannot be 0",
+        )));
+    }
+    if exp < 0 {
+        return Err(InterpreterError::ValueError(String::from(
+            "pow() 2nd argument cannot be negative when 3rd argument specified",
+        )));
+    }
+    let m = (modulus as i64).abs();
+    let mut result: i64 = 1 % m;
+    let mut base = (base as i64).rem_euclid(m);
+    let mut exp = exp;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % m;
+        }
+        base = base * base % m;
+        exp >>= 1;
+    }
+    if modulus < 0 && result != 0 {
+        result -= m;
+    }
+    Ok(EvalResult::CInt(result as i32))
+}
+
+/* join(sep, items) is Python's sep.join(items). The length of the result
+ * is worked out first so that it is built in a single allocation, where
+ * adding the pieces up one by one would copy the prefix again and again. */
+fn join(sep: &str, items: &[EvalResult]) -> Result<EvalResult, InterpreterError> {
+    let mut len = sep.len() * items.len().saturating_sub(1);
+    for (position, item) in items.iter().enumerate() {
+        match item {
+            EvalResult::CString(s) => len += s.len(),
+            _ => {
+                return Err(InterpreterError::TypeError(format!(
+                    "sequence item {}: expected str instance, {} found",
+                    position,
+                    item.type_name()
+                )))
+            }
+        }
+    }
+
+    let mut joined = String::with_capacity(len);
+    for (position, item) in items.iter().enumerate() {
+        if position > 0 {
+            joined.push_str(sep);
+        }
+        if let EvalResult::CString(s) = item {
+            joined.push_str(s);
+        }
+    }
+    Ok(EvalResult::CString(joined))
+}
+
+/* Pairs up the items of any number of iterables into tuples, stopping at
+ * the end of the shortest one. */
+fn zip(args: Vec<EvalResult>) -> Result<EvalResult, InterpreterError> {
+    let mut columns = Vec::with_capacity(args.len());
+    for (position, arg) in args.into_iter().enumerate() {
+        let items = into_iter_items(arg).map_err(|_| {
+            InterpreterError::TypeError(format!(
+                "zip argument #{} must support iteration",
+                position + 1
+            ))
+        })?;
+        columns.push(items.into_iter());
+    }
+
+    let mut rows = Vec::new();
+    if columns.is_empty() {
+        return Ok(EvalResult::List(rows));
+    }
+    loop {
+        let mut row = Vec::with_capacity(columns.len());
+        for column in columns.iter_mut() {
+            match column.next() {
+                Some(item) => row.push(item),
+                None => return Ok(EvalResult::List(rows)),
+            }
+        }
+        rows.push(EvalResult::Tuple(row));
+    }
+}
+
+/* Pairs every item with its position, counting from 'start', as a list
+ * of (index, item) tuples that a for loop can unpack. */
+fn enumerate(iterable: EvalResult, start: i32) -> Result<EvalResult, InterpreterError> {
+    let mut rows = Vec::new();
+    let mut index = start;
+    for item in into_iter_items(iterable)? {
+        rows.push(EvalResult::Tuple(vec![EvalResult::CInt(index), item]));
+        index = index
+            .checked_add(1)
+            .ok_or(InterpreterError::IntegerOverflow)?;
+    }
+    Ok(EvalResult::List(rows))
+}
+
+/* min and max take either a single list or several values, and compare
+ * them like '<' does, so ints and reals mix while the winning element is
+ * returned as it was given. On ties the first one wins. */
+fn extreme(
+    name: &str,
+    args: Vec<EvalResult>,
+    wanted: Ordering,
+) -> Result<EvalResult, InterpreterError> {
+    let items = match args.as_slice() {
+        [] => {
+            return Err(InterpreterError::TypeError(format!(
+                "{} expected at least 1 argument, got 0",
+                name
+            )))
+        }
+        [EvalResult::List(items) | EvalResult::Tuple(items)] => items.clone(),
+        [set @ EvalResult::Set(_)] => into_iter_items(set.clone())?,
+        [value] => {
+            return Err(InterpreterError::TypeError(format!(
+                "'{}' object is not iterable",
+                value.type_name()
+            )))
+        }
+        _ => args,
+    };
+
+    let op = if wanted == Ordering::Less { "<" } else { ">" };
+    let mut items = items.into_iter();
+    let mut best = match items.next() {
+        Some(item) => item,
+        None => {
+            return Err(InterpreterError::ValueError(format!(
+                "{}() arg is an empty sequence",
+                name
+            )))
+        }
+    };
+    for item in items {
+        if compare(&item, &best, op)? == Some(wanted) {
+            best = item;
+        }
+    }
+    Ok(best)
+}
+
+/* A new list with the items in ascending order, or descending when
+ * 'reverse' is set. Like Python's, the sort is stable either way, and it
+ * fails on the first pair of items that can't be compared. */
+fn sorted(iterable: EvalResult, reverse: bool) -> Result<EvalResult, InterpreterError> {
+    let mut items = match iterable {
+        EvalResult::List(_)
+        | EvalResult::Tuple(_)
+        | EvalResult::CString(_)
+        | EvalResult::Set(_) => into_iter_items(iterable)?,
+        value => {
+            return Err(InterpreterError::TypeError(format!(
+                "'{}' object is not iterable",
+                value.type_name()
+            )))
+        }
+    };
+
+    let mut error = None;
+    items.sort_by(|lhs, rhs| {
+        let (lhs, rhs) = if reverse { (rhs, lhs) } else { (lhs, rhs) };
+        match compare(lhs, rhs, "<") {
+            Ok(order) => order.unwrap_or(Ordering::Equal),
+            Err(e) => {
+                error.get_or_insert(e);
+                Ordering::Equal
+            }
+        }
+    });
+    match error {
+        Some(e) => Err(e),
+        None => Ok(EvalResult::List(items)),
+    }
+}
+
+/* Adds the items up as a real with Neumaier's compensated summation,
+ * carried out in f64, so the low order bits that a naive running total
+ * drops are kept in 'compensation' and added back at the end. */
+fn fsum(items: &[EvalResult]) -> Result<EvalResult, InterpreterError> {
+    let mut acc = 0.0_f64;
+    let mut compensation = 0.0_f64;
+    for item in items {
+        let value = match item {
+            EvalResult::CInt(v) => *v as f64,
+            EvalResult::CReal(v) => *v as f64,
+            EvalResult::Bool(v) => *v as i32 as f64,
+            _ => {
+                return Err(InterpreterError::TypeError(format!(
+                    "must be real number, not {}",
+                    item.type_name()
+                )))
+            }
+        };
+        let next = acc + value;
+        compensation += if acc.abs() >= value.abs() {
+            (acc - next) + value
+        } else {
+            (value - next) + acc
+        };
+        acc = next;
+    }
+    Ok(EvalResult::CReal((acc + compensation) as f32))
+}
+
+/* Adds the items to start with the coercions of '+', so the sum stays an
+ * int until a real shows up. Only numbers are summed: '+' would also join
+ * strings and lists, which sum does not do. */
+fn total(start: EvalResult, items: &[EvalResult]) -> Result<EvalResult, InterpreterError> {
+    let is_number = |value: &EvalResult| {
+        matches!(
+            value,
+            EvalResult::CInt(_) | EvalResult::CReal(_) | EvalResult::Bool(_)
+        )
+    };
+    let mut acc = match start {
+        EvalResult::Bool(v) => EvalResult::CInt(v as i32),
+        start => start,
+    };
+    for item in items {
+        if !is_number(&acc) || !is_number(item) {
+            return Err(InterpreterError::TypeError(format!(
+                "unsupported operand type(s) for +: '{}' and '{}'",
+                acc.type_name(),
+                item.type_name()
+            )));
+        }
+        acc = eval_add(acc, item.clone())?;
+    }
+    Ok(acc)
+}
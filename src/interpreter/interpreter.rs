@@ -1,23 +1,177 @@
-use std::collections::HashMap;
-
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+use crate::interpreter::builtins::{call_builtin, is_builtin};
+use crate::ir::ast::CmpOp;
 use crate::ir::ast::Expression;
 use crate::ir::ast::Name;
+use crate::ir::ast::Span;
 use crate::ir::ast::Statement;
+use crate::ir::ast::Type;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpreterError {
+    DivisionByZero,
+    IntegerOverflow,
+    UndefinedVariable(Name),
+    UndefinedFunction(Name),
+    TypeError(String),
+    ValueError(String),
+    SyntaxError(String),
+    IndexError(String),
+    KeyError(String),
+    ArityMismatch {
+        name: Name,
+        expected: usize,
+        got: usize,
+    },
+    ConditionError(Box<InterpreterError>),
+    /* An error raised inside a call to 'name'. 'call' is where the call
+     * was made, once the error has propagated out to it. */
+    FunctionError {
+        name: Name,
+        error: Box<InterpreterError>,
+        call: Option<Span>,
+    },
+    Located {
+        error: Box<InterpreterError>,
+        span: Span,
+    },
+    /* A failed 'assert', with its message when it was given one. */
+    AssertionError(Option<String>),
+    RecursionLimitExceeded,
+    StepLimitExceeded,
+    NotImplemented,
+}
+
+impl InterpreterError {
+    /* The error that started it all, looking through the wrappers added
+     * while it propagated out of conditions and function calls. */
+    pub fn root_cause(&self) -> &InterpreterError {
+        match self {
+            InterpreterError::ConditionError(error) => error.root_cause(),
+            InterpreterError::FunctionError { error, .. } => error.root_cause(),
+            InterpreterError::Located { error, .. } => error.root_cause(),
+            _ => self,
+        }
+    }
+
+    /* Tags the error with the position of the node it escaped from. Only
+     * the innermost position is kept, as it is the most precise one, except
+     * that an error leaving a function call also records the call site;
+     * nested calls thus read like a traceback. */
+    pub fn located(self, span: Span) -> InterpreterError {
+        match self {
+            InterpreterError::FunctionError {
+                name,
+                error,
+                call: None,
+            } => InterpreterError::FunctionError {
+                name,
+                error,
+                call: Some(span),
+            },
+            _ if self.is_located() => self,
+            _ => InterpreterError::Located {
+                error: Box::new(self),
+                span,
+            },
+        }
+    }
+
+    fn is_located(&self) -> bool {
+        match self {
+            InterpreterError::Located { .. } => true,
+            InterpreterError::ConditionError(error) => error.is_located(),
+            InterpreterError::FunctionError { error, call, .. } => {
+                call.is_some() || error.is_located()
+            }
+            _ => false,
+        }
+    }
+}
 
-type ErrorMessage = String;
+impl fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InterpreterError::DivisionByZero => write!(f, "Division by zero"),
+            InterpreterError::IntegerOverflow => write!(f, "Integer overflow"),
+            InterpreterError::UndefinedVariable(name) => write!(f, "Variable {} not found", name),
+            InterpreterError::UndefinedFunction(name) => write!(f, "{} is not defined", name),
+            InterpreterError::TypeError(message) => write!(f, "{}", message),
+            InterpreterError::ValueError(message) => write!(f, "{}", message),
+            InterpreterError::SyntaxError(message) => write!(f, "{}", message),
+            InterpreterError::IndexError(message) => write!(f, "{}", message),
+            InterpreterError::KeyError(key) => write!(f, "KeyError: {}", key),
+            InterpreterError::ArityMismatch {
+                name,
+                expected,
+                got,
+            } => write!(f, "{} requires {} arguments, got {}", name, expected, got),
+            InterpreterError::ConditionError(error) => {
+                write!(f, "Condition resulted in an error: {}", error)
+            }
+            /* A runaway recursion nests the same call once per level, so
+             * a run of identical calls is written once, with a count. */
+            InterpreterError::FunctionError { name, error, call } => {
+                let mut error = error;
+                let mut repeats = 0;
+                while let InterpreterError::FunctionError {
+                    name: inner_name,
+                    error: inner_error,
+                    call: inner_call,
+                } = error.as_ref()
+                {
+                    if inner_name != name || inner_call != call {
+                        break;
+                    }
+                    repeats += 1;
+                    error = inner_error;
+                }
+                match call {
+                    Some(span) => write!(
+                        f,
+                        "{} called at line {}, col {}",
+                        name, span.line, span.column
+                    )?,
+                    None => write!(f, "{}", name)?,
+                }
+                if repeats > 0 {
+                    write!(f, " [repeated {} more times]", repeats)?;
+                }
+                write!(f, " generated an error: {}", error)
+            }
+            InterpreterError::Located { error, span } => {
+                write!(f, "{} at line {}, col {}", error, span.line, span.column)
+            }
+            InterpreterError::AssertionError(None) => write!(f, "AssertionError"),
+            InterpreterError::AssertionError(Some(message)) => {
+                write!(f, "AssertionError: {}", message)
+            }
+            InterpreterError::RecursionLimitExceeded => {
+                write!(f, "maximum recursion depth exceeded")
+            }
+            InterpreterError::StepLimitExceeded => write!(f, "execution step limit exceeded"),
+            InterpreterError::NotImplemented => write!(f, "not implemented yet"),
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum EnvValue {
     CInt(i32),
     CReal(f32),
     Bool(bool),
+    CString(String),
     List(Vec<EvalResult>),
-    Func(
-        Box<EvalResult>,
-        Option<HashMap<Name, Box<EvalResult>>>,
-        Option<Box<Statement>>,
-        Box<Expression>,
-    ),
+    Tuple(Vec<EvalResult>),
+    Dict(Box<Dict>),
+    Set(Box<Set>),
+    Func(Rc<Function>),
     None,
 }
 
@@ -26,645 +180,2507 @@ pub enum EvalResult {
     CInt(i32),
     CReal(f32),
     Bool(bool),
+    CString(String),
     List(Vec<EvalResult>),
+    Tuple(Vec<EvalResult>),
+    /* Boxed, like Set, so that values stay as small as a list. */
+    Dict(Box<Dict>),
+    Set(Box<Set>),
+    Func(Rc<Function>),
     None,
 }
 
-type Environment = HashMap<Name, EnvValue>;
+/* A function value: its definition together with the environment it was
+ * defined in. The environment shares its scopes with the definition site,
+ * so the body sees later updates to the variables it captured, and
+ * updates made through 'nonlocal' are seen by every other holder of those
+ * scopes. Functions compare by identity, like in Python.
+ *
+ * A memoized function also carries the results of its past calls, keyed
+ * on their arguments. */
+pub struct Function {
+    pub name: Name,
+    pub kind: Type,
+    pub params: Vec<(Name, Type)>,
+    pub body: Option<Box<Statement>>,
+    pub retrn: Box<Expression>,
+    pub env: Environment,
+    pub memo: Option<RefCell<HashMap<Vec<HashKey>, EvalResult>>>,
+}
 
-pub fn eval(exp: &Expression, env: &Environment) -> Result<EvalResult, ErrorMessage> {
-    match exp {
-        Expression::CInt(v) => Ok(EvalResult::CInt(*v)),
-        Expression::CReal(v) => Ok(EvalResult::CReal(*v)),
-        Expression::Bool(v) => Ok(EvalResult::Bool(*v)),
-        Expression::None => Ok(EvalResult::None),
-        Expression::List(items) => {
-            let mut list_vec: Vec<EvalResult> = Vec::new();
-            let list_env = env.clone();
-
-            if items.len() < 1 {
-                return Err(String::from(
-                    "List initialization must have at least one element",
-                ));
-            } else {
-                let first_item = eval(&items[0], &list_env)?;
-                for item in items {
-                    let value = eval(&item, &list_env)?;
-                    match (&first_item, &value) {
-                        (EvalResult::CInt(_), EvalResult::CInt(_)) => list_vec.push(value),
-                        (EvalResult::CReal(_), EvalResult::CReal(_)) => list_vec.push(value),
-                        (EvalResult::Bool(_), EvalResult::Bool(_)) => list_vec.push(value),
-                        (EvalResult::List(_), EvalResult::List(_)) => list_vec.push(value),
-                        _ => return Err(String::from("List must be homogeneous")),
-                    }
-                }
-            }
-            Ok(EvalResult::List(list_vec))
-        }
-        Expression::Add(lhs, rhs) => {
-            let lhs_value = eval(lhs, env)?;
-            let rhs_value = eval(rhs, env)?;
-            match (lhs_value, rhs_value) {
-                (EvalResult::CInt(lhs), EvalResult::CInt(rhs)) => Ok(EvalResult::CInt(lhs + rhs)),
-                (EvalResult::CReal(lhs), EvalResult::CReal(rhs)) => {
-                    Ok(EvalResult::CReal(lhs + rhs))
-                }
-                (EvalResult::CInt(lhs), EvalResult::CReal(rhs)) => {
-                    Ok(EvalResult::CReal(lhs as f32 + rhs))
-                }
-                (EvalResult::CReal(lhs), EvalResult::CInt(rhs)) => {
-                    Ok(EvalResult::CReal(lhs + rhs as f32))
-                }
-                (EvalResult::CInt(lhs), EvalResult::Bool(rhs)) => {
-                    Ok(EvalResult::CInt(lhs + rhs as i32))
-                }
-                (EvalResult::CReal(lhs), EvalResult::Bool(rhs)) => {
-                    Ok(EvalResult::CReal(lhs + (rhs as i32) as f32))
-                }
-                (EvalResult::Bool(lhs), EvalResult::CInt(rhs)) => {
-                    Ok(EvalResult::CInt(lhs as i32 + rhs))
-                }
-                (EvalResult::Bool(lhs), EvalResult::CReal(rhs)) => {
-                    Ok(EvalResult::CReal((lhs as i32) as f32 + rhs))
-                }
-                (EvalResult::Bool(lhs), EvalResult::Bool(rhs)) => {
-                    Ok(EvalResult::CInt(lhs as i32 + rhs as i32))
-                }
-                (EvalResult::List(lhs), EvalResult::List(rhs)) => {
-                    let mut result_list = lhs.clone();
-                    result_list.extend(rhs);
-                    Ok(EvalResult::List(result_list))
-                }
-                (EvalResult::List(_), _) => Err(String::from("Can only concatenate list to list")),
-                (_, EvalResult::List(_)) => Err(String::from("Can only concatenate list to list")),
-                (EvalResult::None, _) => Err(String::from("Add is not supported for 'None'")),
-                (_, EvalResult::None) => Err(String::from("Add is not supported for 'None'")),
-            }
-        }
-        Expression::Sub(lhs, rhs) => {
-            let lhs_value = eval(lhs, env)?;
-            let rhs_value = eval(rhs, env)?;
-            match (lhs_value, rhs_value) {
-                (EvalResult::CInt(lhs), EvalResult::CInt(rhs)) => Ok(EvalResult::CInt(lhs - rhs)),
-                (EvalResult::CReal(lhs), EvalResult::CReal(rhs)) => {
-                    Ok(EvalResult::CReal(lhs - rhs))
-                }
-                (EvalResult::CInt(lhs), EvalResult::CReal(rhs)) => {
-                    Ok(EvalResult::CReal(lhs as f32 - rhs))
-                }
-                (EvalResult::CReal(lhs), EvalResult::CInt(rhs)) => {
-                    Ok(EvalResult::CReal(lhs - rhs as f32))
-                }
-                (EvalResult::CInt(lhs), EvalResult::Bool(rhs)) => {
-                    Ok(EvalResult::CInt(lhs - rhs as i32))
-                }
-                (EvalResult::CReal(lhs), EvalResult::Bool(rhs)) => {
-                    Ok(EvalResult::CReal(lhs - (rhs as i32) as f32))
-                }
-                (EvalResult::Bool(lhs), EvalResult::CInt(rhs)) => {
-                    Ok(EvalResult::CInt(lhs as i32 - rhs))
-                }
-                (EvalResult::Bool(lhs), EvalResult::CReal(rhs)) => {
-                    Ok(EvalResult::CReal((lhs as i32) as f32 - rhs))
-                }
-                (EvalResult::Bool(lhs), EvalResult::Bool(rhs)) => {
-                    Ok(EvalResult::CInt(lhs as i32 - rhs as i32))
-                }
-                (EvalResult::List(_), _) => Err(String::from("Sub not supported for list")),
-                (_, EvalResult::List(_)) => Err(String::from("Sub not supported for list")),
-                (EvalResult::None, _) => Err(String::from("Sub is not supported for 'None'")),
-                (_, EvalResult::None) => Err(String::from("Sub is not supported for 'None'")),
-            }
-        }
-        Expression::Mul(lhs, rhs) => {
-            let lhs_value = eval(lhs, env)?;
-            let rhs_value = eval(rhs, env)?;
-            match (lhs_value, rhs_value) {
-                (EvalResult::CInt(lhs), EvalResult::CInt(rhs)) => Ok(EvalResult::CInt(lhs * rhs)),
-                (EvalResult::CReal(lhs), EvalResult::CReal(rhs)) => {
-                    Ok(EvalResult::CReal(lhs * rhs))
-                }
-                (EvalResult::CInt(lhs), EvalResult::CReal(rhs)) => {
-                    Ok(EvalResult::CReal(lhs as f32 * rhs))
-                }
-                (EvalResult::CReal(lhs), EvalResult::CInt(rhs)) => {
-                    Ok(EvalResult::CReal(lhs * rhs as f32))
-                }
-                (EvalResult::CInt(lhs), EvalResult::Bool(rhs)) => {
-                    Ok(EvalResult::CInt(lhs * rhs as i32))
-                }
-                (EvalResult::CReal(lhs), EvalResult::Bool(rhs)) => {
-                    Ok(EvalResult::CReal(lhs * (rhs as i32) as f32))
-                }
-                (EvalResult::Bool(lhs), EvalResult::CInt(rhs)) => {
-                    Ok(EvalResult::CInt(lhs as i32 * rhs))
-                }
-                (EvalResult::Bool(lhs), EvalResult::CReal(rhs)) => {
-                    Ok(EvalResult::CReal((lhs as i32) as f32 * rhs))
-                }
-                (EvalResult::Bool(lhs), EvalResult::Bool(rhs)) => {
-                    Ok(EvalResult::CInt(lhs as i32 * rhs as i32))
-                }
-                (EvalResult::List(lhs), EvalResult::CInt(rhs)) => {
-                    let mut result_list = Vec::new();
-                    for _i in 0..rhs {
-                        result_list.extend(lhs.clone());
-                    }
-                    Ok(EvalResult::List(result_list))
-                }
-                (EvalResult::CInt(lhs), EvalResult::List(rhs)) => {
-                    let mut result_list = Vec::new();
-                    for _i in 0..lhs {
-                        result_list.extend(rhs.clone());
-                    }
-                    Ok(EvalResult::List(result_list))
-                }
-                (EvalResult::List(lhs), EvalResult::Bool(rhs)) => {
-                    let mut result_list = Vec::new();
-                    for _i in 0..rhs as i32 {
-                        result_list.extend(lhs.clone());
-                    }
-                    Ok(EvalResult::List(result_list))
-                }
-                (EvalResult::Bool(lhs), EvalResult::List(rhs)) => {
-                    let mut result_list = Vec::new();
-                    for _i in 0..lhs as i32 {
-                        result_list.extend(rhs.clone());
-                    }
-                    Ok(EvalResult::List(result_list))
-                }
-                (EvalResult::List(_), _) => {
-                    Err(String::from("Cannot multiply list by non-integer value"))
-                }
-                (_, EvalResult::List(_)) => {
-                    Err(String::from("Cannot multiply list by non-integer value"))
-                }
-                (EvalResult::None, _) => Err(String::from("Mul is not supported for 'None'")),
-                (_, EvalResult::None) => Err(String::from("Mul is not supported for 'None'")),
-            }
+impl Function {
+    /* A copy of the function with an empty cache of results. */
+    pub fn memoized(&self) -> Function {
+        Function {
+            name: self.name.clone(),
+            kind: self.kind.clone(),
+            params: self.params.clone(),
+            body: self.body.clone(),
+            retrn: self.retrn.clone(),
+            env: self.env.clone(),
+            memo: Some(RefCell::new(HashMap::new())),
         }
-        Expression::Div(lhs, rhs) => {
-            let lhs_value = eval(lhs, env)?;
-            let rhs_value = eval(rhs, env)?;
-            match (lhs_value, rhs_value) {
-                (EvalResult::CInt(lhs), EvalResult::CInt(rhs)) => match rhs {
-                    0 => Err(String::from("Division by zero")),
-                    _ => Ok(EvalResult::CInt(lhs / rhs)),
-                },
-                (EvalResult::CReal(lhs), EvalResult::CReal(rhs)) => match rhs {
-                    0.0 => Err(String::from("Division by zero")),
-                    _ => Ok(EvalResult::CReal(lhs / rhs)),
-                },
-                (EvalResult::CInt(lhs), EvalResult::CReal(rhs)) => match rhs {
-                    0.0 => Err(String::from("Division by zero")),
-                    _ => Ok(EvalResult::CReal(lhs as f32 / rhs)),
-                },
-                (EvalResult::CReal(lhs), EvalResult::CInt(rhs)) => match rhs {
-                    0 => Err(String::from("Division by zero")),
-                    _ => Ok(EvalResult::CReal(lhs / rhs as f32)),
-                },
-                (EvalResult::CInt(lhs), EvalResult::Bool(rhs)) => match rhs {
-                    false => Err(String::from("Division by zero")),
-                    _ => Ok(EvalResult::CInt(lhs / rhs as i32)),
-                },
-                (EvalResult::CReal(lhs), EvalResult::Bool(rhs)) => match rhs {
-                    false => Err(String::from("Division by zero")),
-                    _ => Ok(EvalResult::CReal(lhs / (rhs as i32) as f32)),
-                },
-                (EvalResult::Bool(lhs), EvalResult::CInt(rhs)) => match rhs {
-                    0 => Err(String::from("Division by zero")),
-                    _ => Ok(EvalResult::CInt(lhs as i32 / rhs)),
-                },
-                (EvalResult::Bool(lhs), EvalResult::CReal(rhs)) => match rhs {
-                    0.0 => Err(String::from("Division by zero")),
-                    _ => Ok(EvalResult::CReal((lhs as i32) as f32 / rhs)),
-                },
-                (EvalResult::Bool(lhs), EvalResult::Bool(rhs)) => match rhs {
-                    false => Err(String::from("Division by zero")),
-                    _ => Ok(EvalResult::CInt(lhs as i32 / rhs as i32)),
-                },
-                (EvalResult::List(_), _) => Err(String::from("Div not supported for list")),
-                (_, EvalResult::List(_)) => Err(String::from("Div not supported for list")),
-                (EvalResult::None, _) => Err(String::from("Div is not supported for 'None'")),
-                (_, EvalResult::None) => Err(String::from("Div is not supported for 'None'")),
-            }
-        }
-        Expression::Var(name) => match env.get(name) {
-            Some(EnvValue::CInt(value)) => Ok(EvalResult::CInt(*value)),
-            Some(EnvValue::CReal(value)) => Ok(EvalResult::CReal(*value)),
-            Some(EnvValue::Bool(value)) => Ok(EvalResult::Bool(*value)),
-            Some(EnvValue::List(value)) => Ok(EvalResult::List(value.clone())),
-            Some(EnvValue::None) => Ok(EvalResult::None),
-            _ => Err(format!("Variable {} not found", name)),
-        },
-        Expression::FuncCall(name, args) => match env.get(name) {
-            Some(EnvValue::Func(kind, params, stmt, retrn)) => {
-                let mut func_env = env.clone();
-
-                let new_params: HashMap<String, Box<EvalResult>> = match params {
-                    None => HashMap::new(),
-                    Some(s) => s.clone(),
-                };
-
-                let new_args: Vec<Expression> = match args {
-                    None => Vec::new(),
-                    Some(s) => s.clone(),
-                };
-
-                if new_args.len() != new_params.len() {
-                    return Err(format!(
-                        "{} requires {} arguments, got {}",
-                        name,
-                        new_params.len(),
-                        new_args.len()
-                    ));
-                }
-
-                for (param, arg) in new_params.iter().zip(new_args.iter()) {
-                    let value = eval(arg, env)?;
-
-                    match (*param.1.clone(), value) {
-                        (EvalResult::CInt(_), EvalResult::CInt(v)) => {
-                            func_env.insert(param.0.clone(), EnvValue::CInt(v));
-                        }
-                        (EvalResult::CReal(_), EvalResult::CReal(v)) => {
-                            func_env.insert(param.0.clone(), EnvValue::CReal(v));
-                        }
-                        (EvalResult::Bool(_), EvalResult::Bool(v)) => {
-                            func_env.insert(param.0.clone(), EnvValue::Bool(v));
-                        }
-                        (EvalResult::List(_), EvalResult::List(v)) => {
-                            func_env.insert(param.0.clone(), EnvValue::List(v));
-                        }
-                        _ => return Err(format!("Mismatched types for {:?}", param.1)),
-                    }
-                }
-
-                if let Some(body_stmt) = stmt {
-                    match execute(body_stmt, func_env.clone()) {
-                        Ok(result_env) => {
-                            let result = eval(&retrn, &result_env)?;
-                            let kind_type = *kind.clone();
-                            match (kind_type, result) {
-                                (EvalResult::CInt(_), EvalResult::CInt(v)) => {
-                                    Ok(EvalResult::CInt(v))
-                                }
-                                (EvalResult::CReal(_), EvalResult::CReal(v)) => {
-                                    Ok(EvalResult::CReal(v))
-                                }
-                                (EvalResult::Bool(_), EvalResult::Bool(v)) => {
-                                    Ok(EvalResult::Bool(v))
-                                }
-                                (EvalResult::List(_), EvalResult::List(v)) => {
-                                    Ok(EvalResult::List(v))
-                                }
-                                (EvalResult::None, EvalResult::None) => Ok(EvalResult::None),
-                                _ => Err(format!(
-                                    "{} returned a value different from specified type",
-                                    name
-                                )),
-                            }
-                        }
-                        Err(err) => Err(format!("{} generated an error: {}", name, err)),
-                    }
-                } else {
-                    let result = eval(&retrn, &func_env)?;
-                    let kind_type = *kind.clone();
-                    match (kind_type, result) {
-                        (EvalResult::CInt(_), EvalResult::CInt(v)) => Ok(EvalResult::CInt(v)),
-                        (EvalResult::CReal(_), EvalResult::CReal(v)) => Ok(EvalResult::CReal(v)),
-                        (EvalResult::Bool(_), EvalResult::Bool(v)) => Ok(EvalResult::Bool(v)),
-                        (EvalResult::List(_), EvalResult::List(v)) => Ok(EvalResult::List(v)),
-                        (EvalResult::None, EvalResult::None) => Ok(EvalResult::None),
-                        _ => Err(format!(
-                            "{} returned a value different from specified type",
-                            name
-                        )),
-                    }
-                }
-            }
-            _ => Err(format!("{} is not defined", name)),
-        },
-        Expression::Range(exp1, exp2, exp3) => {
-            let new_env = env.clone();
-            let end_value = eval(exp2, &new_env)?;
+    }
+}
 
-            let mut srt_value = eval(&Expression::CInt(0), &new_env)?;
-            let mut incr_value = eval(&Expression::CInt(1), &new_env)?;
+/* The values a dict accepts as keys. Like in Python, only immutable
+ * values are hashable, and reals are left out as well since they have no
+ * exact equality. True and False are the same key as 1 and 0, and a dict
+ * or set holding one keeps the key it was first given. */
+#[derive(Debug, Clone)]
+pub enum HashKey {
+    CInt(i32),
+    Bool(bool),
+    CString(String),
+}
 
-            match (exp1, exp3) {
-                (None, None) => (),
-                (None, Some(incr_stp)) => {
-                    incr_value = eval(&incr_stp, &new_env)?;
-                }
-                (Some(srt_step), None) => {
-                    srt_value = eval(&srt_step, &new_env)?;
-                }
-                (Some(srt_step), Some(incr_step)) => {
-                    srt_value = eval(&srt_step, &new_env)?;
-                    incr_value = eval(&incr_step, &new_env)?;
-                }
-            }
+impl PartialEq for HashKey {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (HashKey::CString(lhs), HashKey::CString(rhs)) => lhs == rhs,
+            (lhs, rhs) => lhs.as_int().is_some() && lhs.as_int() == rhs.as_int(),
+        }
+    }
+}
 
-            let srt_int: i32;
-            let end_int: i32;
-            let incr_int: i32;
+impl Eq for HashKey {}
 
-            match (srt_value, end_value, incr_value) {
-                (EvalResult::CInt(i), EvalResult::CInt(j), EvalResult::CInt(k)) => {
-                    srt_int = i;
-                    end_int = j;
-                    incr_int = k;
-                }
-                (EvalResult::CInt(i), EvalResult::CInt(j), EvalResult::Bool(k)) => {
-                    srt_int = i;
-                    end_int = j;
-                    incr_int = k as i32;
-                }
-                (EvalResult::CInt(i), EvalResult::Bool(j), EvalResult::CInt(k)) => {
-                    srt_int = i;
-                    end_int = j as i32;
-                    incr_int = k;
-                }
-                (EvalResult::CInt(i), EvalResult::Bool(j), EvalResult::Bool(k)) => {
-                    srt_int = i;
-                    end_int = j as i32;
-                    incr_int = k as i32;
-                }
-                (EvalResult::Bool(i), EvalResult::CInt(j), EvalResult::CInt(k)) => {
-                    srt_int = i as i32;
-                    end_int = j;
-                    incr_int = k;
-                }
-                (EvalResult::Bool(i), EvalResult::CInt(j), EvalResult::Bool(k)) => {
-                    srt_int = i as i32;
-                    end_int = j;
-                    incr_int = k as i32;
-                }
-                (EvalResult::Bool(i), EvalResult::Bool(j), EvalResult::CInt(k)) => {
-                    srt_int = i as i32;
-                    end_int = j as i32;
-                    incr_int = k;
-                }
-                (EvalResult::Bool(i), EvalResult::Bool(j), EvalResult::Bool(k)) => {
-                    srt_int = i as i32;
-                    end_int = j as i32;
-                    incr_int = k as i32;
-                }
-                _ => return Err(String::from("Parameters cannot be converted to integer")),
-            }
+impl Hash for HashKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            HashKey::CString(v) => v.hash(state),
+            key => key.as_int().hash(state),
+        }
+    }
+}
 
-            let mut range_vec: Vec<EvalResult> = Vec::new();
+impl HashKey {
+    fn as_int(&self) -> Option<i32> {
+        match self {
+            HashKey::CInt(v) => Some(*v),
+            HashKey::Bool(v) => Some(*v as i32),
+            HashKey::CString(_) => None,
+        }
+    }
 
-            match incr_int.signum() {
-                0 => Err(String::from("Increment cannot be zero")),
-                -1 => {
-                    for i in (end_int + incr_int.abs()..=srt_int)
-                        .rev()
-                        .step_by(incr_int.abs() as usize)
-                    {
-                        range_vec.push(EvalResult::CInt(i))
-                    }
-                    Ok(EvalResult::List(range_vec))
-                }
-                1 => {
-                    for i in (srt_int..end_int).step_by(incr_int as usize) {
-                        range_vec.push(EvalResult::CInt(i));
-                    }
-                    Ok(EvalResult::List(range_vec))
-                }
-                _ => Ok(EvalResult::List(range_vec)),
-            }
+    pub fn from_value(value: &EvalResult) -> Result<HashKey, InterpreterError> {
+        match value {
+            EvalResult::CInt(v) => Ok(HashKey::CInt(*v)),
+            EvalResult::Bool(v) => Ok(HashKey::Bool(*v)),
+            EvalResult::CString(v) => Ok(HashKey::CString(v.clone())),
+            _ => Err(InterpreterError::TypeError(format!(
+                "unhashable type: '{}'",
+                value.type_name()
+            ))),
         }
     }
 }
 
-pub fn execute(stmt: &Statement, env: Environment) -> Result<Environment, ErrorMessage> {
-    match stmt {
-        Statement::Assignment(name, exp) => {
-            let value = eval(exp, &env)?;
-            let mut new_env = env;
-            match value {
-                EvalResult::CInt(val) => {
-                    new_env.insert(*name.clone(), EnvValue::CInt(val));
-                }
-                EvalResult::CReal(val) => {
-                    new_env.insert(*name.clone(), EnvValue::CReal(val));
-                }
-                EvalResult::Bool(val) => {
-                    new_env.insert(*name.clone(), EnvValue::Bool(val));
-                }
-                EvalResult::List(val) => {
-                    new_env.insert(*name.clone(), EnvValue::List(val));
-                }
-                EvalResult::None => {
-                    new_env.insert(*name.clone(), EnvValue::None);
-                }
-            }
-            Ok(new_env)
+impl From<HashKey> for EvalResult {
+    fn from(key: HashKey) -> Self {
+        match key {
+            HashKey::CInt(v) => EvalResult::CInt(v),
+            HashKey::Bool(v) => EvalResult::Bool(v),
+            HashKey::CString(v) => EvalResult::CString(v),
         }
-        Statement::IfThenElse(cond, stmt_then, stmt_else) => {
-            let value = match eval(cond, &env) {
-                Ok(EvalResult::CInt(v)) => v != 0,
-                Ok(EvalResult::CReal(v)) => v != 0.0,
-                Ok(EvalResult::Bool(v)) => v,
-                Ok(EvalResult::List(v)) => !v.is_empty(),
-                Ok(EvalResult::None) => false,
-                Err(s) => return Err(format!("Condition resulted in an error: {}", s)),
-            };
+    }
+}
 
-            if value {
-                execute(stmt_then, env)
-            } else {
-                execute(stmt_else, env)
-            }
-        }
-        Statement::While(cond, stmt) => {
-            let mut new_env = env.clone();
-            loop {
-                let value = match eval(cond, &new_env) {
-                    Ok(EvalResult::CInt(v)) => v != 0,
-                    Ok(EvalResult::CReal(v)) => v != 0.0,
-                    Ok(EvalResult::Bool(v)) => v,
-                    Ok(EvalResult::List(v)) => !v.is_empty(),
-                    Ok(EvalResult::None) => false,
-                    Err(s) => return Err(format!("Condition resulted in an error: {}", s)),
-                };
+/* A dict remembers the order its keys were first inserted in, as Python's
+ * does, and indexes them so lookups do not scan the entries. Two dicts are
+ * equal when they hold the same entries, whatever their order. */
+#[derive(Debug, Clone, Default)]
+pub struct Dict {
+    entries: Vec<(HashKey, EvalResult)>,
+    index: HashMap<HashKey, usize>,
+}
 
-                if value {
-                    new_env = execute(stmt, new_env)?;
-                } else {
-                    break;
-                }
-            }
-            Ok(new_env)
-        }
-        Statement::Func(name, kind, params, stmt, retrn) => {
-            let mut new_env = env.clone();
+impl Dict {
+    pub fn new() -> Dict {
+        Dict::default()
+    }
 
-            new_env.insert(
-                *name.clone(),
-                EnvValue::Func(kind.clone(), params.clone(), stmt.clone(), retrn.clone()),
-            );
-            Ok(new_env)
-        }
-        Statement::For(var, exp, stmt) => {
-            let mut new_env = env;
-            let exp_value = eval(&exp, &new_env)?;
-            match exp_value {
-                EvalResult::List(vec) => {
-                    for item in vec {
-                        match item {
-                            EvalResult::CInt(v) => {
-                                new_env.insert(*var.clone(), EnvValue::CInt(v));
-                            }
-                            EvalResult::CReal(v) => {
-                                new_env.insert(*var.clone(), EnvValue::CReal(v));
-                            }
-                            EvalResult::Bool(v) => {
-                                new_env.insert(*var.clone(), EnvValue::Bool(v));
-                            }
-                            EvalResult::List(v) => {
-                                new_env.insert(*var.clone(), EnvValue::List(v));
-                            }
-                            EvalResult::None => {
-                                new_env.insert(*var.clone(), EnvValue::None);
-                            }
-                        }
-                        new_env = execute(stmt, new_env)?;
-                    }
-                }
-                _ => return Err(String::from("Expression must be an iterable object")),
+    pub fn insert(&mut self, key: HashKey, value: EvalResult) {
+        match self.index.get(&key) {
+            Some(&position) => self.entries[position].1 = value,
+            None => {
+                self.index.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
             }
-            new_env.remove(&var as &str);
-            Ok(new_env)
         }
-        Statement::Sequence(s1, s2) => execute(s1, env).and_then(|new_env| execute(s2, new_env)),
-        _ => Err(String::from("not implemented yet")),
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    pub fn get(&self, key: &HashKey) -> Option<&EvalResult> {
+        self.index
+            .get(key)
+            .map(|&position| &self.entries[position].1)
+    }
 
-    #[test]
-    fn eval_constant_integer() {
-        let env = HashMap::new();
-        let c10 = Expression::CInt(10);
-        let c20 = Expression::CInt(20);
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
 
-        assert_eq!(eval(&c10, &env), Ok(EvalResult::CInt(10)));
-        assert_eq!(eval(&c20, &env), Ok(EvalResult::CInt(20)));
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
     }
 
-    #[test]
-    fn eval_constant_real() {
-        let env = HashMap::new();
-        let c10_5 = Expression::CReal(10.5);
-        let c20_3 = Expression::CReal(20.3);
+    pub fn iter(&self) -> impl Iterator<Item = (&HashKey, &EvalResult)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
 
-        assert_eq!(eval(&c10_5, &env), Ok(EvalResult::CReal(10.5)));
-        assert_eq!(eval(&c20_3, &env), Ok(EvalResult::CReal(20.3)))
+impl PartialEq for Dict {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
     }
+}
 
-    #[test]
-    fn eval_constant_bool() {
-        let env = HashMap::new();
-        let ctrue = Expression::Bool(true);
-        let cfalse = Expression::Bool(false);
+/* Sets hold hashable values only and, for a stable rendering, keep the
+ * order their items were first added in. */
+#[derive(Debug, Clone, Default)]
+pub struct Set {
+    items: Vec<HashKey>,
+    index: HashSet<HashKey>,
+}
 
-        assert_eq!(eval(&ctrue, &env), Ok(EvalResult::Bool(true)));
-        assert_eq!(eval(&cfalse, &env), Ok(EvalResult::Bool(false)))
+impl Set {
+    pub fn new() -> Set {
+        Set::default()
     }
 
-    #[test]
-    fn eval_constant_list() {
-        let env = HashMap::new();
-        let cl1 = Expression::List(vec![Expression::CInt(1), Expression::CInt(2)]);
-        let cl2 = Expression::List(vec![Expression::CReal(23.3), Expression::CReal(0.00)]);
+    pub fn insert(&mut self, item: HashKey) {
+        if self.index.insert(item.clone()) {
+            self.items.push(item);
+        }
+    }
 
-        assert_eq!(
-            eval(&cl1, &env),
-            Ok(EvalResult::List(vec![
-                EvalResult::CInt(1),
-                EvalResult::CInt(2)
-            ]))
-        );
-        assert_eq!(
-            eval(&cl2, &env),
-            Ok(EvalResult::List(vec![
-                EvalResult::CReal(23.3),
-                EvalResult::CReal(0.00)
-            ]))
-        );
+    pub fn contains(&self, item: &HashKey) -> bool {
+        self.index.contains(item)
     }
 
-    #[test]
-    fn eval_list_of_list() {
-        let env = HashMap::new();
-        let cl1 = Expression::List(vec![Expression::List(vec![Expression::CInt(1)])]);
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
 
-        assert_eq!(
-            eval(&cl1, &env),
-            Ok(EvalResult::List(vec![EvalResult::List(vec![
-                EvalResult::CInt(1)
-            ])]))
-        );
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
     }
 
-    #[test]
-    fn eval_add_integers_1() {
-        let env = HashMap::new();
-        let c10 = Expression::CInt(10);
-        let c20 = Expression::CInt(20);
-        let add1 = Expression::Add(Box::new(c10), Box::new(c20));
-        assert_eq!(eval(&add1, &env), Ok(EvalResult::CInt(30)));
+    pub fn iter(&self) -> impl Iterator<Item = &HashKey> {
+        self.items.iter()
     }
+}
 
-    #[test]
-    fn eval_add_integers_2() {
-        let env = HashMap::new();
-        let c10 = Expression::CInt(10);
-        let c20 = Expression::CInt(20);
-        let c30 = Expression::CInt(30);
-        let add1 = Expression::Add(Box::new(c10), Box::new(c20));
-        let add2 = Expression::Add(Box::new(add1), Box::new(c30));
-        assert_eq!(eval(&add2, &env), Ok(EvalResult::CInt(60)));
+impl PartialEq for Set {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|item| other.contains(item))
     }
+}
 
-    #[test]
-    fn eval_add_reals_1() {
-        let env = HashMap::new();
-        let c10_5 = Expression::CReal(10.5);
-        let c20_3 = Expression::CReal(20.3);
-        let add1 = Expression::Add(Box::new(c10_5), Box::new(c20_3));
-        assert_eq!(eval(&add1, &env), Ok(EvalResult::CReal(30.8)));
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Function({})", self.name)
     }
+}
 
-    #[test]
-    fn eval_add_reals_2() {
-        let env = HashMap::new();
-        let c10_5 = Expression::CReal(10.5);
-        let c20_3 = Expression::CReal(20.3);
-        let c30_1 = Expression::CReal(30.1);
-        let add1 = Expression::Add(Box::new(c10_5), Box::new(c20_3));
-        let add2 = Expression::Add(Box::new(add1), Box::new(c30_1));
-        assert_eq!(eval(&add2, &env), Ok(EvalResult::CReal(60.9)));
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
     }
+}
 
-    #[test]
-    fn eval_add_integer_real() {
-        let env = HashMap::new();
-        let c10 = Expression::CInt(10);
-        let c20_3 = Expression::CReal(20.3);
-        let add1 = Expression::Add(Box::new(c10), Box::new(c20_3));
-        assert_eq!(eval(&add1, &env), Ok(EvalResult::CReal(30.3)));
+impl From<EvalResult> for EnvValue {
+    fn from(value: EvalResult) -> Self {
+        match value {
+            EvalResult::CInt(v) => EnvValue::CInt(v),
+            EvalResult::CReal(v) => EnvValue::CReal(v),
+            EvalResult::Bool(v) => EnvValue::Bool(v),
+            EvalResult::CString(v) => EnvValue::CString(v),
+            EvalResult::List(v) => EnvValue::List(v),
+            EvalResult::Tuple(v) => EnvValue::Tuple(v),
+            EvalResult::Dict(v) => EnvValue::Dict(v),
+            EvalResult::Set(v) => EnvValue::Set(v),
+            EvalResult::Func(v) => EnvValue::Func(v),
+            EvalResult::None => EnvValue::None,
+        }
     }
+}
 
-    #[test]
+impl EvalResult {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            EvalResult::CInt(_) => "int",
+            EvalResult::CReal(_) => "real",
+            EvalResult::Bool(_) => "bool",
+            EvalResult::CString(_) => "str",
+            EvalResult::List(_) => "list",
+            EvalResult::Tuple(_) => "tuple",
+            EvalResult::Dict(_) => "dict",
+            EvalResult::Set(_) => "set",
+            EvalResult::Func(_) => "function",
+            EvalResult::None => "NoneType",
+        }
+    }
+
+    /* Renders the value the way it appears inside a container, with
+     * strings quoted, as opposed to the plain Display rendering. */
+    pub fn repr(&self) -> String {
+        match self {
+            EvalResult::CString(v) => format!("'{}'", v),
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for EvalResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalResult::CInt(v) => write!(f, "{}", v),
+            EvalResult::CReal(v) => write!(f, "{}", format_real(*v)),
+            EvalResult::Bool(true) => write!(f, "True"),
+            EvalResult::Bool(false) => write!(f, "False"),
+            EvalResult::CString(v) => write!(f, "{}", v),
+            EvalResult::List(items) => {
+                let items: Vec<String> = items.iter().map(|item| item.repr()).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+            EvalResult::Tuple(items) => match items.as_slice() {
+                [item] => write!(f, "({},)", item.repr()),
+                _ => {
+                    let items: Vec<String> = items.iter().map(|item| item.repr()).collect();
+                    write!(f, "({})", items.join(", "))
+                }
+            },
+            EvalResult::Dict(dict) => {
+                let entries: Vec<String> = dict
+                    .iter()
+                    .map(|(key, value)| {
+                        format!("{}: {}", EvalResult::from(key.clone()).repr(), value.repr())
+                    })
+                    .collect();
+                write!(f, "{{{}}}", entries.join(", "))
+            }
+            /* '{}' is an empty dict, so the empty set is spelled out. */
+            EvalResult::Set(set) if set.is_empty() => write!(f, "set()"),
+            EvalResult::Set(set) => {
+                let items: Vec<String> = set
+                    .iter()
+                    .map(|item| EvalResult::from(item.clone()).repr())
+                    .collect();
+                write!(f, "{{{}}}", items.join(", "))
+            }
+            EvalResult::Func(func) => write!(f, "<function {}>", func.name),
+            EvalResult::None => write!(f, "None"),
+        }
+    }
+}
+
+/* Renders a real the way Python does: a whole number keeps its '.0' so
+ * that 3.0 and 3 look different, other values use the shortest digits
+ * that read back as the same number, and very large or small ones switch
+ * to an exponent written with a sign and at least two digits (1e+20). */
+fn format_real(v: f32) -> String {
+    if v.is_nan() {
+        return String::from("nan");
+    }
+    if v.is_infinite() {
+        return String::from(if v > 0.0 { "inf" } else { "-inf" });
+    }
+    let repr = format!("{:?}", v);
+    match repr.split_once('e') {
+        Some((mantissa, exp)) => match exp.strip_prefix('-') {
+            Some(digits) => format!("{}e-{:0>2}", mantissa, digits),
+            None => format!("{}e+{:0>2}", mantissa, exp),
+        },
+        None if repr.contains('.') => repr,
+        None => format!("{}.0", repr),
+    }
+}
+
+/* Variables live in a stack of scopes, one per enclosing block. Lookups
+ * walk from the innermost scope outwards, while assignments update the
+ * nearest scope of the current function frame that already binds the name
+ * and only create a new binding in the innermost scope otherwise. Names
+ * declared 'nonlocal' are written to the enclosing function's scope instead,
+ * and names declared 'global' to the module scope.
+ * Popping a scope drops every name that was created inside it.
+ *
+ * Scopes are shared: cloning an environment hands out the same scopes, which
+ * is how closures keep their definition site alive. */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Environment {
+    scopes: Vec<Rc<RefCell<HashMap<Name, EnvValue>>>>,
+    frame: usize,
+    nonlocals: Vec<Name>,
+    globals: Vec<Name>,
+}
+
+impl Environment {
+    pub fn new() -> Environment {
+        Environment::from([])
+    }
+
+    pub fn get(&self, name: &str) -> Option<EnvValue> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.borrow().get(name).cloned())
+    }
+
+    pub fn insert(&mut self, name: Name, value: EnvValue) {
+        if self.globals.contains(&name) {
+            self.scopes[0].borrow_mut().insert(name, value);
+            return;
+        }
+        let scopes = if self.nonlocals.contains(&name) {
+            &self.scopes[..self.frame]
+        } else {
+            &self.scopes[self.frame..]
+        };
+        match scopes
+            .iter()
+            .rev()
+            .find(|scope| scope.borrow().contains_key(&name))
+        {
+            Some(scope) => {
+                scope.borrow_mut().insert(name, value);
+            }
+            None => self.declare(name, value),
+        }
+    }
+
+    pub fn declare(&mut self, name: Name, value: EnvValue) {
+        if let Some(scope) = self.scopes.last() {
+            scope.borrow_mut().insert(name, value);
+        }
+    }
+
+    /* Marks a name as referring to the nearest enclosing function scope that
+     * binds it, failing if there is none. Module level names don't count. */
+    pub fn declare_nonlocal(&mut self, name: Name) -> Result<(), InterpreterError> {
+        if self.frame == 0 {
+            return Err(InterpreterError::SyntaxError(String::from(
+                "nonlocal declaration not allowed at module level",
+            )));
+        }
+        if !self.scopes[1..self.frame]
+            .iter()
+            .any(|scope| scope.borrow().contains_key(&name))
+        {
+            return Err(InterpreterError::SyntaxError(format!(
+                "no binding for nonlocal '{}' found",
+                name
+            )));
+        }
+        if self.globals.contains(&name) {
+            return Err(InterpreterError::SyntaxError(format!(
+                "name '{}' is nonlocal and global",
+                name
+            )));
+        }
+        if !self.nonlocals.contains(&name) {
+            self.nonlocals.push(name);
+        }
+        Ok(())
+    }
+
+    /* Marks a name as referring to the module scope, whether or not it is
+     * bound there yet. At module level this changes nothing. */
+    pub fn declare_global(&mut self, name: Name) -> Result<(), InterpreterError> {
+        if self.nonlocals.contains(&name) {
+            return Err(InterpreterError::SyntaxError(format!(
+                "name '{}' is nonlocal and global",
+                name
+            )));
+        }
+        if self.frame > 0 && !self.globals.contains(&name) {
+            self.globals.push(name);
+        }
+        Ok(())
+    }
+
+    /* A copy that shares no scopes with the original, so assignments made
+     * through either one later on don't show in the other. */
+    pub fn snapshot(&self) -> Environment {
+        Environment {
+            scopes: self
+                .scopes
+                .iter()
+                .map(|scope| Rc::new(RefCell::new(scope.borrow().clone())))
+                .collect(),
+            frame: self.frame,
+            nonlocals: self.nonlocals.clone(),
+            globals: self.globals.clone(),
+        }
+    }
+
+    pub fn push_scope(&mut self) {
+        self.scopes.push(Rc::new(RefCell::new(HashMap::new())));
+    }
+
+    pub fn pop_scope(&mut self) {
+        if self.scopes.len() > self.frame.max(1) {
+            self.scopes.pop();
+        }
+    }
+
+    /* Opens the scope of a function call. Assignments in it create locals
+     * unless the names are later declared 'nonlocal' or 'global'. */
+    pub fn push_frame(&mut self) {
+        self.push_scope();
+        self.frame = self.scopes.len() - 1;
+        self.nonlocals.clear();
+        self.globals.clear();
+    }
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Environment::new()
+    }
+}
+
+impl<const N: usize> From<[(Name, EnvValue); N]> for Environment {
+    fn from(vars: [(Name, EnvValue); N]) -> Self {
+        Environment {
+            scopes: vec![Rc::new(RefCell::new(HashMap::from(vars)))],
+            frame: 0,
+            nonlocals: Vec::new(),
+            globals: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlFlow {
+    Normal(Environment),
+    Return(EvalResult),
+    Break(Environment),
+    Continue(Environment),
+}
+
+/* Budgets bounding a single run of the interpreter. One context is shared
+ * by every nested call, so a chain of calls spends the same budget instead
+ * of starting afresh in each callee. */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interpreter {
+    max_depth: usize,
+    max_steps: Option<usize>,
+    depth: usize,
+    steps: usize,
+}
+
+pub fn eval(exp: &Expression, env: &Environment) -> Result<EvalResult, InterpreterError> {
+    Interpreter::new().eval(exp, env)
+}
+
+pub fn execute(stmt: &Statement, env: Environment) -> Result<Environment, InterpreterError> {
+    Interpreter::new().execute(stmt, env)
+}
+
+/* Runs a whole program from an empty environment and hands back the
+ * environment it leaves behind. */
+pub fn run(program: &Statement) -> Result<Environment, InterpreterError> {
+    execute(program, Environment::new())
+}
+
+/* Evaluates an expression that needs no variables of its own. */
+pub fn run_expr(exp: &Expression) -> Result<EvalResult, InterpreterError> {
+    eval(exp, &Environment::new())
+}
+
+/* Before each call the interpreter makes sure at least STACK_RED_ZONE
+ * bytes of Rust stack are left, moving onto a fresh segment of
+ * STACK_SEGMENT bytes when they are not. A debug build spends tens of
+ * kilobytes of stack on a simple call, and several hundred on one nested
+ * in loops and branches, so without this a deep recursion would overflow
+ * an ordinary thread's stack long before the depth limit is reached. */
+const STACK_RED_ZONE: usize = 1024 * 1024;
+const STACK_SEGMENT: usize = 32 * 1024 * 1024;
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    /* Allows 1000 nested calls, as Python does. */
+    pub fn new() -> Interpreter {
+        Interpreter {
+            max_depth: 1000,
+            max_steps: None,
+            depth: 0,
+            steps: 0,
+        }
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Interpreter {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn with_max_steps(mut self, max_steps: usize) -> Interpreter {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    fn step(&mut self) -> Result<(), InterpreterError> {
+        self.steps += 1;
+        match self.max_steps {
+            Some(max_steps) if self.steps > max_steps => Err(InterpreterError::StepLimitExceeded),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn eval(
+        &mut self,
+        exp: &Expression,
+        env: &Environment,
+    ) -> Result<EvalResult, InterpreterError> {
+        match exp {
+            Expression::Spanned(span, exp) => self.eval(exp, env).map_err(|err| err.located(*span)),
+            Expression::CInt(v) => Ok(EvalResult::CInt(*v)),
+            Expression::CReal(v) => Ok(EvalResult::CReal(*v)),
+            Expression::Bool(v) => Ok(EvalResult::Bool(*v)),
+            Expression::CString(v) => Ok(EvalResult::CString(v.clone())),
+            Expression::FString(parts) => {
+                let mut formatted = String::new();
+                for part in parts {
+                    formatted.push_str(&self.eval(part, env)?.to_string());
+                }
+                Ok(EvalResult::CString(formatted))
+            }
+            Expression::None => Ok(EvalResult::None),
+            Expression::List(items) => self.eval_list(items, env),
+            Expression::ListComp {
+                element,
+                var,
+                iterable,
+                condition,
+            }
+            | Expression::GenExp {
+                element,
+                var,
+                iterable,
+                condition,
+            } => self.eval_list_comp(element, var, iterable, condition, env),
+            Expression::Tuple(items) => {
+                let mut values = Vec::with_capacity(items.len());
+                for item in items {
+                    values.push(self.eval(item, env)?);
+                }
+                Ok(EvalResult::Tuple(values))
+            }
+            Expression::Dict(entries) => {
+                let mut dict = Dict::new();
+                for (key, value) in entries {
+                    let key = HashKey::from_value(&self.eval(key, env)?)?;
+                    dict.insert(key, self.eval(value, env)?);
+                }
+                Ok(EvalResult::Dict(Box::new(dict)))
+            }
+            Expression::Set(items) => {
+                let mut set = Set::new();
+                for item in items {
+                    set.insert(HashKey::from_value(&self.eval(item, env)?)?);
+                }
+                Ok(EvalResult::Set(Box::new(set)))
+            }
+            Expression::Index(target, index) => {
+                let target = self.eval(target, env)?;
+                let index = self.eval(index, env)?;
+                index_value(target, &index)
+            }
+            Expression::Neg(exp) => eval_neg(self.eval(exp, env)?),
+            /* Every binary operator that needs both operands evaluates them
+             * here and leaves the rest to 'eval_binary', which keeps the
+             * stack frame of 'eval' small. */
+            Expression::Add(lhs, rhs)
+            | Expression::Sub(lhs, rhs)
+            | Expression::Mul(lhs, rhs)
+            | Expression::Div(lhs, rhs)
+            | Expression::FloorDiv(lhs, rhs)
+            | Expression::Mod(lhs, rhs)
+            | Expression::Pow(lhs, rhs)
+            | Expression::BitAnd(lhs, rhs)
+            | Expression::BitOr(lhs, rhs)
+            | Expression::BitXor(lhs, rhs)
+            | Expression::Shl(lhs, rhs)
+            | Expression::Shr(lhs, rhs)
+            | Expression::Eq(lhs, rhs)
+            | Expression::NotEq(lhs, rhs)
+            | Expression::Lt(lhs, rhs)
+            | Expression::LtE(lhs, rhs)
+            | Expression::Gt(lhs, rhs)
+            | Expression::GtE(lhs, rhs)
+            | Expression::Is(lhs, rhs)
+            | Expression::IsNot(lhs, rhs)
+            | Expression::In(lhs, rhs)
+            | Expression::NotIn(lhs, rhs) => {
+                let lhs_value = self.eval(lhs, env)?;
+                let rhs_value = self.eval(rhs, env)?;
+                eval_binary(exp, lhs_value, rhs_value)
+            }
+            /* Each operand is evaluated once, when its link is reached, and
+             * the chain stops at the first link that does not hold. */
+            Expression::Compare(first, links) => {
+                let mut lhs_value = self.eval(first, env)?;
+                for (op, rhs) in links {
+                    let rhs_value = self.eval(rhs, env)?;
+                    if !is_truthy(&eval_comparison(*op, lhs_value, rhs_value.clone())?) {
+                        return Ok(EvalResult::Bool(false));
+                    }
+                    lhs_value = rhs_value;
+                }
+                Ok(EvalResult::Bool(true))
+            }
+            Expression::Var(name) => match env.get(name) {
+                Some(EnvValue::CInt(value)) => Ok(EvalResult::CInt(value)),
+                Some(EnvValue::CReal(value)) => Ok(EvalResult::CReal(value)),
+                Some(EnvValue::Bool(value)) => Ok(EvalResult::Bool(value)),
+                Some(EnvValue::CString(value)) => Ok(EvalResult::CString(value)),
+                Some(EnvValue::List(value)) => Ok(EvalResult::List(value)),
+                Some(EnvValue::Tuple(value)) => Ok(EvalResult::Tuple(value)),
+                Some(EnvValue::Dict(value)) => Ok(EvalResult::Dict(value)),
+                Some(EnvValue::Set(value)) => Ok(EvalResult::Set(value)),
+                Some(EnvValue::Func(func)) => Ok(EvalResult::Func(func)),
+                Some(EnvValue::None) => Ok(EvalResult::None),
+                None => Err(InterpreterError::UndefinedVariable(name.clone())),
+            },
+            Expression::FuncCall(name, args) => match env.get(name) {
+                Some(EnvValue::Func(func)) => {
+                    let args = self.eval_args(args, env)?;
+                    self.call_function(name, &func, args)
+                }
+                _ if is_builtin(name) => {
+                    let args = self.eval_args(args, env)?;
+                    self.call_builtin(name, args)
+                }
+                _ => Err(InterpreterError::UndefinedFunction(name.clone())),
+            },
+            Expression::Lambda(params, body) => lambda(params, body, env),
+            Expression::Call(callee, args) => match self.eval(callee, env)? {
+                EvalResult::Func(func) => {
+                    let args = self.eval_args(args, env)?;
+                    self.call_function(&func.name, &func, args)
+                }
+                value => Err(InterpreterError::TypeError(format!(
+                    "'{}' object is not callable",
+                    value.type_name()
+                ))),
+            },
+            /* Like Python, the result is the operand that decided it, and
+             * the right operand is only evaluated when the left one does
+             * not decide on its own. */
+            Expression::And(lhs, rhs) => {
+                let lhs = self.eval(lhs, env)?;
+                if is_truthy(&lhs) {
+                    self.eval(rhs, env)
+                } else {
+                    Ok(lhs)
+                }
+            }
+            Expression::Or(lhs, rhs) => {
+                let lhs = self.eval(lhs, env)?;
+                if is_truthy(&lhs) {
+                    Ok(lhs)
+                } else {
+                    self.eval(rhs, env)
+                }
+            }
+            Expression::Range(exp1, exp2, exp3) => self.eval_range(exp1, exp2, exp3, false, env),
+            Expression::RangeInclusive(exp1, exp2, exp3) => {
+                self.eval_range(exp1, exp2, exp3, true, env)
+            }
+        }
+    }
+
+    fn eval_list(
+        &mut self,
+        items: &[Expression],
+        env: &Environment,
+    ) -> Result<EvalResult, InterpreterError> {
+        let mut list_vec: Vec<EvalResult> = Vec::with_capacity(items.len());
+        let mut has_real = false;
+
+        for item in items {
+            let value = self.eval(item, env)?;
+            let first_item = list_vec.first().unwrap_or(&value);
+            match (first_item, &value) {
+                (EvalResult::CInt(_) | EvalResult::CReal(_), EvalResult::CInt(_)) => (),
+                (EvalResult::CInt(_) | EvalResult::CReal(_), EvalResult::CReal(_)) => {
+                    has_real = true;
+                }
+                (EvalResult::Bool(_), EvalResult::Bool(_)) => (),
+                (EvalResult::CString(_), EvalResult::CString(_)) => (),
+                (EvalResult::List(_), EvalResult::List(_)) => (),
+                (EvalResult::Tuple(_), EvalResult::Tuple(_)) => (),
+                (EvalResult::Dict(_), EvalResult::Dict(_)) => (),
+                (EvalResult::Set(_), EvalResult::Set(_)) => (),
+                _ => {
+                    return Err(InterpreterError::TypeError(String::from(
+                        "List must be homogeneous",
+                    )))
+                }
+            }
+            list_vec.push(value);
+        }
+
+        if has_real {
+            list_vec = list_vec
+                .into_iter()
+                .map(|value| match value {
+                    EvalResult::CInt(v) => EvalResult::CReal(v as f32),
+                    _ => value,
+                })
+                .collect();
+        }
+        Ok(EvalResult::List(list_vec))
+    }
+
+    /* The loop variable is bound in a scope pushed onto a copy of the
+     * environment, so like the variable of a 'for' loop it is gone once
+     * the comprehension is evaluated. */
+    fn eval_list_comp(
+        &mut self,
+        element: &Expression,
+        var: &Name,
+        iterable: &Expression,
+        condition: &Option<Box<Expression>>,
+        env: &Environment,
+    ) -> Result<EvalResult, InterpreterError> {
+        let items = into_iter_items(self.eval(iterable, env)?)?;
+        let mut comp_env = env.clone();
+        comp_env.push_scope();
+
+        let mut values = Vec::new();
+        for item in items {
+            comp_env.declare(var.clone(), EnvValue::from(item));
+            if let Some(condition) = condition {
+                if !is_truthy(&self.eval(condition, &comp_env)?) {
+                    continue;
+                }
+            }
+            values.push(self.eval(element, &comp_env)?);
+        }
+        Ok(EvalResult::List(values))
+    }
+
+    fn eval_range(
+        &mut self,
+        exp1: &Option<Box<Expression>>,
+        exp2: &Expression,
+        exp3: &Option<Box<Expression>>,
+        inclusive: bool,
+        env: &Environment,
+    ) -> Result<EvalResult, InterpreterError> {
+        let end_value = self.eval(exp2, env)?;
+        let srt_value = match exp1 {
+            Some(exp) => self.eval(exp, env)?,
+            None => EvalResult::CInt(0),
+        };
+        let incr_value = match exp3 {
+            Some(exp) => self.eval(exp, env)?,
+            None => EvalResult::CInt(1),
+        };
+
+        if inclusive {
+            range_inclusive(srt_value, end_value, incr_value)
+        } else {
+            range(srt_value, end_value, incr_value)
+        }
+    }
+
+    pub fn execute(
+        &mut self,
+        stmt: &Statement,
+        env: Environment,
+    ) -> Result<Environment, InterpreterError> {
+        match self.exec(stmt, env)? {
+            ControlFlow::Normal(new_env) => Ok(new_env),
+            ControlFlow::Return(_) => Err(InterpreterError::SyntaxError(String::from(
+                "'return' outside function",
+            ))),
+            ControlFlow::Break(_) => Err(InterpreterError::SyntaxError(String::from(
+                "'break' outside loop",
+            ))),
+            ControlFlow::Continue(_) => Err(InterpreterError::SyntaxError(String::from(
+                "'continue' not properly in loop",
+            ))),
+        }
+    }
+
+    /* Executes a statement and reports how control leaves it, so that a
+     * 'return' nested inside loops and sequences reaches the enclosing call. */
+    fn exec(
+        &mut self,
+        stmt: &Statement,
+        env: Environment,
+    ) -> Result<ControlFlow, InterpreterError> {
+        if let Statement::Spanned(span, stmt) = stmt {
+            return self.exec(stmt, env).map_err(|err| err.located(*span));
+        }
+        self.step()?;
+        match stmt {
+            Statement::Assignment(name, exp) => {
+                let value = self.eval(exp, &env)?;
+                let mut new_env = env;
+                new_env.insert(*name.clone(), EnvValue::from(value));
+                Ok(ControlFlow::Normal(new_env))
+            }
+            Statement::IndexAssign(target, index, exp) => {
+                let value = self.eval(exp, &env)?;
+                let mut new_env = env;
+                self.assign_index(target, index, value, &mut new_env)?;
+                Ok(ControlFlow::Normal(new_env))
+            }
+            Statement::MultiAssign(names, exps) => {
+                /* Every value is read before any name is bound, which is
+                 * what makes 'a, b = b, a' a swap. A single expression on
+                 * the right is unpacked instead. */
+                let value = match exps.as_slice() {
+                    [exp] => self.eval(exp, &env)?,
+                    _ => {
+                        let mut values = Vec::with_capacity(exps.len());
+                        for exp in exps {
+                            values.push(self.eval(exp, &env)?);
+                        }
+                        EvalResult::Tuple(values)
+                    }
+                };
+
+                let mut new_env = env;
+                for (name, value) in names.iter().zip(unpack(value, names.len())?) {
+                    if !is_discard(name) {
+                        new_env.insert(name.clone(), EnvValue::from(value));
+                    }
+                }
+                Ok(ControlFlow::Normal(new_env))
+            }
+            Statement::IfThenElse(cond, stmt_then, stmt_else) => {
+                let value = match self.eval(cond, &env) {
+                    Ok(value) => is_truthy(&value),
+                    Err(s) => return Err(InterpreterError::ConditionError(Box::new(s))),
+                };
+
+                if value {
+                    self.exec_block(stmt_then, env)
+                } else {
+                    self.exec_block(stmt_else, env)
+                }
+            }
+            Statement::If(cond, stmt_then) => {
+                let value = match self.eval(cond, &env) {
+                    Ok(value) => is_truthy(&value),
+                    Err(s) => return Err(InterpreterError::ConditionError(Box::new(s))),
+                };
+
+                if value {
+                    self.exec_block(stmt_then, env)
+                } else {
+                    Ok(ControlFlow::Normal(env))
+                }
+            }
+            Statement::While(cond, stmt) => self.exec_while(cond, stmt, None, env),
+            Statement::WhileElse(cond, stmt, stmt_else) => {
+                self.exec_while(cond, stmt, Some(stmt_else), env)
+            }
+            Statement::Func(name, kind, params, stmt, retrn) => {
+                let mut new_env = env.clone();
+
+                if let Some(params) = params {
+                    for (i, (param, _)) in params.iter().enumerate() {
+                        if params[..i].iter().any(|(other, _)| other == param) {
+                            return Err(InterpreterError::SyntaxError(format!(
+                                "duplicate argument '{}' in function definition",
+                                param
+                            )));
+                        }
+                    }
+                }
+
+                let func = Function {
+                    name: *name.clone(),
+                    kind: *kind.clone(),
+                    params: params
+                        .iter()
+                        .flatten()
+                        .map(|(param, kind)| (param.clone(), *kind.clone()))
+                        .collect(),
+                    body: stmt.clone(),
+                    retrn: retrn.clone(),
+                    env: new_env.clone(),
+                    memo: None,
+                };
+                /* The captured environment shares its scopes with new_env,
+                 * so the binding made here is also visible from inside the
+                 * body, and recursive calls find the function. */
+                new_env.insert(*name.clone(), EnvValue::Func(Rc::new(func)));
+                Ok(ControlFlow::Normal(new_env))
+            }
+            Statement::For(var, exp, stmt) => {
+                let items = into_iter_items(self.eval(exp, &env)?)?;
+                self.exec_for(items, stmt, None, env, |env, item| {
+                    if !is_discard(var) {
+                        env.declare(*var.clone(), EnvValue::from(item));
+                    }
+                    Ok(())
+                })
+            }
+            Statement::ForElse(var, exp, stmt, stmt_else) => {
+                let items = into_iter_items(self.eval(exp, &env)?)?;
+                self.exec_for(items, stmt, Some(stmt_else), env, |env, item| {
+                    if !is_discard(var) {
+                        env.declare(*var.clone(), EnvValue::from(item));
+                    }
+                    Ok(())
+                })
+            }
+            Statement::ForUnpack(vars, exp, stmt) => {
+                let items = into_iter_items(self.eval(exp, &env)?)?;
+                self.exec_for(items, stmt, None, env, |env, item| {
+                    for (var, value) in vars.iter().zip(unpack(item, vars.len())?) {
+                        if !is_discard(var) {
+                            env.declare(var.clone(), EnvValue::from(value));
+                        }
+                    }
+                    Ok(())
+                })
+            }
+            Statement::Sequence(s1, s2) => match self.exec(s1, env)? {
+                ControlFlow::Normal(new_env) => self.exec(s2, new_env),
+                flow => Ok(flow),
+            },
+            Statement::Return(exp) => Ok(ControlFlow::Return(self.eval(exp, &env)?)),
+            Statement::Nonlocal(names) => {
+                let mut new_env = env;
+                for name in names {
+                    new_env.declare_nonlocal(name.clone())?;
+                }
+                Ok(ControlFlow::Normal(new_env))
+            }
+            Statement::Global(names) => {
+                let mut new_env = env;
+                for name in names {
+                    new_env.declare_global(name.clone())?;
+                }
+                Ok(ControlFlow::Normal(new_env))
+            }
+            Statement::Assert(cond, message) => {
+                if is_truthy(&self.eval(cond, &env)?) {
+                    return Ok(ControlFlow::Normal(env));
+                }
+                let message = match message {
+                    Some(message) => Some(self.eval(message, &env)?.to_string()),
+                    None => None,
+                };
+                Err(InterpreterError::AssertionError(message))
+            }
+            Statement::Expr(exp) => match append_call(exp, &env) {
+                Some(args) => {
+                    let mut new_env = env;
+                    self.append(args, &mut new_env)?;
+                    Ok(ControlFlow::Normal(new_env))
+                }
+                None => {
+                    self.eval(exp, &env)?;
+                    Ok(ControlFlow::Normal(env))
+                }
+            },
+            Statement::Pass => Ok(ControlFlow::Normal(env)),
+            Statement::Break => Ok(ControlFlow::Break(env)),
+            Statement::Continue => Ok(ControlFlow::Continue(env)),
+            _ => Err(InterpreterError::NotImplemented),
+        }
+    }
+
+    /* Runs a loop body while its condition holds. The else block, if any,
+     * runs only when the condition turns false: a 'break', a 'return' or
+     * an error (the step limit included) leaves the loop without it. */
+    fn exec_while(
+        &mut self,
+        cond: &Expression,
+        stmt: &Statement,
+        stmt_else: Option<&Statement>,
+        env: Environment,
+    ) -> Result<ControlFlow, InterpreterError> {
+        let mut new_env = env;
+        loop {
+            let value = match self.eval(cond, &new_env) {
+                Ok(value) => is_truthy(&value),
+                Err(s) => return Err(InterpreterError::ConditionError(Box::new(s))),
+            };
+
+            if !value {
+                break;
+            }
+            match self.exec_block(stmt, new_env)? {
+                ControlFlow::Normal(env) | ControlFlow::Continue(env) => new_env = env,
+                ControlFlow::Break(env) => return Ok(ControlFlow::Normal(env)),
+                ControlFlow::Return(value) => return Ok(ControlFlow::Return(value)),
+            }
+        }
+        match stmt_else {
+            Some(stmt_else) => self.exec_block(stmt_else, new_env),
+            None => Ok(ControlFlow::Normal(new_env)),
+        }
+    }
+
+    /* Runs a loop body once per item. The loop variables, declared by 'bind'
+     * for each item, live in a scope of the loop's own that is dropped
+     * once the loop ends, before the else block, if any, runs. As with
+     * 'while', the else block only runs when no 'break' ended the loop. */
+    fn exec_for(
+        &mut self,
+        items: Vec<EvalResult>,
+        stmt: &Statement,
+        stmt_else: Option<&Statement>,
+        mut env: Environment,
+        bind: impl Fn(&mut Environment, EvalResult) -> Result<(), InterpreterError>,
+    ) -> Result<ControlFlow, InterpreterError> {
+        env.push_scope();
+        for item in items {
+            bind(&mut env, item)?;
+            match self.exec_block(stmt, env)? {
+                ControlFlow::Normal(new_env) | ControlFlow::Continue(new_env) => env = new_env,
+                ControlFlow::Break(mut new_env) => {
+                    new_env.pop_scope();
+                    return Ok(ControlFlow::Normal(new_env));
+                }
+                ControlFlow::Return(value) => return Ok(ControlFlow::Return(value)),
+            }
+        }
+        env.pop_scope();
+        match stmt_else {
+            Some(stmt_else) => self.exec_block(stmt_else, env),
+            None => Ok(ControlFlow::Normal(env)),
+        }
+    }
+
+    /* Executes a statement in a scope of its own, so that names first assigned
+     * inside the block are dropped once control leaves it. */
+    fn exec_block(
+        &mut self,
+        stmt: &Statement,
+        mut env: Environment,
+    ) -> Result<ControlFlow, InterpreterError> {
+        env.push_scope();
+        let flow = match self.exec(stmt, env)? {
+            ControlFlow::Normal(mut env) => {
+                env.pop_scope();
+                ControlFlow::Normal(env)
+            }
+            ControlFlow::Break(mut env) => {
+                env.pop_scope();
+                ControlFlow::Break(env)
+            }
+            ControlFlow::Continue(mut env) => {
+                env.pop_scope();
+                ControlFlow::Continue(env)
+            }
+            ControlFlow::Return(value) => ControlFlow::Return(value),
+        };
+        Ok(flow)
+    }
+
+    /* Stores 'target[index] = value', with the value already evaluated.
+     * As in Python, the target is then evaluated from the outside in, and
+     * every index exactly once, so 'm[f()][g()] = v' calls f before g and
+     * each of them once. Containers are values, so every container along
+     * the path is updated and written back into the one holding it, up to
+     * the variable. */
+    fn assign_index(
+        &mut self,
+        target: &Expression,
+        index: &Expression,
+        value: EvalResult,
+        env: &mut Environment,
+    ) -> Result<(), InterpreterError> {
+        let mut path = vec![index];
+        let mut root = target;
+        loop {
+            match root {
+                Expression::Spanned(_, inner) => root = inner,
+                Expression::Index(outer, index) => {
+                    path.push(index);
+                    root = outer;
+                }
+                _ => break,
+            }
+        }
+        let Expression::Var(name) = root else {
+            return Err(InterpreterError::SyntaxError(String::from(
+                "cannot assign to expression",
+            )));
+        };
+
+        /* containers[i] is the container that indices[i] indexes. */
+        let depth = path.len();
+        let mut containers = vec![self.eval(root, env)?];
+        let mut indices = Vec::with_capacity(depth);
+        for (level, index) in path.into_iter().rev().enumerate() {
+            let index = self.eval(index, env)?;
+            if level + 1 < depth {
+                containers.push(index_value(containers[level].clone(), &index)?);
+            }
+            indices.push(index);
+        }
+
+        let mut updated = value;
+        for (container, index) in containers.into_iter().zip(indices).rev() {
+            updated = set_item(container, &index, updated)?;
+        }
+        env.insert(name.clone(), EnvValue::from(updated));
+        Ok(())
+    }
+
+    fn eval_args(
+        &mut self,
+        args: &Option<Vec<Expression>>,
+        env: &Environment,
+    ) -> Result<Vec<EvalResult>, InterpreterError> {
+        let mut values = Vec::with_capacity(args.as_ref().map_or(0, Vec::len));
+        for arg in args.iter().flatten() {
+            values.push(self.eval(arg, env)?);
+        }
+        Ok(values)
+    }
+
+    /* Runs 'append(xs, v)'. Lists are stored by value, so the grown list
+     * is put back under the name of the variable holding it. */
+    fn append(
+        &mut self,
+        args: &Option<Vec<Expression>>,
+        env: &mut Environment,
+    ) -> Result<(), InterpreterError> {
+        let values = self.eval_args(args, env)?;
+        let [target, value] = <[EvalResult; 2]>::try_from(values).map_err(|values| {
+            InterpreterError::TypeError(format!(
+                "append expected 2 arguments, got {}",
+                values.len()
+            ))
+        })?;
+        let mut items = match target {
+            EvalResult::List(items) => items,
+            target => {
+                return Err(InterpreterError::TypeError(format!(
+                    "'{}' object has no attribute 'append'",
+                    target.type_name()
+                )))
+            }
+        };
+        let mut name = args.iter().flatten().next();
+        while let Some(Expression::Spanned(_, inner)) = name {
+            name = Some(inner);
+        }
+        let Some(Expression::Var(name)) = name else {
+            return Err(InterpreterError::SyntaxError(String::from(
+                "cannot append to expression",
+            )));
+        };
+        items.push(value);
+        env.insert(name.clone(), EnvValue::List(items));
+        Ok(())
+    }
+
+    /* map and filter call back into the program, so they run here with
+     * the interpreter at hand. The other builtins live in builtins.rs. */
+    fn call_builtin(
+        &mut self,
+        name: &str,
+        args: Vec<EvalResult>,
+    ) -> Result<EvalResult, InterpreterError> {
+        if name != "map" && name != "filter" {
+            return call_builtin(name, args);
+        }
+        let [func, iterable] = <[EvalResult; 2]>::try_from(args).map_err(|args| {
+            InterpreterError::TypeError(format!(
+                "{} expected 2 arguments, got {}",
+                name,
+                args.len()
+            ))
+        })?;
+        let func = match func {
+            EvalResult::Func(func) => func,
+            value => {
+                return Err(InterpreterError::TypeError(format!(
+                    "'{}' object is not callable",
+                    value.type_name()
+                )))
+            }
+        };
+
+        let mut results = Vec::new();
+        for item in into_iter_items(iterable)? {
+            let value = self.call_function(&func.name, &func, vec![item.clone()])?;
+            if name == "map" {
+                results.push(value);
+            } else if is_truthy(&value) {
+                results.push(item);
+            }
+        }
+        Ok(EvalResult::List(results))
+    }
+
+    /* Calls a user-defined function with already evaluated arguments.
+     *
+     * The body runs in the environment the function was defined in, under a
+     * new frame holding the parameters. Assignments create locals in that
+     * frame, so the caller's variables, including ones sharing a name with
+     * the callee's, are left exactly as they were.
+     *
+     * Arguments are passed as copies. A list bound to a parameter is the
+     * callee's own, so appending to it or assigning into it changes only
+     * the copy, never the list the caller passed in.
+     *
+     * Cloning the definition environment copies only its chain of scope
+     * pointers, never the variables, so the cost of a call does not grow
+     * with the number of names in scope. */
+    fn call_function(
+        &mut self,
+        name: &str,
+        func: &Function,
+        args: Vec<EvalResult>,
+    ) -> Result<EvalResult, InterpreterError> {
+        if args.len() != func.params.len() {
+            return Err(InterpreterError::ArityMismatch {
+                name: name.to_string(),
+                expected: func.params.len(),
+                got: args.len(),
+            });
+        }
+
+        /* Arguments become the cache key before they are moved into the
+         * callee's frame. Unhashable ones, which include every list, are
+         * refused up front rather than hashed element by element. */
+        let memo_key = match &func.memo {
+            Some(memo) => {
+                let mut key = Vec::with_capacity(args.len());
+                for arg in &args {
+                    key.push(HashKey::from_value(arg).map_err(|_| {
+                        InterpreterError::TypeError(format!(
+                            "memoized function {} got an unhashable argument of type '{}'",
+                            name,
+                            arg.type_name()
+                        ))
+                    })?);
+                }
+                if let Some(value) = memo.borrow().get(&key) {
+                    return Ok(value.clone());
+                }
+                Some(key)
+            }
+            None => None,
+        };
+
+        let mut func_env = func.env.clone();
+        func_env.push_frame();
+        for ((param, kind), value) in func.params.iter().zip(args) {
+            if !has_type(&value, kind) {
+                return Err(InterpreterError::TypeError(format!(
+                    "argument '{}' expected {} but got {}",
+                    param,
+                    kind,
+                    value.type_name()
+                )));
+            }
+            func_env.declare(param.clone(), EnvValue::from(value));
+        }
+
+        if self.depth >= self.max_depth {
+            return Err(InterpreterError::RecursionLimitExceeded);
+        }
+        self.depth += 1;
+        let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || {
+            self.run_body(name, &func.body, &func.retrn, func_env)
+        });
+        self.depth -= 1;
+        let result = result?;
+
+        if !has_type(&result, &func.kind) {
+            return Err(InterpreterError::TypeError(format!(
+                "{} returned a value different from specified type",
+                name
+            )));
+        }
+        if let (Some(memo), Some(key)) = (&func.memo, memo_key) {
+            memo.borrow_mut().insert(key, result.clone());
+        }
+        Ok(result)
+    }
+
+    fn run_body(
+        &mut self,
+        name: &str,
+        stmt: &Option<Box<Statement>>,
+        retrn: &Expression,
+        func_env: Environment,
+    ) -> Result<EvalResult, InterpreterError> {
+        match stmt {
+            Some(body_stmt) => match self.exec(body_stmt, func_env) {
+                Ok(ControlFlow::Normal(result_env)) => self.eval(retrn, &result_env),
+                Ok(ControlFlow::Return(value)) => Ok(value),
+                Ok(ControlFlow::Break(_)) => Err(InterpreterError::SyntaxError(String::from(
+                    "'break' outside loop",
+                ))),
+                Ok(ControlFlow::Continue(_)) => Err(InterpreterError::SyntaxError(String::from(
+                    "'continue' not properly in loop",
+                ))),
+                Err(err) => Err(InterpreterError::FunctionError {
+                    name: name.to_string(),
+                    error: Box::new(err),
+                    call: None,
+                }),
+            },
+            None => self.eval(retrn, &func_env),
+        }
+    }
+}
+
+/* The arguments of a line calling the builtin append, which grows a list
+ * in place and so runs as a statement. As for any builtin, a function the
+ * program defines under the same name is called instead. */
+pub(crate) fn append_call<'a>(
+    exp: &'a Expression,
+    env: &Environment,
+) -> Option<&'a Option<Vec<Expression>>> {
+    match exp {
+        Expression::Spanned(_, exp) => append_call(exp, env),
+        Expression::FuncCall(name, args) if name == "append" => match env.get(name) {
+            Some(EnvValue::Func(_)) => None,
+            _ => Some(args),
+        },
+        _ => None,
+    }
+}
+
+/* A lambda is a function whose parameters and result may be of any type
+ * and whose body is just the expression it returns. Like a def, it keeps
+ * the scopes it was created in. */
+fn lambda(
+    params: &[Name],
+    body: &Expression,
+    env: &Environment,
+) -> Result<EvalResult, InterpreterError> {
+    for (i, param) in params.iter().enumerate() {
+        if params[..i].contains(param) {
+            return Err(InterpreterError::SyntaxError(format!(
+                "duplicate argument '{}' in function definition",
+                param
+            )));
+        }
+    }
+    Ok(EvalResult::Func(Rc::new(Function {
+        name: String::from("<lambda>"),
+        kind: Type::TAny,
+        params: params
+            .iter()
+            .map(|param| (param.clone(), Type::TAny))
+            .collect(),
+        body: None,
+        retrn: Box::new(body.clone()),
+        env: env.clone(),
+        memo: None,
+    })))
+}
+
+fn has_type(value: &EvalResult, kind: &Type) -> bool {
+    match (kind, value) {
+        (Type::TAny, _) => true,
+        (Type::TInteger, EvalResult::CInt(_)) => true,
+        (Type::TReal, EvalResult::CReal(_)) => true,
+        (Type::TBool, EvalResult::Bool(_)) => true,
+        (Type::TString, EvalResult::CString(_)) => true,
+        (Type::TList(item_kind), EvalResult::List(items)) => {
+            items.iter().all(|item| has_type(item, item_kind))
+        }
+        (Type::TTuple(item_kinds), EvalResult::Tuple(items)) => {
+            item_kinds.len() == items.len()
+                && items
+                    .iter()
+                    .zip(item_kinds)
+                    .all(|(item, item_kind)| has_type(item, item_kind))
+        }
+        (Type::TFunction, EvalResult::Func(_)) => true,
+        (Type::TNone, EvalResult::None) => true,
+        (Type::TOptional(_), EvalResult::None) => true,
+        (Type::TOptional(kind), value) => has_type(value, kind),
+        _ => false,
+    }
+}
+
+/* The error for an operator applied to operands it does not support,
+ * worded like Python's. */
+fn unsupported_operand(op: &str, lhs: &EvalResult, rhs: &EvalResult) -> InterpreterError {
+    InterpreterError::TypeError(format!(
+        "unsupported operand type(s) for {}: '{}' and '{}'",
+        op,
+        lhs.type_name(),
+        rhs.type_name()
+    ))
+}
+
+/* Int arithmetic is checked, so a result outside the range of an int is
+ * an error rather than a panic or a silent wrap. */
+fn int_result(result: Option<i32>) -> Result<EvalResult, InterpreterError> {
+    result
+        .map(EvalResult::CInt)
+        .ok_or(InterpreterError::IntegerOverflow)
+}
+
+/* The arithmetic operators, applied to operands that are already
+ * evaluated. They are kept out of 'eval' because its stack frame is paid
+ * again for every nested expression and call, and these matches are what
+ * made it large. */
+pub(crate) fn eval_add(
+    lhs_value: EvalResult,
+    rhs_value: EvalResult,
+) -> Result<EvalResult, InterpreterError> {
+    match (lhs_value, rhs_value) {
+        (EvalResult::CInt(lhs), EvalResult::CInt(rhs)) => int_result(lhs.checked_add(rhs)),
+        (EvalResult::CReal(lhs), EvalResult::CReal(rhs)) => Ok(EvalResult::CReal(lhs + rhs)),
+        (EvalResult::CInt(lhs), EvalResult::CReal(rhs)) => Ok(EvalResult::CReal(lhs as f32 + rhs)),
+        (EvalResult::CReal(lhs), EvalResult::CInt(rhs)) => Ok(EvalResult::CReal(lhs + rhs as f32)),
+        (EvalResult::CInt(lhs), EvalResult::Bool(rhs)) => int_result(lhs.checked_add(rhs as i32)),
+        (EvalResult::CReal(lhs), EvalResult::Bool(rhs)) => {
+            Ok(EvalResult::CReal(lhs + (rhs as i32) as f32))
+        }
+        (EvalResult::Bool(lhs), EvalResult::CInt(rhs)) => int_result((lhs as i32).checked_add(rhs)),
+        (EvalResult::Bool(lhs), EvalResult::CReal(rhs)) => {
+            Ok(EvalResult::CReal((lhs as i32) as f32 + rhs))
+        }
+        (EvalResult::Bool(lhs), EvalResult::Bool(rhs)) => {
+            int_result((lhs as i32).checked_add(rhs as i32))
+        }
+        (lhs @ EvalResult::None, rhs) | (lhs, rhs @ EvalResult::None) => {
+            Err(unsupported_operand("+", &lhs, &rhs))
+        }
+        (EvalResult::Tuple(lhs), EvalResult::Tuple(rhs)) => {
+            let mut result_tuple = lhs;
+            result_tuple.extend(rhs);
+            Ok(EvalResult::Tuple(result_tuple))
+        }
+        (EvalResult::Tuple(_), _) | (_, EvalResult::Tuple(_)) => Err(InterpreterError::TypeError(
+            String::from("Can only concatenate tuple to tuple"),
+        )),
+        (EvalResult::List(lhs), EvalResult::List(rhs)) => {
+            let mut result_list = lhs.clone();
+            result_list.extend(rhs);
+            Ok(EvalResult::List(result_list))
+        }
+        (EvalResult::List(_), _) => Err(InterpreterError::TypeError(String::from(
+            "Can only concatenate list to list",
+        ))),
+        (_, EvalResult::List(_)) => Err(InterpreterError::TypeError(String::from(
+            "Can only concatenate list to list",
+        ))),
+        (EvalResult::CString(lhs), EvalResult::CString(rhs)) => Ok(EvalResult::CString(lhs + &rhs)),
+        (EvalResult::CString(_), _) => Err(InterpreterError::TypeError(String::from(
+            "Can only concatenate str to str",
+        ))),
+        (_, EvalResult::CString(_)) => Err(InterpreterError::TypeError(String::from(
+            "Can only concatenate str to str",
+        ))),
+        (EvalResult::Func(_), _) | (_, EvalResult::Func(_)) => Err(InterpreterError::TypeError(
+            String::from("Add not supported for function"),
+        )),
+        (EvalResult::Dict(_), _) | (_, EvalResult::Dict(_)) => Err(InterpreterError::TypeError(
+            String::from("Add not supported for dict"),
+        )),
+        (EvalResult::Set(_), _) | (_, EvalResult::Set(_)) => Err(InterpreterError::TypeError(
+            String::from("Add not supported for set"),
+        )),
+    }
+}
+
+/* Like Python, negating a bool gives an int, and negating a real flips
+ * its sign even when it is zero. */
+fn eval_neg(value: EvalResult) -> Result<EvalResult, InterpreterError> {
+    match value {
+        EvalResult::CInt(v) => int_result(v.checked_neg()),
+        EvalResult::CReal(v) => Ok(EvalResult::CReal(-v)),
+        EvalResult::Bool(v) => Ok(EvalResult::CInt(-(v as i32))),
+        value => Err(InterpreterError::TypeError(format!(
+            "bad operand type for unary -: '{}'",
+            value.type_name()
+        ))),
+    }
+}
+
+fn eval_sub(lhs_value: EvalResult, rhs_value: EvalResult) -> Result<EvalResult, InterpreterError> {
+    match (lhs_value, rhs_value) {
+        (EvalResult::CInt(lhs), EvalResult::CInt(rhs)) => int_result(lhs.checked_sub(rhs)),
+        (EvalResult::CReal(lhs), EvalResult::CReal(rhs)) => Ok(EvalResult::CReal(lhs - rhs)),
+        (EvalResult::CInt(lhs), EvalResult::CReal(rhs)) => Ok(EvalResult::CReal(lhs as f32 - rhs)),
+        (EvalResult::CReal(lhs), EvalResult::CInt(rhs)) => Ok(EvalResult::CReal(lhs - rhs as f32)),
+        (EvalResult::CInt(lhs), EvalResult::Bool(rhs)) => int_result(lhs.checked_sub(rhs as i32)),
+        (EvalResult::CReal(lhs), EvalResult::Bool(rhs)) => {
+            Ok(EvalResult::CReal(lhs - (rhs as i32) as f32))
+        }
+        (EvalResult::Bool(lhs), EvalResult::CInt(rhs)) => int_result((lhs as i32).checked_sub(rhs)),
+        (EvalResult::Bool(lhs), EvalResult::CReal(rhs)) => {
+            Ok(EvalResult::CReal((lhs as i32) as f32 - rhs))
+        }
+        (EvalResult::Bool(lhs), EvalResult::Bool(rhs)) => {
+            int_result((lhs as i32).checked_sub(rhs as i32))
+        }
+        (lhs @ EvalResult::None, rhs) | (lhs, rhs @ EvalResult::None) => {
+            Err(unsupported_operand("-", &lhs, &rhs))
+        }
+        (EvalResult::List(_), _) => Err(InterpreterError::TypeError(String::from(
+            "Sub not supported for list",
+        ))),
+        (_, EvalResult::List(_)) => Err(InterpreterError::TypeError(String::from(
+            "Sub not supported for list",
+        ))),
+        (EvalResult::CString(_), _) => Err(InterpreterError::TypeError(String::from(
+            "Sub not supported for str",
+        ))),
+        (_, EvalResult::CString(_)) => Err(InterpreterError::TypeError(String::from(
+            "Sub not supported for str",
+        ))),
+        (EvalResult::Func(_), _) | (_, EvalResult::Func(_)) => Err(InterpreterError::TypeError(
+            String::from("Sub not supported for function"),
+        )),
+        (EvalResult::Dict(_), _) | (_, EvalResult::Dict(_)) => Err(InterpreterError::TypeError(
+            String::from("Sub not supported for dict"),
+        )),
+        (EvalResult::Set(_), _) | (_, EvalResult::Set(_)) => Err(InterpreterError::TypeError(
+            String::from("Sub not supported for set"),
+        )),
+        (EvalResult::Tuple(_), _) | (_, EvalResult::Tuple(_)) => Err(InterpreterError::TypeError(
+            String::from("Sub not supported for tuple"),
+        )),
+    }
+}
+
+/* The longest list, in items, or str, in bytes, that repetition may
+ * build. Anything longer is refused up front instead of being allocated,
+ * which could bring the whole process down. */
+const MAX_REPEAT_LEN: usize = 1 << 24;
+
+/* How many times a sequence of 'len' items is repeated by 'seq * count'.
+ * Like Python, a negative count gives an empty sequence. */
+fn repeat_count(len: usize, count: i32) -> Result<usize, InterpreterError> {
+    let count = count.max(0) as usize;
+    match len.checked_mul(count) {
+        Some(total) if total <= MAX_REPEAT_LEN => Ok(count),
+        _ => Err(InterpreterError::ValueError(String::from(
+            "repeated sequence is too long",
+        ))),
+    }
+}
+
+fn repeat_list(items: &[EvalResult], count: i32) -> Result<EvalResult, InterpreterError> {
+    let count = repeat_count(items.len(), count)?;
+    Ok(EvalResult::List(
+        items
+            .iter()
+            .cycle()
+            .take(items.len() * count)
+            .cloned()
+            .collect(),
+    ))
+}
+
+fn eval_mul(lhs_value: EvalResult, rhs_value: EvalResult) -> Result<EvalResult, InterpreterError> {
+    match (lhs_value, rhs_value) {
+        (EvalResult::CInt(lhs), EvalResult::CInt(rhs)) => int_result(lhs.checked_mul(rhs)),
+        (EvalResult::CReal(lhs), EvalResult::CReal(rhs)) => Ok(EvalResult::CReal(lhs * rhs)),
+        (EvalResult::CInt(lhs), EvalResult::CReal(rhs)) => Ok(EvalResult::CReal(lhs as f32 * rhs)),
+        (EvalResult::CReal(lhs), EvalResult::CInt(rhs)) => Ok(EvalResult::CReal(lhs * rhs as f32)),
+        (EvalResult::CInt(lhs), EvalResult::Bool(rhs)) => int_result(lhs.checked_mul(rhs as i32)),
+        (EvalResult::CReal(lhs), EvalResult::Bool(rhs)) => {
+            Ok(EvalResult::CReal(lhs * (rhs as i32) as f32))
+        }
+        (EvalResult::Bool(lhs), EvalResult::CInt(rhs)) => int_result((lhs as i32).checked_mul(rhs)),
+        (EvalResult::Bool(lhs), EvalResult::CReal(rhs)) => {
+            Ok(EvalResult::CReal((lhs as i32) as f32 * rhs))
+        }
+        (EvalResult::Bool(lhs), EvalResult::Bool(rhs)) => {
+            int_result((lhs as i32).checked_mul(rhs as i32))
+        }
+        (lhs @ EvalResult::None, rhs) | (lhs, rhs @ EvalResult::None) => {
+            Err(unsupported_operand("*", &lhs, &rhs))
+        }
+        (EvalResult::List(lhs), EvalResult::CInt(rhs)) => repeat_list(&lhs, rhs),
+        (EvalResult::CInt(lhs), EvalResult::List(rhs)) => repeat_list(&rhs, lhs),
+        (EvalResult::List(lhs), EvalResult::Bool(rhs)) => repeat_list(&lhs, rhs as i32),
+        (EvalResult::Bool(lhs), EvalResult::List(rhs)) => repeat_list(&rhs, lhs as i32),
+        (EvalResult::List(_), _) => Err(InterpreterError::TypeError(String::from(
+            "Cannot multiply list by non-integer value",
+        ))),
+        (_, EvalResult::List(_)) => Err(InterpreterError::TypeError(String::from(
+            "Cannot multiply list by non-integer value",
+        ))),
+        (EvalResult::CString(lhs), EvalResult::CInt(rhs)) => Ok(EvalResult::CString(
+            lhs.repeat(repeat_count(lhs.len(), rhs)?),
+        )),
+        (EvalResult::CInt(lhs), EvalResult::CString(rhs)) => Ok(EvalResult::CString(
+            rhs.repeat(repeat_count(rhs.len(), lhs)?),
+        )),
+        (EvalResult::CString(_), _) => Err(InterpreterError::TypeError(String::from(
+            "Cannot multiply str by non-integer value",
+        ))),
+        (_, EvalResult::CString(_)) => Err(InterpreterError::TypeError(String::from(
+            "Cannot multiply str by non-integer value",
+        ))),
+        (EvalResult::Func(_), _) | (_, EvalResult::Func(_)) => Err(InterpreterError::TypeError(
+            String::from("Mul not supported for function"),
+        )),
+        (EvalResult::Dict(_), _) | (_, EvalResult::Dict(_)) => Err(InterpreterError::TypeError(
+            String::from("Mul not supported for dict"),
+        )),
+        (EvalResult::Set(_), _) | (_, EvalResult::Set(_)) => Err(InterpreterError::TypeError(
+            String::from("Mul not supported for set"),
+        )),
+        (EvalResult::Tuple(_), _) | (_, EvalResult::Tuple(_)) => Err(InterpreterError::TypeError(
+            String::from("Mul not supported for tuple"),
+        )),
+    }
+}
+
+/* The 0.0 patterns also catch -0.0, as float patterns compare with '=='.
+ * A NaN can still come out of operands that are NaN or infinite already,
+ * and is reported rather than passed on. */
+fn eval_div(lhs_value: EvalResult, rhs_value: EvalResult) -> Result<EvalResult, InterpreterError> {
+    let result = match (lhs_value, rhs_value) {
+        (EvalResult::CInt(lhs), EvalResult::CInt(rhs)) => match rhs {
+            0 => Err(InterpreterError::DivisionByZero),
+            _ => Ok(EvalResult::CReal(lhs as f32 / rhs as f32)),
+        },
+        (EvalResult::CReal(lhs), EvalResult::CReal(rhs)) => match rhs {
+            0.0 => Err(InterpreterError::DivisionByZero),
+            _ => Ok(EvalResult::CReal(lhs / rhs)),
+        },
+        (EvalResult::CInt(lhs), EvalResult::CReal(rhs)) => match rhs {
+            0.0 => Err(InterpreterError::DivisionByZero),
+            _ => Ok(EvalResult::CReal(lhs as f32 / rhs)),
+        },
+        (EvalResult::CReal(lhs), EvalResult::CInt(rhs)) => match rhs {
+            0 => Err(InterpreterError::DivisionByZero),
+            _ => Ok(EvalResult::CReal(lhs / rhs as f32)),
+        },
+        (EvalResult::CInt(lhs), EvalResult::Bool(rhs)) => match rhs {
+            false => Err(InterpreterError::DivisionByZero),
+            _ => Ok(EvalResult::CReal(lhs as f32 / (rhs as i32) as f32)),
+        },
+        (EvalResult::CReal(lhs), EvalResult::Bool(rhs)) => match rhs {
+            false => Err(InterpreterError::DivisionByZero),
+            _ => Ok(EvalResult::CReal(lhs / (rhs as i32) as f32)),
+        },
+        (EvalResult::Bool(lhs), EvalResult::CInt(rhs)) => match rhs {
+            0 => Err(InterpreterError::DivisionByZero),
+            _ => Ok(EvalResult::CReal((lhs as i32) as f32 / rhs as f32)),
+        },
+        (EvalResult::Bool(lhs), EvalResult::CReal(rhs)) => match rhs {
+            0.0 => Err(InterpreterError::DivisionByZero),
+            _ => Ok(EvalResult::CReal((lhs as i32) as f32 / rhs)),
+        },
+        (EvalResult::Bool(lhs), EvalResult::Bool(rhs)) => match rhs {
+            false => Err(InterpreterError::DivisionByZero),
+            _ => Ok(EvalResult::CReal((lhs as i32) as f32 / (rhs as i32) as f32)),
+        },
+        (lhs @ EvalResult::None, rhs) | (lhs, rhs @ EvalResult::None) => {
+            Err(unsupported_operand("/", &lhs, &rhs))
+        }
+        (EvalResult::List(_), _) => Err(InterpreterError::TypeError(String::from(
+            "Div not supported for list",
+        ))),
+        (_, EvalResult::List(_)) => Err(InterpreterError::TypeError(String::from(
+            "Div not supported for list",
+        ))),
+        (EvalResult::CString(_), _) => Err(InterpreterError::TypeError(String::from(
+            "Div not supported for str",
+        ))),
+        (_, EvalResult::CString(_)) => Err(InterpreterError::TypeError(String::from(
+            "Div not supported for str",
+        ))),
+        (EvalResult::Func(_), _) | (_, EvalResult::Func(_)) => Err(InterpreterError::TypeError(
+            String::from("Div not supported for function"),
+        )),
+        (EvalResult::Dict(_), _) | (_, EvalResult::Dict(_)) => Err(InterpreterError::TypeError(
+            String::from("Div not supported for dict"),
+        )),
+        (EvalResult::Set(_), _) | (_, EvalResult::Set(_)) => Err(InterpreterError::TypeError(
+            String::from("Div not supported for set"),
+        )),
+        (EvalResult::Tuple(_), _) | (_, EvalResult::Tuple(_)) => Err(InterpreterError::TypeError(
+            String::from("Div not supported for tuple"),
+        )),
+    };
+    match result {
+        Ok(EvalResult::CReal(v)) if v.is_nan() => Err(InterpreterError::ValueError(String::from(
+            "Division produced NaN",
+        ))),
+        result => result,
+    }
+}
+
+fn eval_floor_div(
+    lhs_value: EvalResult,
+    rhs_value: EvalResult,
+) -> Result<EvalResult, InterpreterError> {
+    match (lhs_value, rhs_value) {
+        (EvalResult::CInt(lhs), EvalResult::CInt(rhs)) => match rhs {
+            0 => Err(InterpreterError::DivisionByZero),
+            _ => floor_div(lhs, rhs).map(EvalResult::CInt),
+        },
+        (EvalResult::CReal(lhs), EvalResult::CReal(rhs)) => match rhs {
+            0.0 => Err(InterpreterError::DivisionByZero),
+            _ => Ok(EvalResult::CReal((lhs / rhs).floor())),
+        },
+        (EvalResult::CInt(lhs), EvalResult::CReal(rhs)) => match rhs {
+            0.0 => Err(InterpreterError::DivisionByZero),
+            _ => Ok(EvalResult::CReal((lhs as f32 / rhs).floor())),
+        },
+        (EvalResult::CReal(lhs), EvalResult::CInt(rhs)) => match rhs {
+            0 => Err(InterpreterError::DivisionByZero),
+            _ => Ok(EvalResult::CReal((lhs / rhs as f32).floor())),
+        },
+        (EvalResult::CInt(lhs), EvalResult::Bool(rhs)) => match rhs {
+            false => Err(InterpreterError::DivisionByZero),
+            _ => floor_div(lhs, rhs as i32).map(EvalResult::CInt),
+        },
+        (EvalResult::CReal(lhs), EvalResult::Bool(rhs)) => match rhs {
+            false => Err(InterpreterError::DivisionByZero),
+            _ => Ok(EvalResult::CReal((lhs / (rhs as i32) as f32).floor())),
+        },
+        (EvalResult::Bool(lhs), EvalResult::CInt(rhs)) => match rhs {
+            0 => Err(InterpreterError::DivisionByZero),
+            _ => floor_div(lhs as i32, rhs).map(EvalResult::CInt),
+        },
+        (EvalResult::Bool(lhs), EvalResult::CReal(rhs)) => match rhs {
+            0.0 => Err(InterpreterError::DivisionByZero),
+            _ => Ok(EvalResult::CReal(((lhs as i32) as f32 / rhs).floor())),
+        },
+        (EvalResult::Bool(lhs), EvalResult::Bool(rhs)) => match rhs {
+            false => Err(InterpreterError::DivisionByZero),
+            _ => floor_div(lhs as i32, rhs as i32).map(EvalResult::CInt),
+        },
+        (lhs @ EvalResult::None, rhs) | (lhs, rhs @ EvalResult::None) => {
+            Err(unsupported_operand("//", &lhs, &rhs))
+        }
+        (EvalResult::List(_), _) => Err(InterpreterError::TypeError(String::from(
+            "FloorDiv not supported for list",
+        ))),
+        (_, EvalResult::List(_)) => Err(InterpreterError::TypeError(String::from(
+            "FloorDiv not supported for list",
+        ))),
+        (EvalResult::CString(_), _) => Err(InterpreterError::TypeError(String::from(
+            "FloorDiv not supported for str",
+        ))),
+        (_, EvalResult::CString(_)) => Err(InterpreterError::TypeError(String::from(
+            "FloorDiv not supported for str",
+        ))),
+        (EvalResult::Func(_), _) | (_, EvalResult::Func(_)) => Err(InterpreterError::TypeError(
+            String::from("FloorDiv not supported for function"),
+        )),
+        (EvalResult::Dict(_), _) | (_, EvalResult::Dict(_)) => Err(InterpreterError::TypeError(
+            String::from("FloorDiv not supported for dict"),
+        )),
+        (EvalResult::Set(_), _) | (_, EvalResult::Set(_)) => Err(InterpreterError::TypeError(
+            String::from("FloorDiv not supported for set"),
+        )),
+        (EvalResult::Tuple(_), _) | (_, EvalResult::Tuple(_)) => Err(InterpreterError::TypeError(
+            String::from("FloorDiv not supported for tuple"),
+        )),
+    }
+}
+
+/* Ints raised to a non-negative int stay ints; any other mix of numbers
+ * gives a real. Bools count as 0 and 1. */
+pub(crate) fn eval_pow(
+    lhs_value: EvalResult,
+    rhs_value: EvalResult,
+) -> Result<EvalResult, InterpreterError> {
+    let as_int = |value: &EvalResult| match value {
+        EvalResult::CInt(v) => Some(*v),
+        EvalResult::Bool(v) => Some(*v as i32),
+        _ => None,
+    };
+    match (as_int(&lhs_value), as_int(&rhs_value)) {
+        (Some(0), Some(exp)) if exp < 0 => Err(InterpreterError::DivisionByZero),
+        (Some(base), Some(exp)) if exp >= 0 => match int_pow(base, exp as u32) {
+            Some(result) => Ok(EvalResult::CInt(result)),
+            None => Err(InterpreterError::IntegerOverflow),
+        },
+        _ => match (as_number(&lhs_value), as_number(&rhs_value)) {
+            (Some(base), Some(exp)) if base == 0.0 && exp < 0.0 => {
+                Err(InterpreterError::DivisionByZero)
+            }
+            (Some(base), Some(exp)) => Ok(EvalResult::CReal(base.powf(exp) as f32)),
+            _ => Err(unsupported_operand("**", &lhs_value, &rhs_value)),
+        },
+    }
+}
+
+/* '%' as Python has it: a remainder other than zero takes the sign of
+ * the divisor, so -7 % 3 is 2 and 7 % -3 is -2, where Rust's '%' follows
+ * the dividend. Ints and bools stay ints; a real makes the result real. */
+fn eval_mod(lhs_value: EvalResult, rhs_value: EvalResult) -> Result<EvalResult, InterpreterError> {
+    let as_int = |value: &EvalResult| match value {
+        EvalResult::CInt(v) => Some(*v),
+        EvalResult::Bool(v) => Some(*v as i32),
+        _ => None,
+    };
+    let as_real = |value: &EvalResult| match value {
+        EvalResult::CReal(v) => Some(*v),
+        value => as_int(value).map(|v| v as f32),
+    };
+
+    if let (Some(lhs), Some(rhs)) = (as_int(&lhs_value), as_int(&rhs_value)) {
+        if rhs == 0 {
+            return Err(InterpreterError::DivisionByZero);
+        }
+        /* i32::MIN % -1 overflows in Rust, but the remainder is 0. */
+        let rem = lhs.wrapping_rem(rhs);
+        return Ok(EvalResult::CInt(if rem != 0 && (rem < 0) != (rhs < 0) {
+            rem + rhs
+        } else {
+            rem
+        }));
+    }
+    match (as_real(&lhs_value), as_real(&rhs_value)) {
+        (Some(_), Some(0.0)) => Err(InterpreterError::DivisionByZero),
+        (Some(lhs), Some(rhs)) => {
+            let rem = lhs % rhs;
+            Ok(EvalResult::CReal(
+                if rem != 0.0 && (rem < 0.0) != (rhs < 0.0) {
+                    rem + rhs
+                } else {
+                    rem
+                },
+            ))
+        }
+        _ => Err(unsupported_operand("%", &lhs_value, &rhs_value)),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BitOp {
+    And,
+    Or,
+    Xor,
+    Shl,
+    Shr,
+}
+
+/* The bitwise operators take ints, with bools counting as 0 and 1, and
+ * always give an int. Shifting left past the range of an int is an error
+ * rather than a silent wrap. */
+fn eval_bitwise(
+    op: BitOp,
+    lhs_value: EvalResult,
+    rhs_value: EvalResult,
+) -> Result<EvalResult, InterpreterError> {
+    let as_int = |value: &EvalResult| match value {
+        EvalResult::CInt(v) => Some(*v),
+        EvalResult::Bool(v) => Some(*v as i32),
+        _ => None,
+    };
+    let (Some(lhs), Some(rhs)) = (as_int(&lhs_value), as_int(&rhs_value)) else {
+        return Err(InterpreterError::TypeError(String::from(
+            "unsupported operand type(s) for bitwise op",
+        )));
+    };
+
+    match op {
+        BitOp::And => Ok(EvalResult::CInt(lhs & rhs)),
+        BitOp::Or => Ok(EvalResult::CInt(lhs | rhs)),
+        BitOp::Xor => Ok(EvalResult::CInt(lhs ^ rhs)),
+        BitOp::Shl | BitOp::Shr if rhs < 0 => Err(InterpreterError::ValueError(String::from(
+            "negative shift count",
+        ))),
+        BitOp::Shl => i32::try_from((lhs as i64) << rhs.min(32))
+            .map(EvalResult::CInt)
+            .map_err(|_| InterpreterError::IntegerOverflow),
+        BitOp::Shr => Ok(EvalResult::CInt(lhs >> rhs.min(31))),
+    }
+}
+
+/* Exponentiation by squaring: O(log exp) multiplications, giving up as
+ * soon as an intermediate result no longer fits in an int. */
+fn int_pow(base: i32, exp: u32) -> Option<i32> {
+    let mut result: i32 = 1;
+    let mut base = base;
+    let mut exp = exp;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result.checked_mul(base)?;
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = base.checked_mul(base)?;
+        }
+    }
+    Some(result)
+}
+
+/* Whether a value counts as true in a condition: zero, empty containers
+ * and None are false, everything else is true. */
+pub(crate) fn is_truthy(value: &EvalResult) -> bool {
+    match value {
+        EvalResult::CInt(v) => *v != 0,
+        EvalResult::CReal(v) => *v != 0.0,
+        EvalResult::Bool(v) => *v,
+        EvalResult::CString(v) => !v.is_empty(),
+        EvalResult::List(v) | EvalResult::Tuple(v) => !v.is_empty(),
+        EvalResult::Dict(v) => !v.is_empty(),
+        EvalResult::Set(v) => !v.is_empty(),
+        EvalResult::Func(_) => true,
+        EvalResult::None => false,
+    }
+}
+
+/* '_' as an assignment or loop target throws the value away instead of
+ * binding a variable by that name. */
+fn is_discard(name: &str) -> bool {
+    name == "_"
+}
+
+/* Splits a value into exactly 'count' items for multiple assignment and
+ * for loops with several targets. */
+fn unpack(value: EvalResult, count: usize) -> Result<Vec<EvalResult>, InterpreterError> {
+    let items = match value {
+        EvalResult::List(_) | EvalResult::Tuple(_) | EvalResult::CString(_) => {
+            into_iter_items(value)?
+        }
+        _ => {
+            return Err(InterpreterError::TypeError(format!(
+                "cannot unpack non-iterable {} object",
+                value.type_name()
+            )))
+        }
+    };
+    match items.len().cmp(&count) {
+        Ordering::Greater => Err(InterpreterError::ValueError(format!(
+            "too many values to unpack (expected {})",
+            count
+        ))),
+        Ordering::Less => Err(InterpreterError::ValueError(format!(
+            "too few values to unpack (expected {}, got {})",
+            count,
+            items.len()
+        ))),
+        Ordering::Equal => Ok(items),
+    }
+}
+
+/* Expands an iterable value into the items a 'for' loop binds in turn:
+ * the elements of a list or tuple, the characters of a string or the keys
+ * of a dict, in insertion order. */
+pub(crate) fn into_iter_items(value: EvalResult) -> Result<Vec<EvalResult>, InterpreterError> {
+    match value {
+        EvalResult::List(items) | EvalResult::Tuple(items) => Ok(items),
+        EvalResult::CString(v) => Ok(v
+            .chars()
+            .map(|c| EvalResult::CString(c.to_string()))
+            .collect()),
+        EvalResult::Dict(dict) => Ok(dict
+            .iter()
+            .map(|(key, _)| EvalResult::from(key.clone()))
+            .collect()),
+        EvalResult::Set(set) => Ok(set.iter().cloned().map(EvalResult::from).collect()),
+        _ => Err(InterpreterError::TypeError(String::from(
+            "Expression must be an iterable object",
+        ))),
+    }
+}
+
+fn set_item(
+    target: EvalResult,
+    index: &EvalResult,
+    value: EvalResult,
+) -> Result<EvalResult, InterpreterError> {
+    match (target, index) {
+        (EvalResult::Dict(mut dict), _) => {
+            dict.insert(HashKey::from_value(index)?, value);
+            Ok(EvalResult::Dict(dict))
+        }
+        (EvalResult::List(mut items), EvalResult::CInt(position)) => {
+            match sequence_position(*position, items.len()) {
+                Some(position) => {
+                    items[position] = value;
+                    Ok(EvalResult::List(items))
+                }
+                None => Err(InterpreterError::IndexError(String::from(
+                    "list assignment index out of range",
+                ))),
+            }
+        }
+        (EvalResult::List(_), _) => Err(InterpreterError::TypeError(format!(
+            "list indices must be integers, not {}",
+            index.type_name()
+        ))),
+        (target, _) => Err(InterpreterError::TypeError(format!(
+            "'{}' object does not support item assignment",
+            target.type_name()
+        ))),
+    }
+}
+
+/* Looks up 'target[index]'. Sequences take an integer position, counted
+ * from the end when negative, while dicts take any hashable key. */
+fn index_value(target: EvalResult, index: &EvalResult) -> Result<EvalResult, InterpreterError> {
+    match (target, index) {
+        (EvalResult::Dict(dict), _) => match dict.get(&HashKey::from_value(index)?) {
+            Some(value) => Ok(value.clone()),
+            None => Err(InterpreterError::KeyError(index.repr())),
+        },
+        (EvalResult::List(items), EvalResult::CInt(position)) => {
+            match sequence_position(*position, items.len()) {
+                Some(position) => Ok(items[position].clone()),
+                None => Err(InterpreterError::IndexError(String::from(
+                    "list index out of range",
+                ))),
+            }
+        }
+        (EvalResult::Tuple(items), EvalResult::CInt(position)) => {
+            match sequence_position(*position, items.len()) {
+                Some(position) => Ok(items[position].clone()),
+                None => Err(InterpreterError::IndexError(String::from(
+                    "tuple index out of range",
+                ))),
+            }
+        }
+        (EvalResult::CString(v), EvalResult::CInt(position)) => {
+            let chars: Vec<char> = v.chars().collect();
+            match sequence_position(*position, chars.len()) {
+                Some(position) => Ok(EvalResult::CString(chars[position].to_string())),
+                None => Err(InterpreterError::IndexError(String::from(
+                    "string index out of range",
+                ))),
+            }
+        }
+        (target @ (EvalResult::List(_) | EvalResult::Tuple(_) | EvalResult::CString(_)), _) => {
+            Err(InterpreterError::TypeError(format!(
+                "{} indices must be integers, not {}",
+                target.type_name(),
+                index.type_name()
+            )))
+        }
+        (target, _) => Err(InterpreterError::TypeError(format!(
+            "'{}' object is not subscriptable",
+            target.type_name()
+        ))),
+    }
+}
+
+fn sequence_position(position: i32, len: usize) -> Option<usize> {
+    let position = if position < 0 {
+        len as i64 + position as i64
+    } else {
+        position as i64
+    };
+    if (0..len as i64).contains(&position) {
+        Some(position as usize)
+    } else {
+        None
+    }
+}
+
+/* The list a range produces, shared by the 'range' expression and the
+ * 'range' builtin. */
+pub(crate) fn range(
+    start: EvalResult,
+    end: EvalResult,
+    step: EvalResult,
+) -> Result<EvalResult, InterpreterError> {
+    let srt_int = range_bound(start)?;
+    let end_int = range_bound(end)?;
+    let incr_int = range_bound(step)?;
+
+    /* The stepped ranges know their exact length, so collecting
+     * them allocates the result once. */
+    match incr_int.signum() {
+        0 => Err(InterpreterError::ValueError(String::from(
+            "Increment cannot be zero",
+        ))),
+        /* A descending range counts down from the start to just above the
+         * end. The bound is only moved when the range isn't empty, so it
+         * can't overflow. */
+        -1 if srt_int <= end_int => Ok(EvalResult::List(vec![])),
+        -1 => Ok(EvalResult::List(
+            (end_int + 1..=srt_int)
+                .rev()
+                .step_by(incr_int.unsigned_abs() as usize)
+                .map(EvalResult::CInt)
+                .collect(),
+        )),
+        _ => Ok(EvalResult::List(
+            (srt_int..end_int)
+                .step_by(incr_int as usize)
+                .map(EvalResult::CInt)
+                .collect(),
+        )),
+    }
+}
+
+/* An inclusive range counts from the start while it has not passed the
+ * end, in the direction of the step. The count stops when the next value
+ * would not fit in an int, so ranges ending at i32::MAX or i32::MIN work. */
+pub(crate) fn range_inclusive(
+    start: EvalResult,
+    end: EvalResult,
+    step: EvalResult,
+) -> Result<EvalResult, InterpreterError> {
+    let srt_int = range_bound(start)?;
+    let end_int = range_bound(end)?;
+    let incr_int = range_bound(step)?;
+    if incr_int == 0 {
+        return Err(InterpreterError::ValueError(String::from(
+            "Increment cannot be zero",
+        )));
+    }
+
+    Ok(EvalResult::List(
+        std::iter::successors(Some(srt_int), |i| i.checked_add(incr_int))
+            .take_while(|i| {
+                if incr_int > 0 {
+                    *i <= end_int
+                } else {
+                    *i >= end_int
+                }
+            })
+            .map(EvalResult::CInt)
+            .collect(),
+    ))
+}
+
+/* Range bounds must be integers. Bools are accepted as 0 and 1 on
+ * purpose: they already count as integers in arithmetic, and Python's own
+ * range(True, 5) gives [1, 2, 3, 4], so rejecting them here would be the
+ * surprising choice. */
+fn range_bound(value: EvalResult) -> Result<i32, InterpreterError> {
+    match value {
+        EvalResult::CInt(v) => Ok(v),
+        EvalResult::Bool(v) => Ok(v as i32),
+        _ => Err(InterpreterError::TypeError(String::from(
+            "Parameters cannot be converted to integer",
+        ))),
+    }
+}
+
+/* Integer division rounding towards negative infinity, as Python's '//'
+ * does, instead of towards zero like Rust's '/'. The one quotient that
+ * does not fit, i32::MIN // -1, is an overflow. The divisor is never
+ * zero here. */
+fn floor_div(lhs: i32, rhs: i32) -> Result<i32, InterpreterError> {
+    match (lhs.checked_div(rhs), lhs.checked_rem(rhs)) {
+        (Some(quotient), Some(rem)) if rem != 0 && (lhs < 0) != (rhs < 0) => Ok(quotient - 1),
+        (Some(quotient), Some(_)) => Ok(quotient),
+        _ => Err(InterpreterError::IntegerOverflow),
+    }
+}
+
+fn as_number(value: &EvalResult) -> Option<f64> {
+    match value {
+        EvalResult::CInt(v) => Some(*v as f64),
+        EvalResult::CReal(v) => Some(*v as f64),
+        EvalResult::Bool(v) => Some(*v as i32 as f64),
+        _ => None,
+    }
+}
+
+/* Value equality as seen by '=='. Numbers compare by value across int,
+ * real and bool, so -0.0 == 0.0 holds, and mismatched types are simply
+ * unequal rather than an error. Containers compare item by item, however
+ * deeply they nest, and functions by identity.
+ *
+ * NaN is unequal to everything, itself included, so a list holding a NaN
+ * is never equal to anything either. Python would call [x] == [x] true
+ * for a NaN x, because it checks identity before equality; values here
+ * have no identity to check. */
+pub(crate) fn values_equal(lhs: &EvalResult, rhs: &EvalResult) -> bool {
+    match (lhs, rhs) {
+        (EvalResult::CString(lhs), EvalResult::CString(rhs)) => lhs == rhs,
+        (EvalResult::List(lhs), EvalResult::List(rhs))
+        | (EvalResult::Tuple(lhs), EvalResult::Tuple(rhs)) => {
+            lhs.len() == rhs.len() && lhs.iter().zip(rhs).all(|(l, r)| values_equal(l, r))
+        }
+        (EvalResult::Set(lhs), EvalResult::Set(rhs)) => lhs == rhs,
+        (EvalResult::Dict(lhs), EvalResult::Dict(rhs)) => {
+            lhs.len() == rhs.len()
+                && lhs.iter().all(|(key, l)| match rhs.get(key) {
+                    Some(r) => values_equal(l, r),
+                    None => false,
+                })
+        }
+        (EvalResult::None, EvalResult::None) => true,
+        (EvalResult::Func(lhs), EvalResult::Func(rhs)) => Rc::ptr_eq(lhs, rhs),
+        _ => match (as_number(lhs), as_number(rhs)) {
+            (Some(lhs), Some(rhs)) => lhs == rhs,
+            _ => false,
+        },
+    }
+}
+
+/* The binary operators whose operands are already evaluated, picked by
+ * the node they came from. */
+fn eval_binary(
+    exp: &Expression,
+    lhs: EvalResult,
+    rhs: EvalResult,
+) -> Result<EvalResult, InterpreterError> {
+    match exp {
+        Expression::Add(_, _) => eval_add(lhs, rhs),
+        Expression::Sub(_, _) => eval_sub(lhs, rhs),
+        Expression::Mul(_, _) => eval_mul(lhs, rhs),
+        Expression::Div(_, _) => eval_div(lhs, rhs),
+        Expression::FloorDiv(_, _) => eval_floor_div(lhs, rhs),
+        Expression::Mod(_, _) => eval_mod(lhs, rhs),
+        Expression::Pow(_, _) => eval_pow(lhs, rhs),
+        Expression::BitAnd(_, _) => eval_bitwise(BitOp::And, lhs, rhs),
+        Expression::BitOr(_, _) => eval_bitwise(BitOp::Or, lhs, rhs),
+        Expression::BitXor(_, _) => eval_bitwise(BitOp::Xor, lhs, rhs),
+        Expression::Shl(_, _) => eval_bitwise(BitOp::Shl, lhs, rhs),
+        Expression::Shr(_, _) => eval_bitwise(BitOp::Shr, lhs, rhs),
+        Expression::Eq(_, _) => eval_comparison(CmpOp::Eq, lhs, rhs),
+        Expression::NotEq(_, _) => eval_comparison(CmpOp::NotEq, lhs, rhs),
+        Expression::Lt(_, _) => eval_comparison(CmpOp::Lt, lhs, rhs),
+        Expression::LtE(_, _) => eval_comparison(CmpOp::LtE, lhs, rhs),
+        Expression::Gt(_, _) => eval_comparison(CmpOp::Gt, lhs, rhs),
+        Expression::GtE(_, _) => eval_comparison(CmpOp::GtE, lhs, rhs),
+        Expression::Is(_, _) => eval_comparison(CmpOp::Is, lhs, rhs),
+        Expression::IsNot(_, _) => eval_comparison(CmpOp::IsNot, lhs, rhs),
+        Expression::In(_, _) => eval_comparison(CmpOp::In, lhs, rhs),
+        Expression::NotIn(_, _) => eval_comparison(CmpOp::NotIn, lhs, rhs),
+        _ => Err(InterpreterError::NotImplemented),
+    }
+}
+
+/* The comparison operators, membership and identity included. */
+fn eval_comparison(
+    op: CmpOp,
+    lhs: EvalResult,
+    rhs: EvalResult,
+) -> Result<EvalResult, InterpreterError> {
+    let result = match op {
+        CmpOp::Eq => values_equal(&lhs, &rhs),
+        CmpOp::NotEq => !values_equal(&lhs, &rhs),
+        CmpOp::Is => identical(&lhs, &rhs),
+        CmpOp::IsNot => !identical(&lhs, &rhs),
+        CmpOp::In => contains(&rhs, &lhs)?,
+        CmpOp::NotIn => !contains(&rhs, &lhs)?,
+        CmpOp::Lt => compare(&lhs, &rhs, op.symbol())? == Some(Ordering::Less),
+        CmpOp::LtE => matches!(
+            compare(&lhs, &rhs, op.symbol())?,
+            Some(Ordering::Less | Ordering::Equal)
+        ),
+        CmpOp::Gt => compare(&lhs, &rhs, op.symbol())? == Some(Ordering::Greater),
+        CmpOp::GtE => matches!(
+            compare(&lhs, &rhs, op.symbol())?,
+            Some(Ordering::Greater | Ordering::Equal)
+        ),
+    };
+    Ok(EvalResult::Bool(result))
+}
+
+/* Membership used by 'in' and 'not in': an equal item of a list or tuple,
+ * a substring of a str, or a key of a dict or set. */
+fn contains(container: &EvalResult, item: &EvalResult) -> Result<bool, InterpreterError> {
+    match (container, item) {
+        (EvalResult::List(items) | EvalResult::Tuple(items), _) => {
+            Ok(items.iter().any(|value| values_equal(value, item)))
+        }
+        (EvalResult::CString(text), EvalResult::CString(sub)) => Ok(text.contains(sub.as_str())),
+        (EvalResult::CString(_), _) => Err(InterpreterError::TypeError(format!(
+            "'in <string>' requires string as left operand, not {}",
+            item.type_name()
+        ))),
+        (EvalResult::Dict(dict), _) => Ok(dict.get(&HashKey::from_value(item)?).is_some()),
+        (EvalResult::Set(set), _) => Ok(set.contains(&HashKey::from_value(item)?)),
+        _ => Err(InterpreterError::TypeError(format!(
+            "argument of type '{}' is not iterable",
+            container.type_name()
+        ))),
+    }
+}
+
+/* Identity used by 'is' and 'is not'. None is only ever itself; values
+ * have no identity of their own in this language, so any other pair is
+ * compared by value. */
+fn identical(lhs: &EvalResult, rhs: &EvalResult) -> bool {
+    match (lhs, rhs) {
+        (EvalResult::None, EvalResult::None) => true,
+        (EvalResult::None, _) | (_, EvalResult::None) => false,
+        _ => values_equal(lhs, rhs),
+    }
+}
+
+/* Ordering used by '<', '<=', '>' and '>='. Returns None when the
+ * operands are unordered, which only happens for NaN.
+ *
+ * Strings compare by Unicode scalar value, which is what comparing their
+ * UTF-8 bytes gives, so ordering is case-sensitive: "A" < "a". */
+pub(crate) fn compare(
+    lhs: &EvalResult,
+    rhs: &EvalResult,
+    op: &str,
+) -> Result<Option<Ordering>, InterpreterError> {
+    match (lhs, rhs) {
+        (EvalResult::CString(lhs), EvalResult::CString(rhs)) => Ok(Some(lhs.cmp(rhs))),
+        _ => match (as_number(lhs), as_number(rhs)) {
+            (Some(lhs), Some(rhs)) => Ok(lhs.partial_cmp(&rhs)),
+            _ => Err(InterpreterError::TypeError(format!(
+                "'{}' not supported between instances of '{}' and '{}'",
+                op,
+                lhs.type_name(),
+                rhs.type_name()
+            ))),
+        },
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eval_constant_integer() {
+        let env = Environment::new();
+        let c10 = Expression::CInt(10);
+        let c20 = Expression::CInt(20);
+
+        assert_eq!(eval(&c10, &env), Ok(EvalResult::CInt(10)));
+        assert_eq!(eval(&c20, &env), Ok(EvalResult::CInt(20)));
+    }
+
+    #[test]
+    fn eval_constant_real() {
+        let env = Environment::new();
+        let c10_5 = Expression::CReal(10.5);
+        let c20_3 = Expression::CReal(20.3);
+
+        assert_eq!(eval(&c10_5, &env), Ok(EvalResult::CReal(10.5)));
+        assert_eq!(eval(&c20_3, &env), Ok(EvalResult::CReal(20.3)))
+    }
+
+    #[test]
+    fn eval_constant_bool() {
+        let env = Environment::new();
+        let ctrue = Expression::Bool(true);
+        let cfalse = Expression::Bool(false);
+
+        assert_eq!(eval(&ctrue, &env), Ok(EvalResult::Bool(true)));
+        assert_eq!(eval(&cfalse, &env), Ok(EvalResult::Bool(false)))
+    }
+
+    #[test]
+    fn eval_constant_list() {
+        let env = Environment::new();
+        let cl1 = Expression::List(vec![Expression::CInt(1), Expression::CInt(2)]);
+        let cl2 = Expression::List(vec![Expression::CReal(23.3), Expression::CReal(0.00)]);
+
+        assert_eq!(
+            eval(&cl1, &env),
+            Ok(EvalResult::List(vec![
+                EvalResult::CInt(1),
+                EvalResult::CInt(2)
+            ]))
+        );
+        assert_eq!(
+            eval(&cl2, &env),
+            Ok(EvalResult::List(vec![
+                EvalResult::CReal(23.3),
+                EvalResult::CReal(0.00)
+            ]))
+        );
+    }
+
+    #[test]
+    fn eval_empty_list() {
+        let env = Environment::new();
+        let empty = Expression::List(vec![]);
+
+        assert_eq!(eval(&empty, &env), Ok(EvalResult::List(vec![])));
+    }
+
+    #[test]
+    fn eval_mixed_numeric_list() {
+        let env = Environment::new();
+        let mixed = Expression::List(vec![Expression::CInt(1), Expression::CReal(2.5)]);
+
+        assert_eq!(
+            eval(&mixed, &env),
+            Ok(EvalResult::List(vec![
+                EvalResult::CReal(1.0),
+                EvalResult::CReal(2.5)
+            ]))
+        );
+    }
+
+    #[test]
+    fn eval_list_of_list() {
+        let env = Environment::new();
+        let cl1 = Expression::List(vec![Expression::List(vec![Expression::CInt(1)])]);
+
+        assert_eq!(
+            eval(&cl1, &env),
+            Ok(EvalResult::List(vec![EvalResult::List(vec![
+                EvalResult::CInt(1)
+            ])]))
+        );
+    }
+
+    #[test]
+    fn eval_add_integers_1() {
+        let env = Environment::new();
+        let c10 = Expression::CInt(10);
+        let c20 = Expression::CInt(20);
+        let add1 = Expression::Add(Box::new(c10), Box::new(c20));
+        assert_eq!(eval(&add1, &env), Ok(EvalResult::CInt(30)));
+    }
+
+    #[test]
+    fn eval_add_integers_2() {
+        let env = Environment::new();
+        let c10 = Expression::CInt(10);
+        let c20 = Expression::CInt(20);
+        let c30 = Expression::CInt(30);
+        let add1 = Expression::Add(Box::new(c10), Box::new(c20));
+        let add2 = Expression::Add(Box::new(add1), Box::new(c30));
+        assert_eq!(eval(&add2, &env), Ok(EvalResult::CInt(60)));
+    }
+
+    #[test]
+    fn eval_add_reals_1() {
+        let env = Environment::new();
+        let c10_5 = Expression::CReal(10.5);
+        let c20_3 = Expression::CReal(20.3);
+        let add1 = Expression::Add(Box::new(c10_5), Box::new(c20_3));
+        assert_eq!(eval(&add1, &env), Ok(EvalResult::CReal(30.8)));
+    }
+
+    #[test]
+    fn eval_add_reals_2() {
+        let env = Environment::new();
+        let c10_5 = Expression::CReal(10.5);
+        let c20_3 = Expression::CReal(20.3);
+        let c30_1 = Expression::CReal(30.1);
+        let add1 = Expression::Add(Box::new(c10_5), Box::new(c20_3));
+        let add2 = Expression::Add(Box::new(add1), Box::new(c30_1));
+        assert_eq!(eval(&add2, &env), Ok(EvalResult::CReal(60.9)));
+    }
+
+    #[test]
+    fn eval_add_integer_real() {
+        let env = Environment::new();
+        let c10 = Expression::CInt(10);
+        let c20_3 = Expression::CReal(20.3);
+        let add1 = Expression::Add(Box::new(c10), Box::new(c20_3));
+        assert_eq!(eval(&add1, &env), Ok(EvalResult::CReal(30.3)));
+    }
+
+    #[test]
     fn eval_add_bools_1() {
-        let env = HashMap::new();
+        let env = Environment::new();
         let ctrue = Expression::Bool(true);
         let cfalse = Expression::Bool(false);
         let add1 = Expression::Add(Box::new(ctrue), Box::new(cfalse));
@@ -672,418 +2688,4821 @@ mod tests {
     }
 
     #[test]
-    fn eval_add_bools_2() {
-        let env = HashMap::new();
-        let ctrue1 = Expression::Bool(true);
-        let ctrue2 = Expression::Bool(true);
-        let add1 = Expression::Add(Box::new(ctrue1), Box::new(ctrue2));
-        assert_eq!(eval(&add1, &env), Ok(EvalResult::CInt(2)));
+    fn eval_add_bools_2() {
+        let env = Environment::new();
+        let ctrue1 = Expression::Bool(true);
+        let ctrue2 = Expression::Bool(true);
+        let add1 = Expression::Add(Box::new(ctrue1), Box::new(ctrue2));
+        assert_eq!(eval(&add1, &env), Ok(EvalResult::CInt(2)));
+    }
+
+    #[test]
+    fn eval_add_num_bool() {
+        let env = Environment::new();
+        let c10 = Expression::CInt(10);
+        let ctrue2 = Expression::Bool(true);
+        let add1 = Expression::Add(Box::new(c10), Box::new(ctrue2));
+        assert_eq!(eval(&add1, &env), Ok(EvalResult::CInt(11)));
+    }
+
+    #[test]
+    fn eval_add_lists() {
+        let env = Environment::new();
+        let l1 = Expression::List(vec![Expression::CInt(0), Expression::CInt(1)]);
+        let l2 = Expression::List(vec![Expression::CInt(2), Expression::CInt(3)]);
+        let add = Expression::Add(Box::new(l1), Box::new(l2));
+        assert_eq!(
+            eval(&add, &env),
+            Ok(EvalResult::List(vec![
+                EvalResult::CInt(0),
+                EvalResult::CInt(1),
+                EvalResult::CInt(2),
+                EvalResult::CInt(3)
+            ]))
+        );
+    }
+
+    #[test]
+    fn eval_multiply_list() {
+        let env = Environment::new();
+        let l1 = Expression::List(vec![Expression::CInt(0), Expression::CInt(1)]);
+        let l2 = Expression::List(vec![Expression::CInt(0), Expression::CInt(1)]);
+        let mul1 = Expression::Mul(Box::new(l1), Box::new(Expression::CInt(2)));
+        let mul2 = Expression::Mul(Box::new(l2), Box::new(Expression::CInt(0)));
+        assert_eq!(
+            eval(&mul1, &env),
+            Ok(EvalResult::List(vec![
+                EvalResult::CInt(0),
+                EvalResult::CInt(1),
+                EvalResult::CInt(0),
+                EvalResult::CInt(1)
+            ]))
+        );
+        assert_eq!(eval(&mul2, &env), Ok(EvalResult::List(vec![])));
+    }
+
+    #[test]
+    fn eval_repetition_too_long() {
+        /*
+         * > [0] * 2000000000        # refused, not allocated
+         * > "ab" * 2000000000       # refused, not allocated
+         * > [] * 2000000000         # []
+         */
+        let env = Environment::new();
+        let repeat = |seq: Expression| {
+            let count = Box::new(Expression::CInt(2000000000));
+            eval(&Expression::Mul(Box::new(seq), count), &env).map_err(|e| e.to_string())
+        };
+
+        assert_eq!(
+            repeat(Expression::List(vec![Expression::CInt(0)])),
+            Err(String::from("repeated sequence is too long"))
+        );
+        assert_eq!(
+            repeat(Expression::CString(String::from("ab"))),
+            Err(String::from("repeated sequence is too long"))
+        );
+        assert_eq!(
+            repeat(Expression::List(vec![])),
+            Ok(EvalResult::List(vec![]))
+        );
+    }
+
+    #[test]
+    fn eval_true_division() {
+        let env = Environment::new();
+        let div1 = Expression::Div(Box::new(Expression::CInt(7)), Box::new(Expression::CInt(2)));
+        let div2 = Expression::Div(Box::new(Expression::CInt(6)), Box::new(Expression::CInt(2)));
+        let div3 = Expression::Div(Box::new(Expression::CInt(7)), Box::new(Expression::CInt(0)));
+
+        assert_eq!(eval(&div1, &env), Ok(EvalResult::CReal(3.5)));
+        assert_eq!(eval(&div2, &env), Ok(EvalResult::CReal(3.0)));
+        assert_eq!(eval(&div3, &env), Err(InterpreterError::DivisionByZero));
+    }
+
+    #[test]
+    fn eval_floor_division() {
+        let env = Environment::new();
+        let fdiv1 =
+            Expression::FloorDiv(Box::new(Expression::CInt(7)), Box::new(Expression::CInt(2)));
+        let fdiv2 = Expression::FloorDiv(
+            Box::new(Expression::CInt(-7)),
+            Box::new(Expression::CInt(2)),
+        );
+        let fdiv3 = Expression::FloorDiv(
+            Box::new(Expression::CReal(7.5)),
+            Box::new(Expression::CInt(2)),
+        );
+        let fdiv4 =
+            Expression::FloorDiv(Box::new(Expression::CInt(7)), Box::new(Expression::CInt(0)));
+
+        assert_eq!(eval(&fdiv1, &env), Ok(EvalResult::CInt(3)));
+        assert_eq!(eval(&fdiv2, &env), Ok(EvalResult::CInt(-4)));
+        assert_eq!(eval(&fdiv3, &env), Ok(EvalResult::CReal(3.0)));
+        assert_eq!(eval(&fdiv4, &env), Err(InterpreterError::DivisionByZero));
+
+        /* The quotient 2147483648 does not fit in an int. */
+        let fdiv5 = Expression::FloorDiv(
+            Box::new(Expression::CInt(i32::MIN)),
+            Box::new(Expression::CInt(-1)),
+        );
+        let fdiv6 = Expression::FloorDiv(
+            Box::new(Expression::CInt(i32::MIN)),
+            Box::new(Expression::CInt(1)),
+        );
+        assert_eq!(eval(&fdiv5, &env), Err(InterpreterError::IntegerOverflow));
+        assert_eq!(eval(&fdiv6, &env), Ok(EvalResult::CInt(i32::MIN)));
+    }
+
+    #[test]
+    fn eval_variable() {
+        let env = Environment::from([
+            (String::from("w"), EnvValue::CInt(10)),
+            (String::from("x"), EnvValue::CReal(20.7)),
+            (String::from("y"), EnvValue::Bool(true)),
+            (
+                String::from("z"),
+                EnvValue::List(vec![EvalResult::CInt(1), EvalResult::CInt(2)]),
+            ),
+        ]);
+        let v1 = Expression::Var(String::from("w"));
+        let v2 = Expression::Var(String::from("x"));
+        let v3 = Expression::Var(String::from("y"));
+        let v4 = Expression::Var(String::from("z"));
+        assert_eq!(eval(&v1, &env), Ok(EvalResult::CInt(10)));
+        assert_eq!(eval(&v2, &env), Ok(EvalResult::CReal(20.7)));
+        assert_eq!(eval(&v3, &env), Ok(EvalResult::Bool(true)));
+        assert_eq!(
+            eval(&v4, &env),
+            Ok(EvalResult::List(vec![
+                EvalResult::CInt(1),
+                EvalResult::CInt(2)
+            ]))
+        );
+    }
+
+    #[test]
+    fn execute_assignment_same_variable() {
+        let env = Environment::new();
+        let a1 = Statement::Assignment(Box::new(String::from("x")), Box::new(Expression::CInt(1)));
+        let a2 = Statement::Assignment(Box::new(String::from("x")), Box::new(Expression::CInt(2)));
+        let seq = Statement::Sequence(Box::new(a1), Box::new(a2));
+
+        match execute(&seq, env) {
+            Ok(new_env) => match new_env.get("x") {
+                Some(EnvValue::CInt(2)) => {}
+                Some(value) => assert!(false, "Expected 2, got {:?}", value),
+                None => assert!(false, "Variable x not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn execute_assignment() {
+        let env = Environment::new();
+        let assign_stmt =
+            Statement::Assignment(Box::new(String::from("x")), Box::new(Expression::CInt(42)));
+
+        match execute(&assign_stmt, env) {
+            Ok(new_env) => match new_env.get("x") {
+                Some(EnvValue::CInt(42)) => {}
+                Some(value) => assert!(false, "Expected 42, got {:?}", value),
+                None => assert!(false, "Variable x not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn eval_expression_with_variables() {
+        let env = Environment::from([
+            (String::from("a"), EnvValue::CInt(5)),
+            (String::from("b"), EnvValue::CInt(3)),
+        ]);
+        let expr = Expression::Mul(
+            Box::new(Expression::Var(String::from("a"))),
+            Box::new(Expression::Add(
+                Box::new(Expression::Var(String::from("b"))),
+                Box::new(Expression::CInt(2)),
+            )),
+        );
+        assert_eq!(eval(&expr, &env), Ok(EvalResult::CInt(25)));
+    }
+
+    #[test]
+    fn eval_nested_expressions() {
+        let env = Environment::new();
+        let expr = Expression::Add(
+            Box::new(Expression::Mul(
+                Box::new(Expression::CInt(2)),
+                Box::new(Expression::CInt(3)),
+            )),
+            Box::new(Expression::Sub(
+                Box::new(Expression::CInt(10)),
+                Box::new(Expression::CInt(4)),
+            )),
+        );
+        assert_eq!(eval(&expr, &env), Ok(EvalResult::CInt(12)));
+    }
+
+    #[test]
+    fn eval_variable_not_found() {
+        let env = Environment::new();
+        let var_expr = Expression::Var(String::from("z"));
+
+        assert_eq!(
+            eval(&var_expr, &env),
+            Err(InterpreterError::UndefinedVariable(String::from("z")))
+        );
+    }
+
+    #[test]
+    fn eval_summation() {
+        /*
+         * (a test case for the following program)
+         *
+         * > x = 10
+         * > y = 0
+         * > while x:
+         * >   y = y + x
+         * >   x = x - 1
+         *
+         * After executing this program, 'x' must be zero and
+         * 'y' must be 55.
+         */
+        let env = Environment::new();
+
+        let a1 = Statement::Assignment(Box::new(String::from("x")), Box::new(Expression::CInt(10)));
+        let a2 = Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
+        let a3 = Statement::Assignment(
+            Box::new(String::from("y")),
+            Box::new(Expression::Add(
+                Box::new(Expression::Var(String::from("y"))),
+                Box::new(Expression::Var(String::from("x"))),
+            )),
+        );
+        let a4 = Statement::Assignment(
+            Box::new(String::from("x")),
+            Box::new(Expression::Sub(
+                Box::new(Expression::Var(String::from("x"))),
+                Box::new(Expression::CInt(1)),
+            )),
+        );
+
+        let seq1 = Statement::Sequence(Box::new(a3), Box::new(a4));
+
+        let while_statement =
+            Statement::While(Box::new(Expression::Var(String::from("x"))), Box::new(seq1));
+
+        let seq2 = Statement::Sequence(Box::new(a2), Box::new(while_statement));
+        let program = Statement::Sequence(Box::new(a1), Box::new(seq2));
+
+        match execute(&program, env) {
+            Ok(new_env) => {
+                match new_env.get("y") {
+                    Some(EnvValue::CInt(55)) => {}
+                    Some(val) => assert!(false, "Expected 55, got {:?}", val),
+                    None => assert!(false, "Variable y not found"),
+                }
+                match new_env.get("x") {
+                    Some(EnvValue::CInt(0)) => {}
+                    Some(val) => assert!(false, "Expected 0, got {:?}", val),
+                    None => assert!(false, "Variable x not found"),
+                }
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn eval_simple_if_then_else() {
+        /*
+         * Test for simple if-then-else statement
+         *
+         * > y = -1
+         * > x = 10
+         * > if x > 5:
+         * >   y = 1
+         * > else:
+         * >   y = 0
+         *
+         * After executing, 'y' should be 1.
+         */
+        let env = Environment::from([(String::from("y"), EnvValue::CInt(-1))]);
+
+        let condition = Expression::Var(String::from("x"));
+        let then_stmt =
+            Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(1)));
+        let else_stmt =
+            Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
+
+        let if_statement = Statement::IfThenElse(
+            Box::new(condition),
+            Box::new(then_stmt),
+            Box::new(else_stmt),
+        );
+
+        let setup_stmt =
+            Statement::Assignment(Box::new(String::from("x")), Box::new(Expression::CInt(10)));
+        let program = Statement::Sequence(Box::new(setup_stmt), Box::new(if_statement));
+
+        match execute(&program, env) {
+            Ok(new_env) => match new_env.get("y") {
+                Some(EnvValue::CInt(1)) => {}
+                Some(val) => assert!(false, "Expected 1, got {:?}", val),
+                None => assert!(false, "Variable y not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn eval_if_without_else() {
+        /*
+         * Test for if statements without an else branch
+         *
+         * > x = 10
+         * > z = 0
+         * > if False:
+         * >   y = 1
+         * > if x:
+         * >   z = 2
+         *
+         * After executing, 'y' should not exist and 'z' should be 2.
+         */
+        let env = Environment::from([
+            (String::from("x"), EnvValue::CInt(10)),
+            (String::from("z"), EnvValue::CInt(0)),
+        ]);
+
+        let if_false = Statement::If(
+            Box::new(Expression::Bool(false)),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("y")),
+                Box::new(Expression::CInt(1)),
+            )),
+        );
+        let if_true = Statement::If(
+            Box::new(Expression::Var(String::from("x"))),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("z")),
+                Box::new(Expression::CInt(2)),
+            )),
+        );
+
+        match execute(&if_false, env.clone()) {
+            Ok(new_env) => assert_eq!(new_env, env),
+            Err(s) => assert!(false, "{}", s),
+        }
+        match execute(&if_true, env) {
+            Ok(new_env) => match new_env.get("z") {
+                Some(EnvValue::CInt(2)) => {}
+                Some(val) => assert!(false, "Expected 2, got {:?}", val),
+                None => assert!(false, "Variable z not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn eval_while_loop_decrement() {
+        /*
+         * Test for while loop that decrements a variable
+         *
+         * > x = 3
+         * > y = 10
+         * > while x:
+         * >   y = y - 1
+         * >   x = x - 1
+         *
+         * After executing, 'y' should be 7 and 'x' should be 0.
+         */
+        let env = Environment::new();
+
+        let a1 = Statement::Assignment(Box::new(String::from("x")), Box::new(Expression::CInt(3)));
+        let a2 = Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(10)));
+        let a3 = Statement::Assignment(
+            Box::new(String::from("y")),
+            Box::new(Expression::Sub(
+                Box::new(Expression::Var(String::from("y"))),
+                Box::new(Expression::CInt(1)),
+            )),
+        );
+        let a4 = Statement::Assignment(
+            Box::new(String::from("x")),
+            Box::new(Expression::Sub(
+                Box::new(Expression::Var(String::from("x"))),
+                Box::new(Expression::CInt(1)),
+            )),
+        );
+
+        let seq1 = Statement::Sequence(Box::new(a3), Box::new(a4));
+        let while_statement =
+            Statement::While(Box::new(Expression::Var(String::from("x"))), Box::new(seq1));
+        let program = Statement::Sequence(
+            Box::new(a1),
+            Box::new(Statement::Sequence(Box::new(a2), Box::new(while_statement))),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => {
+                match new_env.get("y") {
+                    Some(EnvValue::CInt(7)) => {}
+                    Some(val) => assert!(false, "Expected 7, got {:?}", val),
+                    None => assert!(false, "Variable y not found"),
+                }
+                match new_env.get("x") {
+                    Some(EnvValue::CInt(0)) => {}
+                    Some(val) => assert!(false, "Expected 0, got {:?}", val),
+                    None => assert!(false, "Variable x not found"),
+                }
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn eval_for_loop_increment() {
+        /*
+         * For loop test for variable increment
+         *
+         * > y = 0
+         *
+         * > for i in range(0, 5, 2):
+         * >    y = y + i
+         *
+         * After executing, 'y' should be 6 and 'i' should not be accessible.
+         */
+        let env = Environment::new();
+
+        let a1 = Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
+        let for_exec = Statement::Assignment(
+            Box::new(String::from("y")),
+            Box::new(Expression::Add(
+                Box::new(Expression::Var(String::from("y"))),
+                Box::new(Expression::Var(String::from("i"))),
+            )),
+        );
+
+        let range = Expression::Range(
+            Some(Box::new(Expression::CInt(0))),
+            Box::new(Expression::CInt(5)),
+            Some(Box::new(Expression::CInt(2))),
+        );
+
+        let for_stmt = Statement::For(
+            Box::new(String::from("i")),
+            Box::new(range),
+            Box::new(for_exec),
+        );
+
+        let program = Statement::Sequence(Box::new(a1), Box::new(for_stmt));
+
+        match execute(&program, env) {
+            Ok(new_env) => {
+                match new_env.get("y") {
+                    Some(EnvValue::CInt(6)) => {}
+                    Some(val) => assert!(false, "Expected 6, got {:?}", val),
+                    None => assert!(false, "Variable y not found"),
+                }
+                match new_env.get("i") {
+                    None => {}
+                    Some(val) => assert!(false, "Expected None, got {:?}", val),
+                }
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn eval_for_loop_decrement() {
+        /*
+         * For loop test for variable decrement
+         *
+         * > y = 0
+         *
+         * > for i in range(10, 3, -1):
+         * >    y = y + i
+         *
+         * After executing, 'y' should be 49 and 'i' should not be accessible.
+         */
+        let env = Environment::new();
+
+        let a1 = Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
+        let for_exec = Statement::Assignment(
+            Box::new(String::from("y")),
+            Box::new(Expression::Add(
+                Box::new(Expression::Var(String::from("y"))),
+                Box::new(Expression::Var(String::from("i"))),
+            )),
+        );
+
+        let range = Expression::Range(
+            Some(Box::new(Expression::CInt(10))),
+            Box::new(Expression::CInt(3)),
+            Some(Box::new(Expression::CInt(-1))),
+        );
+
+        let for_stmt = Statement::For(
+            Box::new(String::from("i")),
+            Box::new(range),
+            Box::new(for_exec),
+        );
+
+        let program = Statement::Sequence(Box::new(a1), Box::new(for_stmt));
+
+        match execute(&program, env) {
+            Ok(new_env) => {
+                match new_env.get("y") {
+                    Some(EnvValue::CInt(49)) => {}
+                    Some(val) => assert!(false, "Expected 49, got {:?}", val),
+                    None => assert!(false, "Variable y not found"),
+                }
+                match new_env.get("i") {
+                    None => {}
+                    Some(val) => assert!(false, "Expected None, got {:?}", val),
+                }
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn eval_for_loop_no_values() {
+        /*
+         * For loop test for a loop specified by stop only
+         *
+         * > y = 0
+         *
+         * > for i in range(5):
+         * >    y = y + i
+         *
+         * After executing, 'y' should be 10 and 'i' should not be accessible.
+         */
+        let env = Environment::new();
+
+        let a1 = Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
+        let for_exec = Statement::Assignment(
+            Box::new(String::from("y")),
+            Box::new(Expression::Add(
+                Box::new(Expression::Var(String::from("y"))),
+                Box::new(Expression::Var(String::from("i"))),
+            )),
+        );
+
+        let range = Expression::Range(None, Box::new(Expression::CInt(5)), None);
+
+        let for_stmt = Statement::For(
+            Box::new(String::from("i")),
+            Box::new(range),
+            Box::new(for_exec),
+        );
+
+        let program = Statement::Sequence(Box::new(a1), Box::new(for_stmt));
+
+        match execute(&program, env) {
+            Ok(new_env) => {
+                match new_env.get("y") {
+                    Some(EnvValue::CInt(10)) => {}
+                    Some(val) => assert!(false, "Expected 10, got {:?}", val),
+                    None => assert!(false, "Variable y not found"),
+                }
+                match new_env.get("i") {
+                    None => {}
+                    Some(val) => assert!(false, "Expected None, got {:?}", val),
+                }
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn eval_for_loop_no_range() {
+        /*
+         * For loop test for condition never reached
+         *
+         * > y = 0
+         *
+         * > for i in range(0, 1, -1):
+         * >    y = y + i
+         *
+         */
+        let env = Environment::new();
+
+        let a1 = Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
+        let for_exec = Statement::Assignment(
+            Box::new(String::from("y")),
+            Box::new(Expression::Add(
+                Box::new(Expression::Var(String::from("y"))),
+                Box::new(Expression::Var(String::from("i"))),
+            )),
+        );
+
+        let range = Expression::Range(
+            Some(Box::new(Expression::CInt(0))),
+            Box::new(Expression::CInt(1)),
+            Some(Box::new(Expression::CInt(-1))),
+        );
+
+        let for_stmt = Statement::For(
+            Box::new(String::from("i")),
+            Box::new(range),
+            Box::new(for_exec),
+        );
+
+        let program = Statement::Sequence(Box::new(a1), Box::new(for_stmt));
+
+        match execute(&program, env) {
+            Ok(new_env) => match new_env.get("y") {
+                Some(EnvValue::CInt(0)) => (),
+                Some(val) => assert!(false, "Expected 0, got {:?}", val),
+                None => assert!(false, "Variable y not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn eval_for_loop_list() {
+        /*
+         * For loop test for a list of objects
+         *
+         * > y = 0
+         *
+         * > for i in [1, 3, 5]:
+         * >    y = y + i
+         *
+         * After executing, 'y' should be 9  and 'i' should not be accessible.
+         */
+        let env = Environment::new();
+
+        let a1 = Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
+
+        let for_exec = Statement::Assignment(
+            Box::new(String::from("y")),
+            Box::new(Expression::Add(
+                Box::new(Expression::Var(String::from("y"))),
+                Box::new(Expression::Var(String::from("i"))),
+            )),
+        );
+
+        let l1 = Expression::List(vec![
+            Expression::CInt(1),
+            Expression::CInt(3),
+            Expression::CInt(5),
+        ]);
+
+        let for_stmt = Statement::For(
+            Box::new(String::from("i")),
+            Box::new(l1),
+            Box::new(for_exec),
+        );
+
+        let program = Statement::Sequence(Box::new(a1), Box::new(for_stmt));
+
+        match execute(&program, env) {
+            Ok(new_env) => match new_env.get("y") {
+                Some(EnvValue::CInt(9)) => (),
+                Some(val) => assert!(false, "Expected 9, got {:?}", val),
+                None => assert!(false, "Variable y not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn eval_nested_if_statements() {
+        /*
+         * Test for nested if-then-else statements
+         *
+         * > y = -1
+         * > x = 10
+         * > if x > 5:
+         * >   if x > 8:
+         * >     y = 1
+         * >   else:
+         * >     y = 2
+         * > else:
+         * >   y = 0
+         *
+         * After executing, 'y' should be 1.
+         */
+        let env = Environment::from([(String::from("y"), EnvValue::CInt(-1))]);
+
+        let inner_then_stmt =
+            Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(1)));
+        let inner_else_stmt =
+            Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(2)));
+        let inner_if_statement = Statement::IfThenElse(
+            Box::new(Expression::Var(String::from("x"))),
+            Box::new(inner_then_stmt),
+            Box::new(inner_else_stmt),
+        );
+
+        let outer_else_stmt =
+            Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
+        let outer_if_statement = Statement::IfThenElse(
+            Box::new(Expression::Var(String::from("x"))),
+            Box::new(inner_if_statement),
+            Box::new(outer_else_stmt),
+        );
+
+        let setup_stmt =
+            Statement::Assignment(Box::new(String::from("x")), Box::new(Expression::CInt(10)));
+        let program = Statement::Sequence(Box::new(setup_stmt), Box::new(outer_if_statement));
+
+        match execute(&program, env) {
+            Ok(new_env) => match new_env.get("y") {
+                Some(EnvValue::CInt(1)) => {}
+                Some(val) => assert!(false, "Expected 1, got {:?}", val),
+                None => assert!(false, "Variable y not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn eval_complex_sequence() {
+        /*
+         * Sequence with multiple assignments and expressions
+         *
+         * > x = 5
+         * > y = 0
+         * > z = 2 * x + 3
+         *
+         * After executing, 'x' should be 5, 'y' should be 0, and 'z' should be 13.
+         */
+        let env = Environment::new();
+
+        let a1 = Statement::Assignment(Box::new(String::from("x")), Box::new(Expression::CInt(5)));
+        let a2 = Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
+        let a3 = Statement::Assignment(
+            Box::new(String::from("z")),
+            Box::new(Expression::Add(
+                Box::new(Expression::Mul(
+                    Box::new(Expression::CInt(2)),
+                    Box::new(Expression::Var(String::from("x"))),
+                )),
+                Box::new(Expression::CInt(3)),
+            )),
+        );
+
+        let program = Statement::Sequence(
+            Box::new(a1),
+            Box::new(Statement::Sequence(Box::new(a2), Box::new(a3))),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => {
+                match new_env.get("x") {
+                    Some(EnvValue::CInt(5)) => {}
+                    Some(val) => assert!(false, "Expected 5, got {:?}", val),
+                    None => assert!(false, "Variable x not found"),
+                }
+                match new_env.get("y") {
+                    Some(EnvValue::CInt(0)) => {}
+                    Some(val) => assert!(false, "Expected 0, got {:?}", val),
+                    None => assert!(false, "Variable y not found"),
+                }
+                match new_env.get("z") {
+                    Some(EnvValue::CInt(13)) => {}
+                    Some(val) => assert!(false, "Expected 13, got {:?}", val),
+                    None => assert!(false, "Variable z not found"),
+                }
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn func_decl_call() {
+        /*
+         * Test for declaration and call of a function
+         *
+         * > def add(a: CInt, b: CInt) -> CInt:
+         * >    t = a + b
+         * >    return t
+         * >
+         * > sum = add(5, 7)
+         *
+         * After executing, 'sum' should be 12.
+         */
+        let env = Environment::new();
+
+        let args = vec![
+            (String::from("a"), Box::new(Type::TInteger)),
+            (String::from("b"), Box::new(Type::TInteger)),
+        ];
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Func(
+                Box::new(String::from("add")),
+                Box::new(Type::TInteger),
+                Some(args),
+                Some(Box::new(Statement::Assignment(
+                    Box::new(String::from("t")),
+                    Box::new(Expression::Add(
+                        Box::new(Expression::Var(String::from("a"))),
+                        Box::new(Expression::Var(String::from("b"))),
+                    )),
+                ))),
+                Box::new(Expression::Var(String::from("t"))),
+            )),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("sum")),
+                Box::new(Expression::FuncCall(
+                    String::from("add"),
+                    Some(vec![Expression::CInt(5), Expression::CInt(7)]),
+                )),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => match new_env.get("sum") {
+                Some(EnvValue::CInt(12)) => {}
+                Some(val) => assert!(false, "Expected 12, got {:?}", val),
+                None => assert!(false, "Variable sum not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn func_decl_call_without_stmt() {
+        /*
+         * Test for declaration and call of a function with no statement
+         *
+         * > def add(a: CInt, b: CInt) -> CInt:
+         * >    return a + b
+         * >
+         * > sum = add(1, 2)
+         *
+         * After executing, 'sum' should be 3.
+         */
+        let env = Environment::new();
+
+        let args = vec![
+            (String::from("a"), Box::new(Type::TInteger)),
+            (String::from("b"), Box::new(Type::TInteger)),
+        ];
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Func(
+                Box::new(String::from("add")),
+                Box::new(Type::TInteger),
+                Some(args),
+                None,
+                Box::new(Expression::Add(
+                    Box::new(Expression::Var(String::from("a"))),
+                    Box::new(Expression::Var(String::from("b"))),
+                )),
+            )),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("sum")),
+                Box::new(Expression::FuncCall(
+                    String::from("add"),
+                    Some(vec![Expression::CInt(1), Expression::CInt(2)]),
+                )),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => match new_env.get("sum") {
+                Some(EnvValue::CInt(3)) => {}
+                Some(val) => assert!(false, "Expected 3, got {:?}", val),
+                None => assert!(false, "Variable sum not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn func_decl_call_without_args() {
+        /*
+         * Test for declaration and call of a function with no arguments
+         *
+         * > def two_plus_two() -> CInt:
+         * >    return 4
+         * >
+         * > value = two_plus_two()
+         *
+         * After executing, 'sum' should be 4.
+         */
+        let env = Environment::new();
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Func(
+                Box::new(String::from("two_plus_two")),
+                Box::new(Type::TInteger),
+                None,
+                None,
+                Box::new(Expression::CInt(4)),
+            )),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("value")),
+                Box::new(Expression::FuncCall(String::from("two_plus_two"), None)),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => match new_env.get("value") {
+                Some(EnvValue::CInt(4)) => {}
+                Some(val) => assert!(false, "Expected 4, got {:?}", val),
+                None => assert!(false, "Variable value not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn num_arguments_error_func_call() {
+        /*
+         * Test for declaration and call of a function where the passed
+         * arguments don't match the functions definition
+         *
+         * > def add(a: CInt, b: CInt) -> CInt:
+         * >    return a + b
+         * >
+         * > sum = add(1, 2, 3)
+         *
+         */
+        let env = Environment::new();
+
+        let args = vec![
+            (String::from("a"), Box::new(Type::TInteger)),
+            (String::from("b"), Box::new(Type::TInteger)),
+        ];
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Func(
+                Box::new(String::from("add")),
+                Box::new(Type::TInteger),
+                Some(args),
+                None,
+                Box::new(Expression::Add(
+                    Box::new(Expression::Var(String::from("a"))),
+                    Box::new(Expression::Var(String::from("b"))),
+                )),
+            )),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("sum")),
+                Box::new(Expression::FuncCall(
+                    String::from("add"),
+                    Some(vec![
+                        Expression::CInt(1),
+                        Expression::CInt(2),
+                        Expression::CInt(3),
+                    ]),
+                )),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(_) => assert!(false, "Function should generate an error"),
+            Err(s) => assert_eq!(
+                s,
+                InterpreterError::ArityMismatch {
+                    name: String::from("add"),
+                    expected: 2,
+                    got: 3
+                }
+            ),
+        }
+    }
+
+    #[test]
+    fn arguments_type_error_func_call() {
+        /*
+         * Test for declaration and call of a function where the passed
+         * arguments don't match their defined types on the function
+         *
+         * > def add(a: CInt, b: CReal) -> CReal:
+         * >    return a + b
+         * >
+         * > sum = add(1, 2)
+         *
+         */
+        let env = Environment::new();
+
+        let args = vec![
+            (String::from("a"), Box::new(Type::TInteger)),
+            (String::from("b"), Box::new(Type::TReal)),
+        ];
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Func(
+                Box::new(String::from("add")),
+                Box::new(Type::TReal),
+                Some(args),
+                None,
+                Box::new(Expression::Add(
+                    Box::new(Expression::Var(String::from("a"))),
+                    Box::new(Expression::Var(String::from("b"))),
+                )),
+            )),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("sum")),
+                Box::new(Expression::FuncCall(
+                    String::from("add"),
+                    Some(vec![Expression::CInt(1), Expression::CInt(2)]),
+                )),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(_) => assert!(false, "Function should generate an error"),
+            Err(s) => assert_eq!(
+                s,
+                InterpreterError::TypeError(String::from("argument 'b' expected real but got int"))
+            ),
+        }
+    }
+
+    #[test]
+    fn func_return_type_error() {
+        /*
+         * Test for declaration and call of a function where the return type
+         * is different from the one defined by the function
+         *
+         * > def add(a: CReal, b: CReal) -> CInt:
+         * >    return a + b
+         * >
+         * > sum = add(1.5, 2.5)
+         *
+         */
+        let env = Environment::new();
+
+        let args = vec![
+            (String::from("a"), Box::new(Type::TReal)),
+            (String::from("b"), Box::new(Type::TReal)),
+        ];
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Func(
+                Box::new(String::from("add")),
+                Box::new(Type::TInteger),
+                Some(args),
+                None,
+                Box::new(Expression::Add(
+                    Box::new(Expression::Var(String::from("a"))),
+                    Box::new(Expression::Var(String::from("b"))),
+                )),
+            )),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("sum")),
+                Box::new(Expression::FuncCall(
+                    String::from("add"),
+                    Some(vec![Expression::CReal(1.5), Expression::CReal(2.5)]),
+                )),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(_) => assert!(false, "Function should generate an error"),
+            Err(s) => assert_eq!(
+                s,
+                InterpreterError::TypeError(String::from(
+                    "add returned a value different from specified type"
+                ))
+            ),
+        }
+    }
+
+    #[test]
+    fn undefined_func_call() {
+        let env = Environment::new();
+
+        let program = Box::new(Statement::Assignment(
+            Box::new(String::from("sum")),
+            Box::new(Expression::FuncCall(
+                String::from("add"),
+                Some(vec![Expression::CInt(1), Expression::CInt(2)]),
+            )),
+        ));
+
+        match execute(&program, env) {
+            Ok(_) => assert!(false, "Function not supposed to execute"),
+            Err(s) => assert_eq!(s, InterpreterError::UndefinedFunction(String::from("add"))),
+        }
+    }
+
+    #[test]
+    fn return_from_nested_loops() {
+        /*
+         * Test for a return inside a while nested in a for, which must
+         * stop both loops and leave the function immediately
+         *
+         * > def search() -> CInt:
+         * >    steps = 0
+         * >    for i in range(3):
+         * >       j = 3
+         * >       while j:
+         * >          steps = steps + 1
+         * >          if i:
+         * >             return steps
+         * >          else:
+         * >             j = j - 1
+         * >    return -1
+         * >
+         * > result = search()
+         *
+         * After executing, 'result' should be 4: three steps for i = 0 and
+         * a single one for i = 1, after which neither loop runs again.
+         */
+        let env = Environment::new();
+
+        let inner_if = Statement::IfThenElse(
+            Box::new(Expression::Var(String::from("i"))),
+            Box::new(Statement::Return(Box::new(Expression::Var(String::from(
+                "steps",
+            ))))),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("j")),
+                Box::new(Expression::Sub(
+                    Box::new(Expression::Var(String::from("j"))),
+                    Box::new(Expression::CInt(1)),
+                )),
+            )),
+        );
+
+        let while_stmt = Statement::While(
+            Box::new(Expression::Var(String::from("j"))),
+            Box::new(Statement::Sequence(
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("steps")),
+                    Box::new(Expression::Add(
+                        Box::new(Expression::Var(String::from("steps"))),
+                        Box::new(Expression::CInt(1)),
+                    )),
+                )),
+                Box::new(inner_if),
+            )),
+        );
+
+        let for_stmt = Statement::For(
+            Box::new(String::from("i")),
+            Box::new(Expression::Range(None, Box::new(Expression::CInt(3)), None)),
+            Box::new(Statement::Sequence(
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("j")),
+                    Box::new(Expression::CInt(3)),
+                )),
+                Box::new(while_stmt),
+            )),
+        );
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Func(
+                Box::new(String::from("search")),
+                Box::new(Type::TInteger),
+                None,
+                Some(Box::new(Statement::Sequence(
+                    Box::new(Statement::Assignment(
+                        Box::new(String::from("steps")),
+                        Box::new(Expression::CInt(0)),
+                    )),
+                    Box::new(for_stmt),
+                ))),
+                Box::new(Expression::CInt(-1)),
+            )),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("result")),
+                Box::new(Expression::FuncCall(String::from("search"), None)),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => match new_env.get("result") {
+                Some(EnvValue::CInt(4)) => {}
+                Some(val) => assert!(false, "Expected 4, got {:?}", val),
+                None => assert!(false, "Variable result not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn func_early_return() {
+        /*
+         * Test for a return inside an if that short-circuits the rest of
+         * the function body and its trailing return expression
+         *
+         * > def check(x: CInt) -> CInt:
+         * >    if x:
+         * >       return 1
+         * >    else:
+         * >       y = 0
+         * >    z = 1 / 0
+         * >    return unreachable
+         * >
+         * > value = check(5)
+         *
+         * After executing, 'value' should be 1, and neither the division
+         * nor the undefined 'unreachable' should be evaluated.
+         */
+        let env = Environment::new();
+
+        let body = Statement::Sequence(
+            Box::new(Statement::IfThenElse(
+                Box::new(Expression::Var(String::from("x"))),
+                Box::new(Statement::Return(Box::new(Expression::CInt(1)))),
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("y")),
+                    Box::new(Expression::CInt(0)),
+                )),
+            )),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("z")),
+                Box::new(Expression::Div(
+                    Box::new(Expression::CInt(1)),
+                    Box::new(Expression::CInt(0)),
+                )),
+            )),
+        );
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Func(
+                Box::new(String::from("check")),
+                Box::new(Type::TInteger),
+                Some(vec![(String::from("x"), Box::new(Type::TInteger))]),
+                Some(Box::new(body)),
+                Box::new(Expression::Var(String::from("unreachable"))),
+            )),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("value")),
+                Box::new(Expression::FuncCall(
+                    String::from("check"),
+                    Some(vec![Expression::CInt(5)]),
+                )),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => match new_env.get("value") {
+                Some(EnvValue::CInt(1)) => {}
+                Some(val) => assert!(false, "Expected 1, got {:?}", val),
+                None => assert!(false, "Variable value not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn return_outside_function() {
+        let env = Environment::new();
+        let program = Statement::Return(Box::new(Expression::CInt(1)));
+
+        match execute(&program, env) {
+            Ok(_) => assert!(false, "Return outside a function should fail"),
+            Err(s) => assert_eq!(
+                s,
+                InterpreterError::SyntaxError(String::from("'return' outside function"))
+            ),
+        }
+    }
+
+    #[test]
+    fn for_loop_shadows_parameter() {
+        /*
+         * Test for a loop variable sharing its name with a function
+         * parameter
+         *
+         * > def shadow(i: CInt) -> CInt:
+         * >    total = 0
+         * >    for i in range(3):
+         * >       total = total + i
+         * >    return total + i
+         * >
+         * > value = shadow(7)
+         *
+         * After executing, 'value' should be 10: the loop sums 0 + 1 + 2
+         * using its own 'i', and the parameter 'i' is 7 again afterwards.
+         */
+        let env = Environment::new();
+
+        let body = Statement::Sequence(
+            Box::new(Statement::Assignment(
+                Box::new(String::from("total")),
+                Box::new(Expression::CInt(0)),
+            )),
+            Box::new(Statement::For(
+                Box::new(String::from("i")),
+                Box::new(Expression::Range(None, Box::new(Expression::CInt(3)), None)),
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("total")),
+                    Box::new(Expression::Add(
+                        Box::new(Expression::Var(String::from("total"))),
+                        Box::new(Expression::Var(String::from("i"))),
+                    )),
+                )),
+            )),
+        );
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Func(
+                Box::new(String::from("shadow")),
+                Box::new(Type::TInteger),
+                Some(vec![(String::from("i"), Box::new(Type::TInteger))]),
+                Some(Box::new(body)),
+                Box::new(Expression::Add(
+                    Box::new(Expression::Var(String::from("total"))),
+                    Box::new(Expression::Var(String::from("i"))),
+                )),
+            )),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("value")),
+                Box::new(Expression::FuncCall(
+                    String::from("shadow"),
+                    Some(vec![Expression::CInt(7)]),
+                )),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => match new_env.get("value") {
+                Some(EnvValue::CInt(10)) => {}
+                Some(val) => assert!(false, "Expected 10, got {:?}", val),
+                None => assert!(false, "Variable value not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn while_loop_break() {
+        /*
+         * Test for a while loop that is stopped by a break
+         *
+         * > x = 0
+         * > y = 0
+         * > while True:
+         * >    x = x + 1
+         * >    if x - 5:
+         * >       y = x
+         * >    else:
+         * >       break
+         *
+         * After executing, 'x' should be 5 and 'y' should be 4.
+         */
+        let env = Environment::from([(String::from("y"), EnvValue::CInt(0))]);
+
+        let body = Statement::Sequence(
+            Box::new(Statement::Assignment(
+                Box::new(String::from("x")),
+                Box::new(Expression::Add(
+                    Box::new(Expression::Var(String::from("x"))),
+                    Box::new(Expression::CInt(1)),
+                )),
+            )),
+            Box::new(Statement::IfThenElse(
+                Box::new(Expression::Sub(
+                    Box::new(Expression::Var(String::from("x"))),
+                    Box::new(Expression::CInt(5)),
+                )),
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("y")),
+                    Box::new(Expression::Var(String::from("x"))),
+                )),
+                Box::new(Statement::Break),
+            )),
+        );
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Assignment(
+                Box::new(String::from("x")),
+                Box::new(Expression::CInt(0)),
+            )),
+            Box::new(Statement::While(
+                Box::new(Expression::Bool(true)),
+                Box::new(body),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => {
+                match new_env.get("x") {
+                    Some(EnvValue::CInt(5)) => {}
+                    Some(val) => assert!(false, "Expected 5, got {:?}", val),
+                    None => assert!(false, "Variable x not found"),
+                }
+                match new_env.get("y") {
+                    Some(EnvValue::CInt(4)) => {}
+                    Some(val) => assert!(false, "Expected 4, got {:?}", val),
+                    None => assert!(false, "Variable y not found"),
+                }
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn for_loop_continue() {
+        /*
+         * Test for a for loop that skips odd values with continue
+         *
+         * > total = 0
+         * > for i in range(10):
+         * >    if i - i // 2 * 2:
+         * >       continue
+         * >    else:
+         * >       total = total
+         * >    total = total + i
+         *
+         * After executing, 'total' should be 20 (0 + 2 + 4 + 6 + 8).
+         */
+        let env = Environment::new();
+
+        let is_odd = Expression::Sub(
+            Box::new(Expression::Var(String::from("i"))),
+            Box::new(Expression::Mul(
+                Box::new(Expression::FloorDiv(
+                    Box::new(Expression::Var(String::from("i"))),
+                    Box::new(Expression::CInt(2)),
+                )),
+                Box::new(Expression::CInt(2)),
+            )),
+        );
+
+        let body = Statement::Sequence(
+            Box::new(Statement::IfThenElse(
+                Box::new(is_odd),
+                Box::new(Statement::Continue),
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("total")),
+                    Box::new(Expression::Var(String::from("total"))),
+                )),
+            )),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("total")),
+                Box::new(Expression::Add(
+                    Box::new(Expression::Var(String::from("total"))),
+                    Box::new(Expression::Var(String::from("i"))),
+                )),
+            )),
+        );
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Assignment(
+                Box::new(String::from("total")),
+                Box::new(Expression::CInt(0)),
+            )),
+            Box::new(Statement::For(
+                Box::new(String::from("i")),
+                Box::new(Expression::Range(
+                    None,
+                    Box::new(Expression::CInt(10)),
+                    None,
+                )),
+                Box::new(body),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => match new_env.get("total") {
+                Some(EnvValue::CInt(20)) => {}
+                Some(val) => assert!(false, "Expected 20, got {:?}", val),
+                None => assert!(false, "Variable total not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn break_and_continue_outside_loop() {
+        let env = Environment::new();
+
+        match execute(&Statement::Break, env.clone()) {
+            Ok(_) => assert!(false, "Break outside a loop should fail"),
+            Err(s) => assert_eq!(
+                s,
+                InterpreterError::SyntaxError(String::from("'break' outside loop"))
+            ),
+        }
+        match execute(&Statement::Continue, env) {
+            Ok(_) => assert!(false, "Continue outside a loop should fail"),
+            Err(s) => assert_eq!(
+                s,
+                InterpreterError::SyntaxError(String::from("'continue' not properly in loop"))
+            ),
+        }
+    }
+
+    #[test]
+    fn func_duplicate_parameters() {
+        /*
+         * Test for a function definition that repeats a parameter name
+         *
+         * > def add(a: CInt, a: CInt) -> CInt:
+         * >    return a + a
+         */
+        let env = Environment::new();
+
+        let args = vec![
+            (String::from("a"), Box::new(Type::TInteger)),
+            (String::from("a"), Box::new(Type::TInteger)),
+        ];
+
+        let program = Statement::Func(
+            Box::new(String::from("add")),
+            Box::new(Type::TInteger),
+            Some(args),
+            None,
+            Box::new(Expression::Add(
+                Box::new(Expression::Var(String::from("a"))),
+                Box::new(Expression::Var(String::from("a"))),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(_) => assert!(false, "Function definition should generate an error"),
+            Err(s) => assert_eq!(
+                s,
+                InterpreterError::SyntaxError(String::from(
+                    "duplicate argument 'a' in function definition"
+                ))
+            ),
+        }
+    }
+
+    #[test]
+    fn eval_negative_zero() {
+        let env = Environment::new();
+        let neg_zero = Expression::CReal(-0.0);
+
+        let eq = Expression::Eq(Box::new(neg_zero.clone()), Box::new(Expression::CReal(0.0)));
+        let to_str = Expression::FuncCall(String::from("str"), Some(vec![neg_zero.clone()]));
+        let div = Expression::Div(Box::new(Expression::CReal(1.0)), Box::new(neg_zero));
+
+        assert_eq!(eval(&eq, &env), Ok(EvalResult::Bool(true)));
+        assert_eq!(
+            eval(&to_str, &env),
+            Ok(EvalResult::CString(String::from("-0.0")))
+        );
+        assert_eq!(eval(&div, &env), Err(InterpreterError::DivisionByZero));
+    }
+
+    #[test]
+    fn for_loop_fstring_chars() {
+        /*
+         * Test for a for loop over the characters of an f-string
+         *
+         * > n = 42
+         * > chars = []
+         * > for c in f"x{n}":
+         * >    chars = chars + [c]
+         *
+         * After executing, 'chars' should be ['x', '4', '2'].
+         */
+        let env = Environment::new();
+
+        let fstring = Expression::FString(vec![
+            Expression::CString(String::from("x")),
+            Expression::Var(String::from("n")),
+        ]);
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Assignment(
+                Box::new(String::from("n")),
+                Box::new(Expression::CInt(42)),
+            )),
+            Box::new(Statement::Sequence(
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("chars")),
+                    Box::new(Expression::List(vec![])),
+                )),
+                Box::new(Statement::For(
+                    Box::new(String::from("c")),
+                    Box::new(fstring),
+                    Box::new(Statement::Assignment(
+                        Box::new(String::from("chars")),
+                        Box::new(Expression::Add(
+                            Box::new(Expression::Var(String::from("chars"))),
+                            Box::new(Expression::List(vec![Expression::Var(String::from("c"))])),
+                        )),
+                    )),
+                )),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => assert_eq!(
+                new_env.get("chars"),
+                Some(EnvValue::List(vec![
+                    EvalResult::CString(String::from("x")),
+                    EvalResult::CString(String::from("4")),
+                    EvalResult::CString(String::from("2")),
+                ]))
+            ),
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn func_locals_do_not_leak() {
+        /*
+         * Test for the call boundary: assignments made by the callee stay
+         * in the callee
+         *
+         * > x = 1
+         * > def scratch() -> CInt:
+         * >    tmp = 10
+         * >    x = tmp
+         * >    return x
+         * >
+         * > value = scratch()
+         *
+         * After executing, 'value' should be 10, 'x' should still be 1
+         * and 'tmp' should not exist in the caller's environment.
+         */
+        let env = Environment::new();
+
+        let body = Statement::Sequence(
+            Box::new(Statement::Assignment(
+                Box::new(String::from("tmp")),
+                Box::new(Expression::CInt(10)),
+            )),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("x")),
+                Box::new(Expression::Var(String::from("tmp"))),
+            )),
+        );
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Assignment(
+                Box::new(String::from("x")),
+                Box::new(Expression::CInt(1)),
+            )),
+            Box::new(Statement::Sequence(
+                Box::new(Statement::Func(
+                    Box::new(String::from("scratch")),
+                    Box::new(Type::TInteger),
+                    None,
+                    Some(Box::new(body)),
+                    Box::new(Expression::Var(String::from("x"))),
+                )),
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("value")),
+                    Box::new(Expression::FuncCall(String::from("scratch"), None)),
+                )),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => {
+                match new_env.get("value") {
+                    Some(EnvValue::CInt(10)) => {}
+                    Some(val) => assert!(false, "Expected 10, got {:?}", val),
+                    None => assert!(false, "Variable value not found"),
+                }
+                match new_env.get("x") {
+                    Some(EnvValue::CInt(1)) => {}
+                    Some(val) => assert!(false, "Expected 1, got {:?}", val),
+                    None => assert!(false, "Variable x not found"),
+                }
+                match new_env.get("tmp") {
+                    None => {}
+                    Some(val) => assert!(false, "Expected None, got {:?}", val),
+                }
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn block_scoped_variables() {
+        /*
+         * Test for variables created inside a block
+         *
+         * > x = 1
+         * > if x:
+         * >    x = 2
+         * >    inner = 5
+         *
+         * After executing, 'x' should be 2, since assigning an outer
+         * variable updates it, but 'inner' should not be visible.
+         */
+        let env = Environment::new();
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Assignment(
+                Box::new(String::from("x")),
+                Box::new(Expression::CInt(1)),
+            )),
+            Box::new(Statement::If(
+                Box::new(Expression::Var(String::from("x"))),
+                Box::new(Statement::Sequence(
+                    Box::new(Statement::Assignment(
+                        Box::new(String::from("x")),
+                        Box::new(Expression::CInt(2)),
+                    )),
+                    Box::new(Statement::Assignment(
+                        Box::new(String::from("inner")),
+                        Box::new(Expression::CInt(5)),
+                    )),
+                )),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => {
+                match new_env.get("x") {
+                    Some(EnvValue::CInt(2)) => {}
+                    Some(val) => assert!(false, "Expected 2, got {:?}", val),
+                    None => assert!(false, "Variable x not found"),
+                }
+                match new_env.get("inner") {
+                    None => {}
+                    Some(val) => assert!(false, "Expected None, got {:?}", val),
+                }
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn assignment_reads_old_value() {
+        /*
+         * Test for assignments whose right-hand side reads the variable
+         * being rebound
+         *
+         * > x = 5
+         * > x = [x]
+         * > y = [1]
+         * > y = y + [y]
+         *
+         * After executing, 'x' should be [5] and 'y' should be [1, [1]]:
+         * both right-hand sides see the old value before the rebinding.
+         */
+        let env = Environment::new();
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Assignment(
+                Box::new(String::from("x")),
+                Box::new(Expression::CInt(5)),
+            )),
+            Box::new(Statement::Sequence(
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("x")),
+                    Box::new(Expression::List(vec![Expression::Var(String::from("x"))])),
+                )),
+                Box::new(Statement::Sequence(
+                    Box::new(Statement::Assignment(
+                        Box::new(String::from("y")),
+                        Box::new(Expression::List(vec![Expression::CInt(1)])),
+                    )),
+                    Box::new(Statement::Assignment(
+                        Box::new(String::from("y")),
+                        Box::new(Expression::Add(
+                            Box::new(Expression::Var(String::from("y"))),
+                            Box::new(Expression::List(vec![Expression::Var(String::from("y"))])),
+                        )),
+                    )),
+                )),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => {
+                assert_eq!(
+                    new_env.get("x"),
+                    Some(EnvValue::List(vec![EvalResult::CInt(5)]))
+                );
+                assert_eq!(
+                    new_env.get("y"),
+                    Some(EnvValue::List(vec![
+                        EvalResult::CInt(1),
+                        EvalResult::List(vec![EvalResult::CInt(1)]),
+                    ]))
+                );
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn nested_calls_share_step_budget() {
+        /*
+         * Test that nested calls draw on one step budget
+         *
+         * > def inner() -> CInt:
+         * >    x = 1
+         * >    x = x + 1
+         * >    return x
+         * >
+         * > def middle() -> CInt:
+         * >    y = inner()
+         * >    y = y + inner()
+         * >    return y
+         * >
+         * > def outer() -> CInt:
+         * >    z = middle()
+         * >    z = z + middle()
+         * >    return z
+         * >
+         * > result = outer()
+         *
+         * Each call to 'middle' fits in a budget of 20 statements on its
+         * own, but the whole call tree does not, so running with that
+         * budget must fail while a larger budget lets 'result' reach 8.
+         */
+        let function = |name: &str, var: &str, callee: Option<&str>| {
+            let first = match callee {
+                Some(callee) => Expression::FuncCall(String::from(callee), None),
+                None => Expression::CInt(1),
+            };
+            let second = match callee {
+                Some(callee) => Expression::FuncCall(String::from(callee), None),
+                None => Expression::CInt(1),
+            };
+            Statement::Func(
+                Box::new(String::from(name)),
+                Box::new(Type::TInteger),
+                None,
+                Some(Box::new(Statement::Sequence(
+                    Box::new(Statement::Assignment(
+                        Box::new(String::from(var)),
+                        Box::new(first),
+                    )),
+                    Box::new(Statement::Assignment(
+                        Box::new(String::from(var)),
+                        Box::new(Expression::Add(
+                            Box::new(Expression::Var(String::from(var))),
+                            Box::new(second),
+                        )),
+                    )),
+                ))),
+                Box::new(Expression::Var(String::from(var))),
+            )
+        };
+
+        let program = Statement::Sequence(
+            Box::new(function("inner", "x", None)),
+            Box::new(Statement::Sequence(
+                Box::new(function("middle", "y", Some("inner"))),
+                Box::new(Statement::Sequence(
+                    Box::new(function("outer", "z", Some("middle"))),
+                    Box::new(Statement::Assignment(
+                        Box::new(String::from("result")),
+                        Box::new(Expression::FuncCall(String::from("outer"), None)),
+                    )),
+                )),
+            )),
+        );
+
+        match Interpreter::new()
+            .with_max_steps(100)
+            .execute(&program, Environment::new())
+        {
+            Ok(new_env) => match new_env.get("result") {
+                Some(EnvValue::CInt(8)) => {}
+                Some(val) => assert!(false, "Expected 8, got {:?}", val),
+                None => assert!(false, "Variable result not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+
+        match Interpreter::new()
+            .with_max_steps(20)
+            .execute(&program, Environment::new())
+        {
+            Ok(_) => assert!(false, "Expected the step budget to be exceeded"),
+            Err(s) => assert_eq!(s.root_cause(), &InterpreterError::StepLimitExceeded),
+        }
+    }
+
+    #[test]
+    fn func_counter_closure() {
+        /*
+         * Test for a closure updating a variable captured from the
+         * function that created it
+         *
+         * > def make_counter() -> Callable:
+         * >    count = 0
+         * >    def increment() -> CInt:
+         * >       nonlocal count
+         * >       count = count + 1
+         * >       return count
+         * >    return increment
+         * >
+         * > counter = make_counter()
+         * > a = counter()
+         * > b = counter()
+         * > c = counter()
+         * > other = make_counter()
+         * > d = other()
+         *
+         * After executing, 'a', 'b' and 'c' should be 1, 2 and 3, while
+         * 'd' should be 1 since every counter gets a count of its own.
+         */
+        let env = Environment::new();
+
+        let increment = Statement::Func(
+            Box::new(String::from("increment")),
+            Box::new(Type::TInteger),
+            None,
+            Some(Box::new(Statement::Sequence(
+                Box::new(Statement::Nonlocal(vec![String::from("count")])),
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("count")),
+                    Box::new(Expression::Add(
+                        Box::new(Expression::Var(String::from("count"))),
+                        Box::new(Expression::CInt(1)),
+                    )),
+                )),
+            ))),
+            Box::new(Expression::Var(String::from("count"))),
+        );
+
+        let make_counter = Statement::Func(
+            Box::new(String::from("make_counter")),
+            Box::new(Type::TFunction),
+            None,
+            Some(Box::new(Statement::Sequence(
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("count")),
+                    Box::new(Expression::CInt(0)),
+                )),
+                Box::new(increment),
+            ))),
+            Box::new(Expression::Var(String::from("increment"))),
+        );
+
+        let call = |var: &str, func: &str| {
+            Statement::Assignment(
+                Box::new(String::from(var)),
+                Box::new(Expression::FuncCall(String::from(func), None)),
+            )
+        };
+
+        let program = [
+            call("counter", "make_counter"),
+            call("a", "counter"),
+            call("b", "counter"),
+            call("c", "counter"),
+            call("other", "make_counter"),
+            call("d", "other"),
+        ]
+        .into_iter()
+        .fold(make_counter, |rest, stmt| {
+            Statement::Sequence(Box::new(rest), Box::new(stmt))
+        });
+
+        match execute(&program, env) {
+            Ok(new_env) => {
+                for (var, expected) in [("a", 1), ("b", 2), ("c", 3), ("d", 1)] {
+                    match new_env.get(var) {
+                        Some(EnvValue::CInt(n)) => {
+                            assert_eq!(n, expected, "Wrong value for {}", var)
+                        }
+                        Some(val) => assert!(false, "Expected {}, got {:?}", expected, val),
+                        None => assert!(false, "Variable {} not found", var),
+                    }
+                }
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn interpreter_error_messages() {
+        let error = InterpreterError::FunctionError {
+            name: String::from("outer"),
+            error: Box::new(InterpreterError::ConditionError(Box::new(
+                InterpreterError::UndefinedVariable(String::from("x")),
+            ))),
+            call: None,
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "outer generated an error: Condition resulted in an error: Variable x not found"
+        );
+        assert_eq!(
+            error.root_cause(),
+            &InterpreterError::UndefinedVariable(String::from("x"))
+        );
+        assert_eq!(
+            InterpreterError::ArityMismatch {
+                name: String::from("add"),
+                expected: 2,
+                got: 3
+            }
+            .to_string(),
+            "add requires 2 arguments, got 3"
+        );
+    }
+
+    #[test]
+    fn eval_large_list() {
+        /*
+         * The list arm allocates room for every item up front, so a large
+         * literal is built without growing the vector along the way.
+         */
+        let env = Environment::new();
+        let items: Vec<Expression> = (0..10_000).map(Expression::CInt).collect();
+
+        match eval(&Expression::List(items), &env) {
+            Ok(EvalResult::List(values)) => {
+                assert_eq!(values.len(), 10_000);
+                assert_eq!(values.first(), Some(&EvalResult::CInt(0)));
+                assert_eq!(values.last(), Some(&EvalResult::CInt(9_999)));
+            }
+            Ok(val) => assert!(false, "Expected a list, got {:?}", val),
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn for_loop_over_tuple() {
+        /*
+         * Test for iterating a tuple whose elements have different types
+         *
+         * > items = ()
+         * > for x in (1, 2.5, "a", True, None, [1, 2]):
+         * >   items = items + (x,)
+         *
+         * After executing, 'items' should hold the elements in order, with
+         * the type tags int, real, str, bool, NoneType and list.
+         */
+        let env = Environment::new();
+
+        let tuple = Expression::Tuple(vec![
+            Expression::CInt(1),
+            Expression::CReal(2.5),
+            Expression::CString(String::from("a")),
+            Expression::Bool(true),
+            Expression::None,
+            Expression::List(vec![Expression::CInt(1), Expression::CInt(2)]),
+        ]);
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Assignment(
+                Box::new(String::from("items")),
+                Box::new(Expression::Tuple(vec![])),
+            )),
+            Box::new(Statement::For(
+                Box::new(String::from("x")),
+                Box::new(tuple),
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("items")),
+                    Box::new(Expression::Add(
+                        Box::new(Expression::Var(String::from("items"))),
+                        Box::new(Expression::Tuple(vec![Expression::Var(String::from("x"))])),
+                    )),
+                )),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => match new_env.get("items") {
+                Some(EnvValue::Tuple(items)) => {
+                    let tags: Vec<&str> = items.iter().map(|item| item.type_name()).collect();
+                    assert_eq!(tags, vec!["int", "real", "str", "bool", "NoneType", "list"]);
+                }
+                Some(val) => assert!(false, "Expected a tuple, got {:?}", val),
+                None => assert!(false, "Variable items not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn eval_dict_index() {
+        /*
+         * Test for building a dict from a literal and reading its values
+         *
+         * > ages = {"ana": 30, "bia": 25, 7: True}
+         * > a = ages["bia"]
+         * > b = ages[7]
+         *
+         * After executing, 'a' should be 25 and 'b' should be True.
+         */
+        let env = Environment::new();
+
+        let dict = Expression::Dict(vec![
+            (
+                Expression::CString(String::from("ana")),
+                Expression::CInt(30),
+            ),
+            (
+                Expression::CString(String::from("bia")),
+                Expression::CInt(25),
+            ),
+            (Expression::CInt(7), Expression::Bool(true)),
+        ]);
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Assignment(
+                Box::new(String::from("ages")),
+                Box::new(dict),
+            )),
+            Box::new(Statement::Sequence(
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("a")),
+                    Box::new(Expression::Index(
+                        Box::new(Expression::Var(String::from("ages"))),
+                        Box::new(Expression::CString(String::from("bia"))),
+                    )),
+                )),
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("b")),
+                    Box::new(Expression::Index(
+                        Box::new(Expression::Var(String::from("ages"))),
+                        Box::new(Expression::CInt(7)),
+                    )),
+                )),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("a"), Some(EnvValue::CInt(25)));
+                assert_eq!(new_env.get("b"), Some(EnvValue::Bool(true)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn dict_key_errors() {
+        /*
+         * > {"a": 1}["b"]      # KeyError: 'b'
+         * > {[1]: 1}           # unhashable type: 'list'
+         * > {None: 1}          # unhashable type: 'NoneType'
+         */
+        let env = Environment::new();
+
+        let missing = Expression::Index(
+            Box::new(Expression::Dict(vec![(
+                Expression::CString(String::from("a")),
+                Expression::CInt(1),
+            )])),
+            Box::new(Expression::CString(String::from("b"))),
+        );
+        match eval(&missing, &env) {
+            Err(e) => {
+                assert_eq!(e, InterpreterError::KeyError(String::from("'b'")));
+                assert_eq!(e.to_string(), "KeyError: 'b'");
+            }
+            Ok(v) => assert!(false, "Expected a KeyError, got {:?}", v),
+        }
+
+        let list_key = Expression::Dict(vec![(
+            Expression::List(vec![Expression::CInt(1)]),
+            Expression::CInt(1),
+        )]);
+        match eval(&list_key, &env) {
+            Err(e) => assert_eq!(e.to_string(), "unhashable type: 'list'"),
+            Ok(v) => assert!(false, "Expected an error, got {:?}", v),
+        }
+
+        let none_key = Expression::Dict(vec![(Expression::None, Expression::CInt(1))]);
+        match eval(&none_key, &env) {
+            Err(e) => assert_eq!(e.to_string(), "unhashable type: 'NoneType'"),
+            Ok(v) => assert!(false, "Expected an error, got {:?}", v),
+        }
+    }
+
+    #[test]
+    fn argument_type_error_names_parameter() {
+        /*
+         * > def scale(n: int, b: real) -> real:
+         * >    return n * b
+         * >
+         * > x = scale(2, [1.0])
+         *
+         * The error should name the parameter and both types involved.
+         */
+        let env = Environment::new();
+
+        let program = Statement::Sequence(
+            Box::new(Statement::Func(
+                Box::new(String::from("scale")),
+                Box::new(Type::TReal),
+                Some(vec![
+                    (String::from("n"), Box::new(Type::TInteger)),
+                    (String::from("b"), Box::new(Type::TReal)),
+                ]),
+                None,
+                Box::new(Expression::Mul(
+                    Box::new(Expression::Var(String::from("n"))),
+                    Box::new(Expression::Var(String::from("b"))),
+                )),
+            )),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("x")),
+                Box::new(Expression::FuncCall(
+                    String::from("scale"),
+                    Some(vec![
+                        Expression::CInt(2),
+                        Expression::List(vec![Expression::CReal(1.0)]),
+                    ]),
+                )),
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(_) => assert!(false, "Function should generate an error"),
+            Err(s) => assert_eq!(s.to_string(), "argument 'b' expected real but got list"),
+        }
+    }
+
+    #[test]
+    fn call_non_callable_value() {
+        /*
+         * > [1, 2][0](3)
+         */
+        let env = Environment::new();
+
+        let call = Expression::Call(
+            Box::new(Expression::Index(
+                Box::new(Expression::List(vec![
+                    Expression::CInt(1),
+                    Expression::CInt(2),
+                ])),
+                Box::new(Expression::CInt(0)),
+            )),
+            Some(vec![Expression::CInt(3)]),
+        );
+
+        match eval(&call, &env) {
+            Err(e) => assert_eq!(e.to_string(), "'int' object is not callable"),
+            Ok(v) => assert!(false, "Expected an error, got {:?}", v),
+        }
+    }
+
+    #[test]
+    fn multi_assign_unpack() {
+        /*
+         * > a, b = 1, 2.5
+         * > c, d = [3, 4]
+         *
+         * After executing, 'a' should be 1, 'b' 2.5, 'c' 3 and 'd' 4.
+         */
+        let env = Environment::new();
+
+        let program = Statement::Sequence(
+            Box::new(Statement::MultiAssign(
+                vec![String::from("a"), String::from("b")],
+                vec![Expression::CInt(1), Expression::CReal(2.5)],
+            )),
+            Box::new(Statement::MultiAssign(
+                vec![String::from("c"), String::from("d")],
+                vec![Expression::List(vec![
+                    Expression::CInt(3),
+                    Expression::CInt(4),
+                ])],
+            )),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("a"), Some(EnvValue::CInt(1)));
+                assert_eq!(new_env.get("b"), Some(EnvValue::CReal(2.5)));
+                assert_eq!(new_env.get("c"), Some(EnvValue::CInt(3)));
+                assert_eq!(new_env.get("d"), Some(EnvValue::CInt(4)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn multi_assign_swap() {
+        /*
+         * > a = 1
+         * > b = 2
+         * > a, b = b, a
+         *
+         * After executing, 'a' should be 2 and 'b' should be 1.
+         */
+        let env = Environment::from([
+            (String::from("a"), EnvValue::CInt(1)),
+            (String::from("b"), EnvValue::CInt(2)),
+        ]);
+
+        let program = Statement::MultiAssign(
+            vec![String::from("a"), String::from("b")],
+            vec![
+                Expression::Var(String::from("b")),
+                Expression::Var(String::from("a")),
+            ],
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("a"), Some(EnvValue::CInt(2)));
+                assert_eq!(new_env.get("b"), Some(EnvValue::CInt(1)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn multi_assign_count_mismatch() {
+        /*
+         * > a, b = 1, 2, 3
+         * > a, b, c = (1, 2)
+         */
+        let names = vec![String::from("a"), String::from("b")];
+        let program = Statement::MultiAssign(
+            names,
+            vec![
+                Expression::CInt(1),
+                Expression::CInt(2),
+                Expression::CInt(3),
+            ],
+        );
+        match execute(&program, Environment::new()) {
+            Err(e) => assert_eq!(e.to_string(), "too many values to unpack (expected 2)"),
+            Ok(_) => assert!(false, "Assignment should generate an error"),
+        }
+
+        let names = vec![String::from("a"), String::from("b"), String::from("c")];
+        let program = Statement::MultiAssign(
+            names,
+            vec![Expression::Tuple(vec![
+                Expression::CInt(1),
+                Expression::CInt(2),
+            ])],
+        );
+        match execute(&program, Environment::new()) {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "too few values to unpack (expected 3, got 2)"
+            ),
+            Ok(_) => assert!(false, "Assignment should generate an error"),
+        }
+    }
+
+    #[test]
+    fn eval_range_bool_bounds() {
+        /*
+         * Bools in range bounds count as 0 and 1, like in arithmetic
+         *
+         * > range(True, 5)          # [1, 2, 3, 4]
+         * > range(True, False)      # []
+         * > range(0, 3, True)       # [0, 1, 2]
+         */
+        let env = Environment::new();
+
+        let cases = [
+            (
+                Expression::Range(
+                    Some(Box::new(Expression::Bool(true))),
+                    Box::new(Expression::CInt(5)),
+                    None,
+                ),
+                vec![1, 2, 3, 4],
+            ),
+            (
+                Expression::Range(
+                    Some(Box::new(Expression::Bool(true))),
+                    Box::new(Expression::Bool(false)),
+                    None,
+                ),
+                vec![],
+            ),
+            (
+                Expression::Range(
+                    Some(Box::new(Expression::CInt(0))),
+                    Box::new(Expression::CInt(3)),
+                    Some(Box::new(Expression::Bool(true))),
+                ),
+                vec![0, 1, 2],
+            ),
+        ];
+
+        for (range, expected) in cases {
+            let expected = expected.into_iter().map(EvalResult::CInt).collect();
+            assert_eq!(eval(&range, &env), Ok(EvalResult::List(expected)));
+        }
+    }
+
+    #[test]
+    fn eval_range_rejects_real_bounds() {
+        let env = Environment::new();
+
+        let range = Expression::Range(None, Box::new(Expression::CReal(5.0)), None);
+
+        assert_eq!(
+            eval(&range, &env),
+            Err(InterpreterError::TypeError(String::from(
+                "Parameters cannot be converted to integer"
+            )))
+        );
+    }
+
+    #[test]
+    fn truthiness_table() {
+        let mut dict = Dict::new();
+        dict.insert(HashKey::CInt(1), EvalResult::None);
+
+        let cases = [
+            (EvalResult::CInt(0), false),
+            (EvalResult::CInt(-3), true),
+            (EvalResult::CReal(0.0), false),
+            (EvalResult::CReal(-0.0), false),
+            (EvalResult::CReal(0.5), true),
+            (EvalResult::Bool(false), false),
+            (EvalResult::Bool(true), true),
+            (EvalResult::CString(String::new()), false),
+            (EvalResult::CString(String::from("a")), true),
+            (EvalResult::List(vec![]), false),
+            (EvalResult::List(vec![EvalResult::CInt(0)]), true),
+            (EvalResult::Tuple(vec![]), false),
+            (EvalResult::Tuple(vec![EvalResult::None]), true),
+            (EvalResult::Dict(Box::new(Dict::new())), false),
+            (EvalResult::Dict(Box::new(dict)), true),
+            (EvalResult::None, false),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(is_truthy(&value), expected, "truthiness of {}", value);
+        }
+    }
+
+    #[test]
+    fn display_dict() {
+        /*
+         * > str({"a": 1, "b": [2.5], 3: "c"})
+         *
+         * Keys keep their insertion order and strings are quoted.
+         */
+        let env = Environment::new();
+
+        let dict = Expression::Dict(vec![
+            (Expression::CString(String::from("a")), Expression::CInt(1)),
+            (
+                Expression::CString(String::from("b")),
+                Expression::List(vec![Expression::CReal(2.5)]),
+            ),
+            (Expression::CInt(3), Expression::CString(String::from("c"))),
+        ]);
+
+        assert_eq!(
+            eval(
+                &Expression::FuncCall(String::from("str"), Some(vec![dict])),
+                &env
+            ),
+            Ok(EvalResult::CString(String::from(
+                "{'a': 1, 'b': [2.5], 3: 'c'}"
+            )))
+        );
+        assert_eq!(
+            eval(&Expression::Dict(vec![]), &env).map(|value| value.to_string()),
+            Ok(String::from("{}"))
+        );
+    }
+
+    #[test]
+    fn display_set() {
+        /*
+         * > {1, 2, 3, 2}          # {1, 2, 3}
+         * > {"x"}                 # {'x'}
+         * > set()                 # set()
+         */
+        let env = Environment::new();
+
+        let set = Expression::Set(vec![
+            Expression::CInt(1),
+            Expression::CInt(2),
+            Expression::CInt(3),
+            Expression::CInt(2),
+        ]);
+        assert_eq!(
+            eval(&set, &env).map(|value| value.to_string()),
+            Ok(String::from("{1, 2, 3}"))
+        );
+
+        let set = Expression::Set(vec![Expression::CString(String::from("x"))]);
+        assert_eq!(
+            eval(&set, &env).map(|value| value.to_string()),
+            Ok(String::from("{'x'}"))
+        );
+
+        let empty = Expression::FuncCall(String::from("set"), None);
+        assert_eq!(
+            eval(&empty, &env).map(|value| value.to_string()),
+            Ok(String::from("set()"))
+        );
+    }
+
+    #[test]
+    fn builtin_min_max() {
+        /*
+         * > max([3, 1, 2])          # 3
+         * > min(4, 7.5, 2)          # 2
+         * > max(2, 2.0)             # 2, the first of equal values
+         * > min([1.5, 1])           # 1
+         */
+        let env = Environment::new();
+
+        let call = |name: &str, args: Vec<Expression>| {
+            eval(&Expression::FuncCall(String::from(name), Some(args)), &env)
+        };
+
+        assert_eq!(
+            call(
+                "max",
+                vec![Expression::List(vec![
+                    Expression::CInt(3),
+                    Expression::CInt(1),
+                    Expression::CInt(2),
+                ])]
+            ),
+            Ok(EvalResult::CInt(3))
+        );
+        assert_eq!(
+            call(
+                "min",
+                vec![
+                    Expression::CInt(4),
+                    Expression::CReal(7.5),
+                    Expression::CInt(2),
+                ]
+            ),
+            Ok(EvalResult::CInt(2))
+        );
+        assert_eq!(
+            call("max", vec![Expression::CInt(2), Expression::CReal(2.0)]),
+            Ok(EvalResult::CInt(2))
+        );
+        assert_eq!(
+            call(
+                "min",
+                vec![Expression::List(vec![
+                    Expression::CReal(1.5),
+                    Expression::CInt(1),
+                ])]
+            ),
+            Ok(EvalResult::CReal(1.0))
+        );
+    }
+
+    #[test]
+    fn builtin_min_max_errors() {
+        let env = Environment::new();
+
+        let empty = Expression::FuncCall(String::from("min"), Some(vec![Expression::List(vec![])]));
+        assert_eq!(
+            eval(&empty, &env),
+            Err(InterpreterError::ValueError(String::from(
+                "min() arg is an empty sequence"
+            )))
+        );
+
+        let mixed = Expression::FuncCall(
+            String::from("max"),
+            Some(vec![
+                Expression::CInt(1),
+                Expression::CString(String::from("a")),
+            ]),
+        );
+        assert_eq!(
+            eval(&mixed, &env).map_err(|e| e.to_string()),
+            Err(String::from(
+                "'>' not supported between instances of 'str' and 'int'"
+            ))
+        );
+    }
+
+    #[test]
+    fn memoized_recursive_fibonacci() {
+        /*
+         * > def fib(n: int) -> int:
+         * >    if n < 2:
+         * >       return n
+         * >    return fib(n - 1) + fib(n - 2)
+         * >
+         * > fib = memoize(fib)
+         * > x = fib(20)
+         *
+         * The recursive calls go through the memoized binding, so each
+         * fib(k) runs once: the whole program fits in a step budget that
+         * the exponential plain version would blow through.
+         */
+        let call = |arg: i32| {
+            Expression::FuncCall(
+                String::from("fib"),
+                Some(vec![Expression::Sub(
+                    Box::new(Expression::Var(String::from("n"))),
+                    Box::new(Expression::CInt(arg)),
+                )]),
+            )
+        };
+
+        let fib = Statement::Func(
+            Box::new(String::from("fib")),
+            Box::new(Type::TInteger),
+            Some(vec![(String::from("n"), Box::new(Type::TInteger))]),
+            Some(Box::new(Statement::Sequence(
+                Box::new(Statement::If(
+                    Box::new(Expression::Lt(
+                        Box::new(Expression::Var(String::from("n"))),
+                        Box::new(Expression::CInt(2)),
+                    )),
+                    Box::new(Statement::Return(Box::new(Expression::Var(String::from(
+                        "n",
+                    ))))),
+                )),
+                Box::new(Statement::Return(Box::new(Expression::Add(
+                    Box::new(call(1)),
+                    Box::new(call(2)),
+                )))),
+            ))),
+            Box::new(Expression::None),
+        );
+
+        let program = Statement::Sequence(
+            Box::new(fib),
+            Box::new(Statement::Sequence(
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("fib")),
+                    Box::new(Expression::FuncCall(
+                        String::from("memoize"),
+                        Some(vec![Expression::Var(String::from("fib"))]),
+                    )),
+                )),
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("x")),
+                    Box::new(Expression::FuncCall(
+                        String::from("fib"),
+                        Some(vec![Expression::CInt(20)]),
+                    )),
+                )),
+            )),
+        );
+
+        match Interpreter::new()
+            .with_max_steps(1000)
+            .execute(&program, Environment::new())
+        {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("x"), Some(EnvValue::CInt(6765)));
+                match new_env.get("fib") {
+                    Some(EnvValue::Func(func)) => match &func.memo {
+                        Some(memo) => assert_eq!(memo.borrow().len(), 21),
+                        None => assert!(false, "Expected fib to be memoized"),
+                    },
+                    Some(val) => assert!(false, "Expected a function, got {:?}", val),
+                    None => assert!(false, "Variable fib not found"),
+                }
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn memoized_unhashable_argument() {
+        /*
+         * > def first(xs: list[int]) -> int:
+         * >    return 1
+         * >
+         * > first = memoize(first)
+         * > x = first([1, 2])
+         */
+        let program = Statement::Sequence(
+            Box::new(Statement::Func(
+                Box::new(String::from("first")),
+                Box::new(Type::TInteger),
+                Some(vec![(
+                    String::from("xs"),
+                    Box::new(Type::TList(Box::new(Type::TInteger))),
+                )]),
+                None,
+                Box::new(Expression::CInt(1)),
+            )),
+            Box::new(Statement::Sequence(
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("first")),
+                    Box::new(Expression::FuncCall(
+                        String::from("memoize"),
+                        Some(vec![Expression::Var(String::from("first"))]),
+                    )),
+                )),
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("x")),
+                    Box::new(Expression::FuncCall(
+                        String::from("first"),
+                        Some(vec![Expression::List(vec![
+                            Expression::CInt(1),
+                            Expression::CInt(2),
+                        ])]),
+                    )),
+                )),
+            )),
+        );
+
+        match execute(&program, Environment::new()) {
+            Ok(_) => assert!(false, "Expected an unhashable argument error"),
+            Err(s) => assert_eq!(
+                s.to_string(),
+                "memoized function first got an unhashable argument of type 'list'"
+            ),
+        }
+    }
+
+    #[test]
+    fn builtin_abs() {
+        /*
+         * > abs(-5)             # 5
+         * > abs(-2147483648)    # too large for an int
+         * > abs(-2.5)           # 2.5
+         * > abs(True)           # 1
+         * > abs([1])            # bad operand type for abs(): 'list'
+         * > abs(None)           # bad operand type for abs(): 'NoneType'
+         */
+        let env = Environment::new();
+
+        let abs = |arg: Expression| {
+            eval(
+                &Expression::FuncCall(String::from("abs"), Some(vec![arg])),
+                &env,
+            )
+        };
+
+        assert_eq!(abs(Expression::CInt(-5)), Ok(EvalResult::CInt(5)));
+        assert_eq!(
+            abs(Expression::CInt(i32::MIN)),
+            Err(InterpreterError::IntegerOverflow)
+        );
+        assert_eq!(
+            abs(Expression::CInt(i32::MIN + 1)),
+            Ok(EvalResult::CInt(i32::MAX))
+        );
+        assert_eq!(abs(Expression::CReal(-2.5)), Ok(EvalResult::CReal(2.5)));
+        assert_eq!(abs(Expression::Bool(true)), Ok(EvalResult::CInt(1)));
+        assert_eq!(
+            abs(Expression::List(vec![Expression::CInt(1)])).map_err(|e| e.to_string()),
+            Err(String::from("bad operand type for abs(): 'list'"))
+        );
+        assert_eq!(
+            abs(Expression::None).map_err(|e| e.to_string()),
+            Err(String::from("bad operand type for abs(): 'NoneType'"))
+        );
+    }
+
+    #[test]
+    fn builtin_floor_ceil_round() {
+        /*
+         * > floor(2.7)      # 2
+         * > ceil(2.1)       # 3
+         * > round(2.5)      # 2
+         * > round(3.5)      # 4
+         * > floor(-2.5)     # -3
+         * > ceil(True)      # 1
+         * > round(7)        # 7
+         * > floor([1])      # must be real number, not 'list'
+         * > round(None)     # must be real number, not 'NoneType'
+         */
+        let env = Environment::new();
+
+        let call = |name: &str, arg: Expression| {
+            eval(
+                &Expression::FuncCall(String::from(name), Some(vec![arg])),
+                &env,
+            )
+        };
+
+        assert_eq!(
+            call("floor", Expression::CReal(2.7)),
+            Ok(EvalResult::CInt(2))
+        );
+        assert_eq!(
+            call("ceil", Expression::CReal(2.1)),
+            Ok(EvalResult::CInt(3))
+        );
+        assert_eq!(
+            call("round", Expression::CReal(2.5)),
+            Ok(EvalResult::CInt(2))
+        );
+        assert_eq!(
+            call("round", Expression::CReal(3.5)),
+            Ok(EvalResult::CInt(4))
+        );
+        assert_eq!(
+            call("round", Expression::CReal(-0.5)),
+            Ok(EvalResult::CInt(0))
+        );
+        assert_eq!(
+            call("floor", Expression::CReal(-2.5)),
+            Ok(EvalResult::CInt(-3))
+        );
+        assert_eq!(
+            call("ceil", Expression::Bool(true)),
+            Ok(EvalResult::CInt(1))
+        );
+        assert_eq!(call("round", Expression::CInt(7)), Ok(EvalResult::CInt(7)));
+        assert_eq!(
+            call("floor", Expression::List(vec![Expression::CInt(1)])).map_err(|e| e.to_string()),
+            Err(String::from("must be real number, not 'list'"))
+        );
+        assert_eq!(
+            call("round", Expression::None).map_err(|e| e.to_string()),
+            Err(String::from("must be real number, not 'NoneType'"))
+        );
+        assert_eq!(
+            call("ceil", Expression::CReal(1e20)),
+            Err(InterpreterError::IntegerOverflow)
+        );
+    }
+
+    #[test]
+    fn user_function_shadows_builtin() {
+        /*
+         * > def abs(x: int) -> int:
+         * >    return 0
+         * >
+         * > y = abs(-5)
+         *
+         * After executing, 'y' should be 0.
+         */
+        let program = Statement::Sequence(
+            Box::new(Statement::Func(
+                Box::new(String::from("abs")),
+                Box::new(Type::TInteger),
+                Some(vec![(String::from("x"), Box::new(Type::TInteger))]),
+                None,
+                Box::new(Expression::CInt(0)),
+            )),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("y")),
+                Box::new(Expression::FuncCall(
+                    String::from("abs"),
+                    Some(vec![Expression::CInt(-5)]),
+                )),
+            )),
+        );
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => assert_eq!(new_env.get("y"), Some(EnvValue::CInt(0))),
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn func_optional_parameter() {
+        /*
+         * > def or_zero(x: Optional[int]) -> int:
+         * >    if x == None:
+         * >       return 0
+         * >    return x
+         * >
+         * > a = or_zero(None)
+         * > b = or_zero(7)
+         * > c = or_zero("7")
+         *
+         * 'a' should be 0 and 'b' 7, while the last call is a type error.
+         */
+        let or_zero = Statement::Func(
+            Box::new(String::from("or_zero")),
+            Box::new(Type::TInteger),
+            Some(vec![(
+                String::from("x"),
+                Box::new(Type::TOptional(Box::new(Type::TInteger))),
+            )]),
+            Some(Box::new(Statement::Sequence(
+                Box::new(Statement::If(
+                    Box::new(Expression::Eq(
+                        Box::new(Expression::Var(String::from("x"))),
+                        Box::new(Expression::None),
+                    )),
+                    Box::new(Statement::Return(Box::new(Expression::CInt(0)))),
+                )),
+                Box::new(Statement::Return(Box::new(Expression::Var(String::from(
+                    "x",
+                ))))),
+            ))),
+            Box::new(Expression::None),
+        );
+        let call = |var: &str, arg: Expression| {
+            Statement::Assignment(
+                Box::new(String::from(var)),
+                Box::new(Expression::FuncCall(
+                    String::from("or_zero"),
+                    Some(vec![arg]),
+                )),
+            )
+        };
+
+        let program = Statement::Sequence(
+            Box::new(or_zero.clone()),
+            Box::new(Statement::Sequence(
+                Box::new(call("a", Expression::None)),
+                Box::new(call("b", Expression::CInt(7))),
+            )),
+        );
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("a"), Some(EnvValue::CInt(0)));
+                assert_eq!(new_env.get("b"), Some(EnvValue::CInt(7)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+
+        let program = Statement::Sequence(
+            Box::new(or_zero),
+            Box::new(call("c", Expression::CString(String::from("7")))),
+        );
+        match execute(&program, Environment::new()) {
+            Ok(_) => assert!(false, "Function should generate an error"),
+            Err(s) => assert_eq!(
+                s.to_string(),
+                "argument 'x' expected Optional[int] but got str"
+            ),
+        }
+    }
+
+    #[test]
+    fn builtin_sum() {
+        /*
+         * > sum([1, 2, 3])            # 6
+         * > sum([1.5, 2])             # 3.5
+         * > sum([])                   # 0
+         * > sum([], 10)               # 10
+         * > sum([1, 2], 0.5)          # 3.5
+         */
+        let env = Environment::new();
+
+        let sum = |args: Vec<Expression>| {
+            eval(&Expression::FuncCall(String::from("sum"), Some(args)), &env)
+        };
+        let list = |items: Vec<Expression>| Expression::List(items);
+
+        assert_eq!(
+            sum(vec![list(vec![
+                Expression::CInt(1),
+                Expression::CInt(2),
+                Expression::CInt(3),
+            ])]),
+            Ok(EvalResult::CInt(6))
+        );
+        assert_eq!(
+            sum(vec![list(vec![
+                Expression::CReal(1.5),
+                Expression::CInt(2)
+            ])]),
+            Ok(EvalResult::CReal(3.5))
+        );
+        assert_eq!(sum(vec![list(vec![])]), Ok(EvalResult::CInt(0)));
+        assert_eq!(
+            sum(vec![list(vec![]), Expression::CInt(10)]),
+            Ok(EvalResult::CInt(10))
+        );
+        assert_eq!(
+            sum(vec![
+                list(vec![Expression::CInt(1), Expression::CInt(2)]),
+                Expression::CReal(0.5),
+            ]),
+            Ok(EvalResult::CReal(3.5))
+        );
+    }
+
+    #[test]
+    fn builtin_sum_non_numeric() {
+        /*
+         * > sum(["a", "b"])
+         */
+        let env = Environment::new();
+
+        let call = Expression::FuncCall(
+            String::from("sum"),
+            Some(vec![Expression::List(vec![
+                Expression::CString(String::from("a")),
+                Expression::CString(String::from("b")),
+            ])]),
+        );
+
+        assert_eq!(
+            eval(&call, &env).map_err(|e| e.to_string()),
+            Err(String::from(
+                "unsupported operand type(s) for +: 'int' and 'str'"
+            ))
+        );
+    }
+
+    #[test]
+    fn eval_nested_index() {
+        /*
+         * > m = [[1, 2, 3], [4, 5, 6]]
+         * > x = m[1][2]
+         * > y = m[1][5]
+         *
+         * 'x' should be 6, while reading 'y' is out of range on the inner
+         * list.
+         */
+        let row =
+            |items: [i32; 3]| Expression::List(items.into_iter().map(Expression::CInt).collect());
+        let env = Environment::from([(
+            String::from("m"),
+            EnvValue::List(vec![
+                eval(&row([1, 2, 3]), &Environment::new()).unwrap(),
+                eval(&row([4, 5, 6]), &Environment::new()).unwrap(),
+            ]),
+        )]);
+
+        let index = |i: i32, j: i32| {
+            Expression::Index(
+                Box::new(Expression::Index(
+                    Box::new(Expression::Var(String::from("m"))),
+                    Box::new(Expression::CInt(i)),
+                )),
+                Box::new(Expression::CInt(j)),
+            )
+        };
+
+        assert_eq!(eval(&index(1, 2), &env), Ok(EvalResult::CInt(6)));
+        assert_eq!(eval(&index(-2, -3), &env), Ok(EvalResult::CInt(1)));
+        assert_eq!(
+            eval(&index(1, 5), &env),
+            Err(InterpreterError::IndexError(String::from(
+                "list index out of range"
+            )))
+        );
+    }
+
+    #[test]
+    fn nested_index_assignment() {
+        /*
+         * > m = [[1, 2], [3, 4]]
+         * > m[0][1] = 9
+         * > m[-1][0] = m[0][1] + 1
+         *
+         * After executing, 'm' should be [[1, 9], [10, 4]].
+         */
+        let row = |a: i32, b: i32| EvalResult::List(vec![EvalResult::CInt(a), EvalResult::CInt(b)]);
+        let env = Environment::from([(
+            String::from("m"),
+            EnvValue::List(vec![row(1, 2), row(3, 4)]),
+        )]);
+
+        let cell = |i: i32, j: i32| {
+            Expression::Index(
+                Box::new(Expression::Index(
+                    Box::new(Expression::Var(String::from("m"))),
+                    Box::new(Expression::CInt(i)),
+                )),
+                Box::new(Expression::CInt(j)),
+            )
+        };
+        let assign = |i: i32, j: i32, exp: Expression| {
+            Statement::IndexAssign(
+                Box::new(Expression::Index(
+                    Box::new(Expression::Var(String::from("m"))),
+                    Box::new(Expression::CInt(i)),
+                )),
+                Box::new(Expression::CInt(j)),
+                Box::new(exp),
+            )
+        };
+
+        let program = Statement::Sequence(
+            Box::new(assign(0, 1, Expression::CInt(9))),
+            Box::new(assign(
+                -1,
+                0,
+                Expression::Add(Box::new(cell(0, 1)), Box::new(Expression::CInt(1))),
+            )),
+        );
+
+        match execute(&program, env.clone()) {
+            Ok(new_env) => assert_eq!(
+                new_env.get("m"),
+                Some(EnvValue::List(vec![row(1, 9), row(10, 4)]))
+            ),
+            Err(s) => assert!(false, "{}", s),
+        }
+
+        match execute(&assign(0, 2, Expression::CInt(0)), env) {
+            Ok(_) => assert!(false, "Assignment should generate an error"),
+            Err(s) => assert_eq!(
+                s,
+                InterpreterError::IndexError(String::from("list assignment index out of range"))
+            ),
+        }
+    }
+
+    #[test]
+    fn deep_recursion_shares_environment() {
+        /*
+         * > def down(n: int) -> int:
+         * >    if n == 0:
+         * >       return 0
+         * >    return 1 + down(n - 1)
+         * >
+         * > x = down(500)
+         *
+         * The program runs with ten thousand other variables in scope.
+         * Every call shares the module scope instead of copying it, which
+         * would otherwise make each of the 500 nested calls pay for all
+         * of them.
+         */
+        let down = Statement::Func(
+            Box::new(String::from("down")),
+            Box::new(Type::TInteger),
+            Some(vec![(String::from("n"), Box::new(Type::TInteger))]),
+            Some(Box::new(Statement::Sequence(
+                Box::new(Statement::If(
+                    Box::new(Expression::Eq(
+                        Box::new(Expression::Var(String::from("n"))),
+                        Box::new(Expression::CInt(0)),
+                    )),
+                    Box::new(Statement::Return(Box::new(Expression::CInt(0)))),
+                )),
+                Box::new(Statement::Return(Box::new(Expression::Add(
+                    Box::new(Expression::CInt(1)),
+                    Box::new(Expression::FuncCall(
+                        String::from("down"),
+                        Some(vec![Expression::Sub(
+                            Box::new(Expression::Var(String::from("n"))),
+                            Box::new(Expression::CInt(1)),
+                        )]),
+                    )),
+                )))),
+            ))),
+            Box::new(Expression::None),
+        );
+
+        let program = Statement::Sequence(
+            Box::new(down),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("x")),
+                Box::new(Expression::FuncCall(
+                    String::from("down"),
+                    Some(vec![Expression::CInt(500)]),
+                )),
+            )),
+        );
+
+        let mut env = Environment::new();
+        for i in 0..10_000 {
+            env.insert(format!("v{}", i), EnvValue::CInt(i));
+        }
+        match execute(&program, env) {
+            Ok(new_env) => assert_eq!(new_env.get("x"), Some(EnvValue::CInt(500))),
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn eval_string_comparisons() {
+        /*
+         * > "A" < "a"          # True, uppercase letters come first
+         * > "abc" == "abc"     # True
+         * > "abc" < "abd"      # True
+         * > "abc" == "ABC"     # False
+         * > "b" > "abc"        # True
+         * > "é" > "z"          # True, by code point
+         */
+        let env = Environment::new();
+        let s = |v: &str| Box::new(Expression::CString(String::from(v)));
+
+        let cases = [
+            (Expression::Lt(s("A"), s("a")), true),
+            (Expression::Eq(s("abc"), s("abc")), true),
+            (Expression::Lt(s("abc"), s("abd")), true),
+            (Expression::Eq(s("abc"), s("ABC")), false),
+            (Expression::Gt(s("b"), s("abc")), true),
+            (Expression::Gt(s("é"), s("z")), true),
+        ];
+
+        for (exp, expected) in cases {
+            assert_eq!(
+                eval(&exp, &env),
+                Ok(EvalResult::Bool(expected)),
+                "{:?}",
+                exp
+            );
+        }
+    }
+
+    #[test]
+    fn zip_three_lists_truncates_to_shortest() {
+        /*
+         * > total = 0
+         * > count = 0
+         * > for a, b, c in zip([1, 2, 3], [10, 20], [100, 200, 300, 400]):
+         * >     total = total + a + b + c
+         * >     count = count + 1
+         *
+         * After executing, 'count' should be 2 (the length of the shortest
+         * list) and 'total' should be 111 + 222 = 333.
+         */
+        let env = Environment::from([
+            (String::from("total"), EnvValue::CInt(0)),
+            (String::from("count"), EnvValue::CInt(0)),
+        ]);
+
+        let list =
+            |items: Vec<i32>| Expression::List(items.into_iter().map(Expression::CInt).collect());
+        let zipped = Expression::FuncCall(
+            String::from("zip"),
+            Some(vec![
+                list(vec![1, 2, 3]),
+                list(vec![10, 20]),
+                list(vec![100, 200, 300, 400]),
+            ]),
+        );
+
+        match eval(&zipped, &env) {
+            Ok(EvalResult::List(rows)) => {
+                assert_eq!(rows.len(), 2);
+                assert_eq!(
+                    rows[1],
+                    EvalResult::Tuple(vec![
+                        EvalResult::CInt(2),
+                        EvalResult::CInt(20),
+                        EvalResult::CInt(200),
+                    ])
+                );
+            }
+            Ok(val) => assert!(false, "Expected a list, got {:?}", val),
+            Err(s) => assert!(false, "{}", s),
+        }
+
+        let var = |name: &str| Expression::Var(String::from(name));
+        let body = Statement::Sequence(
+            Box::new(Statement::Assignment(
+                Box::new(String::from("total")),
+                Box::new(Expression::Add(
+                    Box::new(Expression::Add(
+                        Box::new(Expression::Add(Box::new(var("total")), Box::new(var("a")))),
+                        Box::new(var("b")),
+                    )),
+                    Box::new(var("c")),
+                )),
+            )),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("count")),
+                Box::new(Expression::Add(
+                    Box::new(var("count")),
+                    Box::new(Expression::CInt(1)),
+                )),
+            )),
+        );
+        let program = Statement::ForUnpack(
+            vec![String::from("a"), String::from("b"), String::from("c")],
+            Box::new(zipped),
+            Box::new(body),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("count"), Some(EnvValue::CInt(2)));
+                assert_eq!(new_env.get("total"), Some(EnvValue::CInt(333)));
+                assert_eq!(new_env.get("a"), None);
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn for_unpack_count_mismatch() {
+        /*
+         * > for a, b in [(1, 2, 3)]:
+         * >     x = a
+         */
+        let program = Statement::ForUnpack(
+            vec![String::from("a"), String::from("b")],
+            Box::new(Expression::List(vec![Expression::Tuple(vec![
+                Expression::CInt(1),
+                Expression::CInt(2),
+                Expression::CInt(3),
+            ])])),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("x")),
+                Box::new(Expression::Var(String::from("a"))),
+            )),
+        );
+
+        match execute(&program, Environment::new()) {
+            Err(e) => assert_eq!(e.to_string(), "too many values to unpack (expected 2)"),
+            Ok(_) => assert!(false, "Loop should generate an error"),
+        }
+    }
+
+    #[test]
+    fn eval_list_comprehension() {
+        /*
+         * > i = 10
+         * > squares = [i*i for i in range(5) if i > 1]
+         *
+         * 'squares' should be [4, 9, 16] and 'i' should still be 10.
+         */
+        let env = Environment::from([(String::from("i"), EnvValue::CInt(10))]);
+
+        let var = || Box::new(Expression::Var(String::from("i")));
+        let comp = Expression::ListComp {
+            element: Box::new(Expression::Mul(var(), var())),
+            var: String::from("i"),
+            iterable: Box::new(Expression::Range(None, Box::new(Expression::CInt(5)), None)),
+            condition: Some(Box::new(Expression::Gt(
+                var(),
+                Box::new(Expression::CInt(1)),
+            ))),
+        };
+        let program = Statement::Assignment(Box::new(String::from("squares")), Box::new(comp));
+
+        match execute(&program, env) {
+            Ok(new_env) => {
+                assert_eq!(
+                    new_env.get("squares"),
+                    Some(EnvValue::List(vec![
+                        EvalResult::CInt(4),
+                        EvalResult::CInt(9),
+                        EvalResult::CInt(16),
+                    ]))
+                );
+                assert_eq!(new_env.get("i"), Some(EnvValue::CInt(10)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn eval_list_comprehension_does_not_leak() {
+        /*
+         * > xs = [c for c in "ab"]
+         * > c
+         *
+         * 'xs' should be ['a', 'b'] and 'c' should be undefined.
+         */
+        let comp = Expression::ListComp {
+            element: Box::new(Expression::Var(String::from("c"))),
+            var: String::from("c"),
+            iterable: Box::new(Expression::CString(String::from("ab"))),
+            condition: None,
+        };
+        let program = Statement::Assignment(Box::new(String::from("xs")), Box::new(comp));
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(
+                    new_env.get("xs"),
+                    Some(EnvValue::List(vec![
+                        EvalResult::CString(String::from("a")),
+                        EvalResult::CString(String::from("b")),
+                    ]))
+                );
+                assert_eq!(new_env.get("c"), None);
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn builtin_fsum_is_more_accurate_than_sum() {
+        /*
+         * > xs = [100000000.0, 1.0, -100000000.0, 1.0, 1.0]
+         * > sum(xs)                   # 1.0, two of the ones are lost
+         * > fsum(xs)                  # 3.0
+         */
+        let env = Environment::new();
+
+        let xs = Expression::List(
+            [100000000.0, 1.0, -100000000.0, 1.0, 1.0]
+                .into_iter()
+                .map(Expression::CReal)
+                .collect(),
+        );
+        let call = |name: &str| {
+            eval(
+                &Expression::FuncCall(String::from(name), Some(vec![xs.clone()])),
+                &env,
+            )
+        };
+
+        match (call("sum"), call("fsum")) {
+            (Ok(EvalResult::CReal(naive)), Ok(EvalResult::CReal(accurate))) => {
+                assert_eq!(accurate, 3.0);
+                assert!((accurate - 3.0).abs() < (naive - 3.0).abs());
+            }
+            (naive, accurate) => {
+                assert!(false, "Expected reals, got {:?} and {:?}", naive, accurate)
+            }
+        }
+    }
+
+    #[test]
+    fn builtin_fsum_non_numeric() {
+        /*
+         * > fsum([1, 2])              # 3.0
+         * > fsum(["a"])
+         */
+        let env = Environment::new();
+
+        let fsum = |items: Vec<Expression>| {
+            eval(
+                &Expression::FuncCall(String::from("fsum"), Some(vec![Expression::List(items)])),
+                &env,
+            )
+            .map_err(|e| e.to_string())
+        };
+
+        assert_eq!(
+            fsum(vec![Expression::CInt(1), Expression::CInt(2)]),
+            Ok(EvalResult::CReal(3.0))
+        );
+        assert_eq!(
+            fsum(vec![Expression::CString(String::from("a"))]),
+            Err(String::from("must be real number, not str"))
+        );
+    }
+
+    #[test]
+    fn append_in_loop() {
+        /*
+         * > xs = []
+         * > for i in range(1, 4):
+         * >     append(xs, i)
+         *
+         * After executing, 'xs' should be [1, 2, 3].
+         */
+        let env = Environment::from([(String::from("xs"), EnvValue::List(vec![]))]);
+
+        let program = Statement::For(
+            Box::new(String::from("i")),
+            Box::new(Expression::Range(
+                Some(Box::new(Expression::CInt(1))),
+                Box::new(Expression::CInt(4)),
+                None,
+            )),
+            Box::new(Statement::Expr(Box::new(Expression::FuncCall(
+                String::from("append"),
+                Some(vec![
+                    Expression::Var(String::from("xs")),
+                    Expression::Var(String::from("i")),
+                ]),
+            )))),
+        );
+
+        match execute(&program, env) {
+            Ok(new_env) => assert_eq!(
+                new_env.get("xs"),
+                Some(EnvValue::List(vec![
+                    EvalResult::CInt(1),
+                    EvalResult::CInt(2),
+                    EvalResult::CInt(3),
+                ]))
+            ),
+            Err(s) => assert!(false, "{}", s),
+        }
     }
 
     #[test]
-    fn eval_add_num_bool() {
-        let env = HashMap::new();
-        let c10 = Expression::CInt(10);
-        let ctrue2 = Expression::Bool(true);
-        let add1 = Expression::Add(Box::new(c10), Box::new(ctrue2));
-        assert_eq!(eval(&add1, &env), Ok(EvalResult::CInt(11)));
+    fn append_to_non_list() {
+        /*
+         * > x = 1
+         * > append(x, 2)
+         */
+        let env = Environment::from([(String::from("x"), EnvValue::CInt(1))]);
+
+        let program = Statement::Expr(Box::new(Expression::FuncCall(
+            String::from("append"),
+            Some(vec![
+                Expression::Var(String::from("x")),
+                Expression::CInt(2),
+            ]),
+        )));
+
+        match execute(&program, env) {
+            Err(e) => assert_eq!(e.to_string(), "'int' object has no attribute 'append'"),
+            Ok(_) => assert!(false, "Append should generate an error"),
+        }
     }
 
     #[test]
-    fn eval_add_lists() {
-        let env = HashMap::new();
-        let l1 = Expression::List(vec![Expression::CInt(0), Expression::CInt(1)]);
-        let l2 = Expression::List(vec![Expression::CInt(2), Expression::CInt(3)]);
-        let add = Expression::Add(Box::new(l1), Box::new(l2));
+    fn eval_nested_short_circuit() {
+        /*
+         * > a = 0
+         * > b = True
+         * > c = "yes"
+         * > a or (b and (c or undefined1))          # "yes"
+         * > a and (undefined2 or undefined3)        # 0
+         * > b or (undefined4 and undefined5)        # True
+         * > (a or b) and (c and (a or (a and nope)))  # 0
+         */
+        let env = Environment::from([
+            (String::from("a"), EnvValue::CInt(0)),
+            (String::from("b"), EnvValue::Bool(true)),
+            (String::from("c"), EnvValue::CString(String::from("yes"))),
+        ]);
+
+        let var = |name: &str| Box::new(Expression::Var(String::from(name)));
+        let and = |lhs, rhs| Box::new(Expression::And(lhs, rhs));
+        let or = |lhs, rhs| Box::new(Expression::Or(lhs, rhs));
+
+        let exp = or(var("a"), and(var("b"), or(var("c"), var("undefined1"))));
         assert_eq!(
-            eval(&add, &env),
-            Ok(EvalResult::List(vec![
-                EvalResult::CInt(0),
-                EvalResult::CInt(1),
-                EvalResult::CInt(2),
-                EvalResult::CInt(3)
-            ]))
+            eval(&exp, &env),
+            Ok(EvalResult::CString(String::from("yes")))
+        );
+
+        let exp = and(var("a"), or(var("undefined2"), var("undefined3")));
+        assert_eq!(eval(&exp, &env), Ok(EvalResult::CInt(0)));
+
+        let exp = or(var("b"), and(var("undefined4"), var("undefined5")));
+        assert_eq!(eval(&exp, &env), Ok(EvalResult::Bool(true)));
+
+        let exp = and(
+            or(var("a"), var("b")),
+            and(var("c"), or(var("a"), and(var("a"), var("nope")))),
+        );
+        assert_eq!(eval(&exp, &env), Ok(EvalResult::CInt(0)));
+
+        let exp = and(var("b"), or(var("a"), var("missing")));
+        assert_eq!(
+            eval(&exp, &env),
+            Err(InterpreterError::UndefinedVariable(String::from("missing")))
         );
     }
 
     #[test]
-    fn eval_multiply_list() {
-        let env = HashMap::new();
-        let l1 = Expression::List(vec![Expression::CInt(0), Expression::CInt(1)]);
-        let l2 = Expression::List(vec![Expression::CInt(0), Expression::CInt(1)]);
-        let mul1 = Expression::Mul(Box::new(l1), Box::new(Expression::CInt(2)));
-        let mul2 = Expression::Mul(Box::new(l2), Box::new(Expression::CInt(0)));
+    fn eval_deep_short_circuit_chain() {
+        /*
+         * > False or (False or (... or (True or undefined)))
+         *
+         * with 100 levels of nesting, should be True.
+         */
+        let env = Environment::new();
+
+        let mut exp = Expression::Or(
+            Box::new(Expression::Bool(true)),
+            Box::new(Expression::Var(String::from("undefined"))),
+        );
+        for _ in 0..100 {
+            exp = Expression::Or(Box::new(Expression::Bool(false)), Box::new(exp));
+        }
+
+        assert_eq!(eval(&exp, &env), Ok(EvalResult::Bool(true)));
+    }
+
+    #[test]
+    fn func_node_shape() {
+        /*
+         * > def scale(x: float, n: int) -> float:
+         * >     y = x * n
+         * >     return y
+         *
+         * The function value keeps the name, return type and parameters
+         * (in order, with their types) given to the Func node.
+         */
+        let params = vec![
+            (String::from("x"), Box::new(Type::TReal)),
+            (String::from("n"), Box::new(Type::TInteger)),
+        ];
+        let body = Statement::Sequence(
+            Box::new(Statement::Assignment(
+                Box::new(String::from("y")),
+                Box::new(Expression::Mul(
+                    Box::new(Expression::Var(String::from("x"))),
+                    Box::new(Expression::Var(String::from("n"))),
+                )),
+            )),
+            Box::new(Statement::Return(Box::new(Expression::Var(String::from(
+                "y",
+            ))))),
+        );
+        let program = Statement::Func(
+            Box::new(String::from("scale")),
+            Box::new(Type::TReal),
+            Some(params),
+            Some(Box::new(body)),
+            Box::new(Expression::None),
+        );
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => match new_env.get("scale") {
+                Some(EnvValue::Func(func)) => {
+                    assert_eq!(func.name, "scale");
+                    assert_eq!(func.kind, Type::TReal);
+                    assert_eq!(
+                        func.params,
+                        vec![
+                            (String::from("x"), Type::TReal),
+                            (String::from("n"), Type::TInteger),
+                        ]
+                    );
+                    let call = Expression::FuncCall(
+                        String::from("scale"),
+                        Some(vec![Expression::CReal(1.5), Expression::CInt(2)]),
+                    );
+                    assert_eq!(eval(&call, &new_env), Ok(EvalResult::CReal(3.0)));
+                }
+                Some(val) => assert!(false, "Expected a function, got {:?}", val),
+                None => assert!(false, "Function scale not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn multi_assign_discards_underscore() {
+        /*
+         * > a, _, c = [1, 2, 3]
+         *
+         * After executing, 'a' should be 1, 'c' 3 and '_' unbound.
+         */
+        let program = Statement::MultiAssign(
+            vec![String::from("a"), String::from("_"), String::from("c")],
+            vec![Expression::List(vec![
+                Expression::CInt(1),
+                Expression::CInt(2),
+                Expression::CInt(3),
+            ])],
+        );
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("a"), Some(EnvValue::CInt(1)));
+                assert_eq!(new_env.get("c"), Some(EnvValue::CInt(3)));
+                assert_eq!(new_env.get("_"), None);
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn for_unpack_discards_underscore() {
+        /*
+         * > total = 0
+         * > for _, x in [(1, 10), (2, 20)]:
+         * >     total = total + x + _
+         *
+         * '_' is never bound, so reading it in the body is an error.
+         */
+        let env = Environment::from([(String::from("total"), EnvValue::CInt(0))]);
+
+        let pairs = Expression::List(vec![
+            Expression::Tuple(vec![Expression::CInt(1), Expression::CInt(10)]),
+            Expression::Tuple(vec![Expression::CInt(2), Expression::CInt(20)]),
+        ]);
+        let var = |name: &str| Box::new(Expression::Var(String::from(name)));
+        let sum_x = Statement::ForUnpack(
+            vec![String::from("_"), String::from("x")],
+            Box::new(pairs.clone()),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("total")),
+                Box::new(Expression::Add(var("total"), var("x"))),
+            )),
+        );
+
+        match execute(&sum_x, env.clone()) {
+            Ok(new_env) => assert_eq!(new_env.get("total"), Some(EnvValue::CInt(30))),
+            Err(s) => assert!(false, "{}", s),
+        }
+
+        let read_discarded = Statement::ForUnpack(
+            vec![String::from("_"), String::from("x")],
+            Box::new(pairs),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("total")),
+                Box::new(Expression::Add(var("x"), var("_"))),
+            )),
+        );
+
+        match execute(&read_discarded, env) {
+            Err(e) => assert_eq!(e, InterpreterError::UndefinedVariable(String::from("_"))),
+            Ok(_) => assert!(false, "Reading '_' should generate an error"),
+        }
+    }
+
+    #[test]
+    fn run_program_and_expression() {
+        /*
+         * > def square(x: int) -> int:
+         * >     return x * x
+         * > y = square(7)
+         *
+         * run should leave 'y' as 49; run_expr evaluates (2 + 3) * 4 to 20.
+         */
+        let program = Statement::Sequence(
+            Box::new(Statement::Func(
+                Box::new(String::from("square")),
+                Box::new(Type::TInteger),
+                Some(vec![(String::from("x"), Box::new(Type::TInteger))]),
+                Some(Box::new(Statement::Return(Box::new(Expression::Mul(
+                    Box::new(Expression::Var(String::from("x"))),
+                    Box::new(Expression::Var(String::from("x"))),
+                ))))),
+                Box::new(Expression::None),
+            )),
+            Box::new(Statement::Assignment(
+                Box::new(String::from("y")),
+                Box::new(Expression::FuncCall(
+                    String::from("square"),
+                    Some(vec![Expression::CInt(7)]),
+                )),
+            )),
+        );
+
+        match run(&program) {
+            Ok(env) => assert_eq!(env.get("y"), Some(EnvValue::CInt(49))),
+            Err(s) => assert!(false, "{}", s),
+        }
+
+        let exp = Expression::Mul(
+            Box::new(Expression::Add(
+                Box::new(Expression::CInt(2)),
+                Box::new(Expression::CInt(3)),
+            )),
+            Box::new(Expression::CInt(4)),
+        );
+        assert_eq!(run_expr(&exp), Ok(EvalResult::CInt(20)));
+        assert_eq!(
+            run_expr(&Expression::Var(String::from("y"))),
+            Err(InterpreterError::UndefinedVariable(String::from("y")))
+        );
+    }
+
+    #[test]
+    fn eval_generator_expression() {
+        /*
+         * > sum(i*i for i in range(4))            # 14
+         * > any(x > 2 for x in [1, 2, 3])         # True
+         * > all(x > 2 for x in [1, 2, 3])         # False
+         */
+        let env = Environment::new();
+
+        let var = |name: &str| Box::new(Expression::Var(String::from(name)));
+        let squares = Expression::GenExp {
+            element: Box::new(Expression::Mul(var("i"), var("i"))),
+            var: String::from("i"),
+            iterable: Box::new(Expression::Range(None, Box::new(Expression::CInt(4)), None)),
+            condition: None,
+        };
+        let call = |name: &str, arg: Expression| {
+            eval(
+                &Expression::FuncCall(String::from(name), Some(vec![arg])),
+                &env,
+            )
+        };
+        assert_eq!(call("sum", squares), Ok(EvalResult::CInt(14)));
+
+        let above_two = Expression::GenExp {
+            element: Box::new(Expression::Gt(var("x"), Box::new(Expression::CInt(2)))),
+            var: String::from("x"),
+            iterable: Box::new(Expression::List(vec![
+                Expression::CInt(1),
+                Expression::CInt(2),
+                Expression::CInt(3),
+            ])),
+            condition: None,
+        };
+        assert_eq!(call("any", above_two.clone()), Ok(EvalResult::Bool(true)));
+        assert_eq!(call("all", above_two), Ok(EvalResult::Bool(false)));
+        assert_eq!(
+            call("all", Expression::List(vec![])),
+            Ok(EvalResult::Bool(true))
+        );
+    }
+
+    #[test]
+    fn eval_pow() {
+        /*
+         * > 3 ** 19                   # 1162261467
+         * > 3 ** 20                   # integer overflow
+         * > 2 ** -1                   # 0.5
+         * > 2.0 ** 3                  # 8.0
+         * > True ** 5                 # 1
+         * > 0 ** -1                   # division by zero
+         * > "a" ** 2
+         */
+        let env = Environment::new();
+
+        let pow = |lhs: Expression, rhs: Expression| {
+            eval(&Expression::Pow(Box::new(lhs), Box::new(rhs)), &env).map_err(|e| e.to_string())
+        };
+
+        assert_eq!(
+            pow(Expression::CInt(3), Expression::CInt(19)),
+            Ok(EvalResult::CInt(1162261467))
+        );
+        assert_eq!(
+            pow(Expression::CInt(3), Expression::CInt(20)),
+            Err(String::from("Integer overflow"))
+        );
+        assert_eq!(
+            pow(Expression::CInt(2), Expression::CInt(-1)),
+            Ok(EvalResult::CReal(0.5))
+        );
+        assert_eq!(
+            pow(Expression::CReal(2.0), Expression::CInt(3)),
+            Ok(EvalResult::CReal(8.0))
+        );
+        assert_eq!(
+            pow(Expression::Bool(true), Expression::CInt(5)),
+            Ok(EvalResult::CInt(1))
+        );
+        assert_eq!(
+            pow(Expression::CInt(0), Expression::CInt(-1)),
+            Err(String::from("Division by zero"))
+        );
+        assert_eq!(
+            pow(Expression::CString(String::from("a")), Expression::CInt(2)),
+            Err(String::from(
+                "unsupported operand type(s) for **: 'str' and 'int'"
+            ))
+        );
+    }
+
+    #[test]
+    fn builtin_pow_with_modulus() {
+        /*
+         * > pow(2, 10)                        # 1024
+         * > pow(3, 200, 7)                    # 2
+         * > pow(2, 2147483647, 1000000007)    # 914893544
+         * > pow(2, 3, -5)                     # -2
+         * > pow(5, 0, 1)                      # 0
+         * > pow(2, 3, 0)
+         */
+        let env = Environment::new();
+
+        let pow = |args: Vec<i32>| {
+            eval(
+                &Expression::FuncCall(
+                    String::from("pow"),
+                    Some(args.into_iter().map(Expression::CInt).collect()),
+                ),
+                &env,
+            )
+            .map_err(|e| e.to_string())
+        };
+
+        assert_eq!(pow(vec![2, 10]), Ok(EvalResult::CInt(1024)));
+        assert_eq!(pow(vec![3, 200, 7]), Ok(EvalResult::CInt(2)));
+        assert_eq!(
+            pow(vec![2, 2147483647, 1000000007]),
+            Ok(EvalResult::CInt(914893544))
+        );
+        assert_eq!(pow(vec![2, 3, -5]), Ok(EvalResult::CInt(-2)));
+        assert_eq!(pow(vec![5, 0, 1]), Ok(EvalResult::CInt(0)));
+        assert_eq!(
+            pow(vec![2, 3, 0]),
+            Err(String::from("pow() 3rd argument cannot be 0"))
+        );
+    }
+
+    #[test]
+    fn eval_bitwise_operators() {
+        /*
+         * > 6 & 3                     # 2
+         * > 6 | 3                     # 7
+         * > 6 ^ 3                     # 5
+         * > 1 << 4                    # 16
+         * > -16 >> 2                  # -4
+         * > True << 3                 # 8
+         * > 1 << 31                   # integer overflow
+         * > 1 << -1                   # negative shift count
+         * > 1.5 & 1
+         */
+        let env = Environment::new();
+
+        let op = |f: fn(Box<Expression>, Box<Expression>) -> Expression,
+                  lhs: Expression,
+                  rhs: Expression| {
+            eval(&f(Box::new(lhs), Box::new(rhs)), &env).map_err(|e| e.to_string())
+        };
+        let int = Expression::CInt;
+
+        assert_eq!(
+            op(Expression::BitAnd, int(6), int(3)),
+            Ok(EvalResult::CInt(2))
+        );
+        assert_eq!(
+            op(Expression::BitOr, int(6), int(3)),
+            Ok(EvalResult::CInt(7))
+        );
+        assert_eq!(
+            op(Expression::BitXor, int(6), int(3)),
+            Ok(EvalResult::CInt(5))
+        );
+        assert_eq!(
+            op(Expression::Shl, int(1), int(4)),
+            Ok(EvalResult::CInt(16))
+        );
+        assert_eq!(
+            op(Expression::Shr, int(-16), int(2)),
+            Ok(EvalResult::CInt(-4))
+        );
+        assert_eq!(
+            op(Expression::Shl, Expression::Bool(true), int(3)),
+            Ok(EvalResult::CInt(8))
+        );
+        assert_eq!(
+            op(Expression::Shl, int(1), int(31)),
+            Err(String::from("Integer overflow"))
+        );
+        assert_eq!(
+            op(Expression::Shl, int(1), int(-1)),
+            Err(String::from("negative shift count"))
+        );
+        assert_eq!(
+            op(Expression::BitAnd, Expression::CReal(1.5), int(1)),
+            Err(String::from("unsupported operand type(s) for bitwise op"))
+        );
+        assert_eq!(
+            op(Expression::BitOr, Expression::List(vec![]), int(1)),
+            Err(String::from("unsupported operand type(s) for bitwise op"))
+        );
+    }
+
+    #[test]
+    fn eval_div_negative_zero_and_nan() {
+        /*
+         * > 1.0 / -0.0                # division by zero
+         * > 3 / -0.0                  # division by zero
+         * > 0.0 / 0.0                 # division by zero
+         * > -0.0 / 2.0                # -0.0
+         * > big = 1e38 * 10.0         # inf
+         * > big / big                 # Division produced NaN
+         */
+        let env = Environment::new();
+
+        let div = |lhs: Expression, rhs: Expression| {
+            eval(&Expression::Div(Box::new(lhs), Box::new(rhs)), &env)
+        };
+
+        assert_eq!(
+            div(Expression::CReal(1.0), Expression::CReal(-0.0)),
+            Err(InterpreterError::DivisionByZero)
+        );
+        assert_eq!(
+            div(Expression::CInt(3), Expression::CReal(-0.0)),
+            Err(InterpreterError::DivisionByZero)
+        );
+        assert_eq!(
+            div(Expression::CReal(0.0), Expression::CReal(0.0)),
+            Err(InterpreterError::DivisionByZero)
+        );
+        match div(Expression::CReal(-0.0), Expression::CReal(2.0)) {
+            Ok(EvalResult::CReal(v)) => assert!(v == 0.0 && v.is_sign_negative()),
+            result => assert!(false, "Expected -0.0, got {:?}", result),
+        }
+
+        let big = || {
+            Box::new(Expression::Mul(
+                Box::new(Expression::CReal(1e38)),
+                Box::new(Expression::CReal(10.0)),
+            ))
+        };
+        assert_eq!(
+            eval(&Expression::Div(big(), big()), &env),
+            Err(InterpreterError::ValueError(String::from(
+                "Division produced NaN"
+            )))
+        );
+    }
+
+    #[test]
+    fn builtin_join() {
+        /*
+         * > join(", ", ["a", "b", "c"])            # "a, b, c"
+         * > join("-", [])                          # ""
+         * > join("", [str(i) for i in range(10000)])
+         * > join(",", ("a", 1))
+         */
+        let env = Environment::new();
+
+        let join = |sep: &str, items: Expression| {
+            eval(
+                &Expression::FuncCall(
+                    String::from("join"),
+                    Some(vec![Expression::CString(String::from(sep)), items]),
+                ),
+                &env,
+            )
+            .map_err(|e| e.to_string())
+        };
+        let strings = |items: &[&str]| {
+            Expression::List(
+                items
+                    .iter()
+                    .map(|item| Expression::CString(item.to_string()))
+                    .collect(),
+            )
+        };
+
+        assert_eq!(
+            join(", ", strings(&["a", "b", "c"])),
+            Ok(EvalResult::CString(String::from("a, b, c")))
+        );
+        assert_eq!(
+            join("-", strings(&[])),
+            Ok(EvalResult::CString(String::new()))
+        );
+
+        let digits: Vec<String> = (0..10000).map(|i| i.to_string()).collect();
+        let digits: Vec<&str> = digits.iter().map(|s| s.as_str()).collect();
+        match join("", strings(&digits)) {
+            Ok(EvalResult::CString(joined)) => {
+                assert_eq!(joined.len(), 38890);
+                assert!(joined.starts_with("012345678910"));
+                assert!(joined.ends_with("99989999"));
+            }
+            result => assert!(false, "Expected a str, got {:?}", result),
+        }
+
         assert_eq!(
-            eval(&mul1, &env),
-            Ok(EvalResult::List(vec![
-                EvalResult::CInt(0),
-                EvalResult::CInt(1),
-                EvalResult::CInt(0),
-                EvalResult::CInt(1)
-            ]))
+            join(
+                ",",
+                Expression::Tuple(vec![
+                    Expression::CString(String::from("a")),
+                    Expression::CInt(1),
+                ])
+            ),
+            Err(String::from(
+                "sequence item 1: expected str instance, int found"
+            ))
         );
-        assert_eq!(eval(&mul2, &env), Ok(EvalResult::List(vec![])));
     }
 
     #[test]
-    fn eval_variable() {
-        let env = HashMap::from([
-            (String::from("w"), EnvValue::CInt(10)),
-            (String::from("x"), EnvValue::CReal(20.7)),
-            (String::from("y"), EnvValue::Bool(true)),
-            (
-                String::from("z"),
-                EnvValue::List(vec![EvalResult::CInt(1), EvalResult::CInt(2)]),
+    fn eval_int_overflow() {
+        /*
+         * > 2147483647 + 1            # Integer overflow
+         * > -2147483648 - 1           # Integer overflow
+         * > 2147483647 * 2            # Integer overflow
+         * > 2147483647 + True         # Integer overflow
+         * > True - (-2147483647 - 1)  # Integer overflow
+         * > sum([2147483647, 1])      # Integer overflow
+         * > 2147483646 + 1            # 2147483647
+         */
+        let env = Environment::new();
+
+        let op = |f: fn(Box<Expression>, Box<Expression>) -> Expression,
+                  lhs: Expression,
+                  rhs: Expression| eval(&f(Box::new(lhs), Box::new(rhs)), &env);
+        let int = Expression::CInt;
+
+        assert_eq!(
+            op(Expression::Add, int(i32::MAX), int(1)),
+            Err(InterpreterError::IntegerOverflow)
+        );
+        assert_eq!(
+            op(Expression::Sub, int(i32::MIN), int(1)),
+            Err(InterpreterError::IntegerOverflow)
+        );
+        assert_eq!(
+            op(Expression::Mul, int(i32::MAX), int(2)),
+            Err(InterpreterError::IntegerOverflow)
+        );
+        assert_eq!(
+            op(Expression::Add, int(i32::MAX), Expression::Bool(true)),
+            Err(InterpreterError::IntegerOverflow)
+        );
+        assert_eq!(
+            op(Expression::Sub, Expression::Bool(true), int(i32::MIN)),
+            Err(InterpreterError::IntegerOverflow)
+        );
+        assert_eq!(
+            eval(
+                &Expression::FuncCall(
+                    String::from("sum"),
+                    Some(vec![Expression::List(vec![int(i32::MAX), int(1)])]),
+                ),
+                &env,
             ),
-        ]);
-        let v1 = Expression::Var(String::from("w"));
-        let v2 = Expression::Var(String::from("x"));
-        let v3 = Expression::Var(String::from("y"));
-        let v4 = Expression::Var(String::from("z"));
-        assert_eq!(eval(&v1, &env), Ok(EvalResult::CInt(10)));
-        assert_eq!(eval(&v2, &env), Ok(EvalResult::CReal(20.7)));
-        assert_eq!(eval(&v3, &env), Ok(EvalResult::Bool(true)));
+            Err(InterpreterError::IntegerOverflow)
+        );
         assert_eq!(
-            eval(&v4, &env),
-            Ok(EvalResult::List(vec![
-                EvalResult::CInt(1),
-                EvalResult::CInt(2)
-            ]))
+            op(Expression::Add, int(i32::MAX - 1), int(1)),
+            Ok(EvalResult::CInt(i32::MAX))
+        );
+        assert_eq!(
+            InterpreterError::IntegerOverflow.to_string(),
+            "Integer overflow"
         );
     }
 
     #[test]
-    fn execute_assignment_same_variable() {
-        let env = HashMap::new();
-        let a1 = Statement::Assignment(Box::new(String::from("x")), Box::new(Expression::CInt(1)));
-        let a2 = Statement::Assignment(Box::new(String::from("x")), Box::new(Expression::CInt(2)));
-        let seq = Statement::Sequence(Box::new(a1), Box::new(a2));
+    fn builtin_range() {
+        /*
+         * > range(5)                  # [0, 1, 2, 3, 4]
+         * > range(2, 8)               # [2, 3, 4, 5, 6, 7]
+         * > range(10, 0, -2)          # [10, 8, 6, 4, 2]
+         * > range(1, 5, 0)            # Increment cannot be zero
+         * > range()
+         */
+        let env = Environment::new();
 
-        match execute(&seq, env) {
-            Ok(new_env) => match new_env.get("x") {
-                Some(EnvValue::CInt(2)) => {}
-                Some(value) => assert!(false, "Expected 2, got {:?}", value),
-                None => assert!(false, "Variable x not found"),
-            },
-            Err(s) => assert!(false, "{}", s),
-        }
+        let range = |args: &[i32]| {
+            eval(
+                &Expression::FuncCall(
+                    String::from("range"),
+                    Some(args.iter().map(|arg| Expression::CInt(*arg)).collect()),
+                ),
+                &env,
+            )
+            .map_err(|e| e.to_string())
+        };
+        let ints = |items: &[i32]| {
+            Ok(EvalResult::List(
+                items.iter().map(|item| EvalResult::CInt(*item)).collect(),
+            ))
+        };
+
+        assert_eq!(range(&[5]), ints(&[0, 1, 2, 3, 4]));
+        assert_eq!(range(&[2, 8]), ints(&[2, 3, 4, 5, 6, 7]));
+        assert_eq!(range(&[10, 0, -2]), ints(&[10, 8, 6, 4, 2]));
+        assert_eq!(
+            range(&[10, 0, -2]),
+            eval(
+                &Expression::Range(
+                    Some(Box::new(Expression::CInt(10))),
+                    Box::new(Expression::CInt(0)),
+                    Some(Box::new(Expression::CInt(-2))),
+                ),
+                &env,
+            )
+            .map_err(|e| e.to_string())
+        );
+        assert_eq!(
+            range(&[1, 5, 0]),
+            Err(String::from("Increment cannot be zero"))
+        );
+        assert_eq!(
+            range(&[]),
+            Err(String::from("range expected 1 to 3 arguments, got 0"))
+        );
     }
 
     #[test]
-    fn execute_assignment() {
-        let env = HashMap::new();
-        let assign_stmt =
-            Statement::Assignment(Box::new(String::from("x")), Box::new(Expression::CInt(42)));
+    fn builtin_sum_promotes_to_real() {
+        /*
+         * > sum([1, 2.5, 3])          # 6.5
+         * > sum((1, 2.5, 3))          # 6.5
+         * > sum((1, True, 2))         # 4
+         * > sum((1, "a"))
+         * > sum((0.5, [1]))
+         */
+        let env = Environment::new();
 
-        match execute(&assign_stmt, env) {
-            Ok(new_env) => match new_env.get("x") {
-                Some(EnvValue::CInt(42)) => {}
-                Some(value) => assert!(false, "Expected 42, got {:?}", value),
-                None => assert!(false, "Variable x not found"),
-            },
-            Err(s) => assert!(false, "{}", s),
-        }
+        let sum = |items: Expression| {
+            eval(
+                &Expression::FuncCall(String::from("sum"), Some(vec![items])),
+                &env,
+            )
+            .map_err(|e| e.to_string())
+        };
+        let mixed = vec![
+            Expression::CInt(1),
+            Expression::CReal(2.5),
+            Expression::CInt(3),
+        ];
+
+        assert_eq!(
+            sum(Expression::List(mixed.clone())),
+            Ok(EvalResult::CReal(6.5))
+        );
+        assert_eq!(sum(Expression::Tuple(mixed)), Ok(EvalResult::CReal(6.5)));
+        assert_eq!(
+            sum(Expression::Tuple(vec![
+                Expression::CInt(1),
+                Expression::Bool(true),
+                Expression::CInt(2),
+            ])),
+            Ok(EvalResult::CInt(4))
+        );
+        assert_eq!(
+            sum(Expression::Tuple(vec![
+                Expression::CInt(1),
+                Expression::CString(String::from("a")),
+            ])),
+            Err(String::from(
+                "unsupported operand type(s) for +: 'int' and 'str'"
+            ))
+        );
+        assert_eq!(
+            sum(Expression::Tuple(vec![
+                Expression::CReal(0.5),
+                Expression::List(vec![Expression::CInt(1)]),
+            ])),
+            Err(String::from(
+                "unsupported operand type(s) for +: 'real' and 'list'"
+            ))
+        );
     }
 
     #[test]
-    fn eval_expression_with_variables() {
-        let env = HashMap::from([
-            (String::from("a"), EnvValue::CInt(5)),
-            (String::from("b"), EnvValue::CInt(3)),
-        ]);
-        let expr = Expression::Mul(
-            Box::new(Expression::Var(String::from("a"))),
-            Box::new(Expression::Add(
-                Box::new(Expression::Var(String::from("b"))),
-                Box::new(Expression::CInt(2)),
-            )),
+    fn display_real() {
+        /*
+         * > 3.0                       # 3.0
+         * > 0.5                       # 0.5
+         * > 6 / 4                     # 1.5
+         * > [1.0, 0.1]                # [1.0, 0.1]
+         * > 1e20                      # 1e+20
+         * > 0.000015                  # 1.5e-05
+         */
+        let cases = [
+            (3.0, "3.0"),
+            (0.5, "0.5"),
+            (-2.0, "-2.0"),
+            (0.1, "0.1"),
+            (123456.75, "123456.75"),
+            (1e20, "1e+20"),
+            (1.5e-5, "1.5e-05"),
+            (f32::INFINITY, "inf"),
+            (f32::NEG_INFINITY, "-inf"),
+            (f32::NAN, "nan"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(EvalResult::CReal(value).to_string(), expected);
+        }
+
+        let env = Environment::new();
+        let div = Expression::Div(Box::new(Expression::CInt(6)), Box::new(Expression::CInt(4)));
+        assert_eq!(
+            eval(&div, &env).map(|value| value.to_string()),
+            Ok(String::from("1.5"))
+        );
+        let list = Expression::List(vec![Expression::CReal(1.0), Expression::CReal(0.1)]);
+        assert_eq!(
+            eval(&list, &env).map(|value| value.to_string()),
+            Ok(String::from("[1.0, 0.1]"))
         );
-        assert_eq!(eval(&expr, &env), Ok(EvalResult::CInt(25)));
     }
 
     #[test]
-    fn eval_nested_expressions() {
-        let env = HashMap::new();
-        let expr = Expression::Add(
-            Box::new(Expression::Mul(
-                Box::new(Expression::CInt(2)),
-                Box::new(Expression::CInt(3)),
-            )),
-            Box::new(Expression::Sub(
-                Box::new(Expression::CInt(10)),
-                Box::new(Expression::CInt(4)),
-            )),
+    fn eval_is_and_is_not() {
+        /*
+         * > None is None              # True
+         * > 5 is None                 # False
+         * > 5 is not None             # True
+         * > None is not None          # False
+         * > 5 is 5                    # True
+         * > "a" is "b"                # False
+         */
+        let env = Environment::new();
+
+        let op = |f: fn(Box<Expression>, Box<Expression>) -> Expression,
+                  lhs: Expression,
+                  rhs: Expression| eval(&f(Box::new(lhs), Box::new(rhs)), &env);
+        let s = |v: &str| Expression::CString(String::from(v));
+
+        assert_eq!(
+            op(Expression::Is, Expression::None, Expression::None),
+            Ok(EvalResult::Bool(true))
+        );
+        assert_eq!(
+            op(Expression::Is, Expression::CInt(5), Expression::None),
+            Ok(EvalResult::Bool(false))
+        );
+        assert_eq!(
+            op(Expression::IsNot, Expression::CInt(5), Expression::None),
+            Ok(EvalResult::Bool(true))
+        );
+        assert_eq!(
+            op(Expression::IsNot, Expression::None, Expression::None),
+            Ok(EvalResult::Bool(false))
+        );
+        assert_eq!(
+            op(Expression::Is, Expression::CInt(5), Expression::CInt(5)),
+            Ok(EvalResult::Bool(true))
+        );
+        assert_eq!(
+            op(Expression::Is, s("a"), s("b")),
+            Ok(EvalResult::Bool(false))
         );
-        assert_eq!(eval(&expr, &env), Ok(EvalResult::CInt(12)));
     }
 
     #[test]
-    fn eval_variable_not_found() {
-        let env = HashMap::new();
-        let var_expr = Expression::Var(String::from("z"));
+    fn while_else() {
+        /*
+         * > i = 0
+         * > exhausted = False
+         * > while i < 5:
+         * >    if i == target:
+         * >       break
+         * >    i = i + 1
+         * > else:
+         * >    exhausted = True
+         *
+         * With target = 3 the break skips the else: 'i' is 3 and
+         * 'exhausted' stays False. With target = 9 the loop runs out:
+         * 'i' is 5 and 'exhausted' is True.
+         */
+        let var = |name: &str| Box::new(Expression::Var(String::from(name)));
+        let search = |target: i32| {
+            let body = Statement::Sequence(
+                Box::new(Statement::If(
+                    Box::new(Expression::Eq(var("i"), Box::new(Expression::CInt(target)))),
+                    Box::new(Statement::Break),
+                )),
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("i")),
+                    Box::new(Expression::Add(var("i"), Box::new(Expression::CInt(1)))),
+                )),
+            );
+            let program = Statement::WhileElse(
+                Box::new(Expression::Lt(var("i"), Box::new(Expression::CInt(5)))),
+                Box::new(body),
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("exhausted")),
+                    Box::new(Expression::Bool(true)),
+                )),
+            );
+            let env = Environment::from([
+                (String::from("i"), EnvValue::CInt(0)),
+                (String::from("exhausted"), EnvValue::Bool(false)),
+            ]);
+            execute(&program, env).map(|env| (env.get("i"), env.get("exhausted")))
+        };
 
         assert_eq!(
-            eval(&var_expr, &env),
-            Err(String::from("Variable z not found"))
+            search(3),
+            Ok((Some(EnvValue::CInt(3)), Some(EnvValue::Bool(false))))
+        );
+        assert_eq!(
+            search(9),
+            Ok((Some(EnvValue::CInt(5)), Some(EnvValue::Bool(true))))
         );
     }
 
     #[test]
-    fn eval_summation() {
+    fn for_else() {
         /*
-         * (a test case for the following program)
-         *
-         * > x = 10
-         * > y = 0
-         * > while x:
-         * >   y = y + x
-         * >   x = x - 1
+         * > found = 0
+         * > exhausted = False
+         * > for x in [3, 8, 5]:
+         * >    if x > limit:
+         * >       found = x
+         * >       break
+         * > else:
+         * >    exhausted = True
          *
-         * After executing this program, 'x' must be zero and
-         * 'y' must be 55.
+         * With limit = 4 the break on 8 skips the else. With limit = 10
+         * no item matches and the else runs. Either way 'x' is gone once
+         * the loop is over.
          */
-        let env = HashMap::new();
+        let var = |name: &str| Box::new(Expression::Var(String::from(name)));
+        let search = |limit: i32| {
+            let body = Statement::If(
+                Box::new(Expression::Gt(var("x"), Box::new(Expression::CInt(limit)))),
+                Box::new(Statement::Sequence(
+                    Box::new(Statement::Assignment(
+                        Box::new(String::from("found")),
+                        var("x"),
+                    )),
+                    Box::new(Statement::Break),
+                )),
+            );
+            let program = Statement::ForElse(
+                Box::new(String::from("x")),
+                Box::new(Expression::List(vec![
+                    Expression::CInt(3),
+                    Expression::CInt(8),
+                    Expression::CInt(5),
+                ])),
+                Box::new(body),
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("exhausted")),
+                    Box::new(Expression::Bool(true)),
+                )),
+            );
+            let env = Environment::from([
+                (String::from("found"), EnvValue::CInt(0)),
+                (String::from("exhausted"), EnvValue::Bool(false)),
+            ]);
+            execute(&program, env).map(|env| (env.get("found"), env.get("exhausted"), env.get("x")))
+        };
 
-        let a1 = Statement::Assignment(Box::new(String::from("x")), Box::new(Expression::CInt(10)));
-        let a2 = Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
-        let a3 = Statement::Assignment(
-            Box::new(String::from("y")),
-            Box::new(Expression::Add(
-                Box::new(Expression::Var(String::from("y"))),
-                Box::new(Expression::Var(String::from("x"))),
-            )),
+        assert_eq!(
+            search(4),
+            Ok((Some(EnvValue::CInt(8)), Some(EnvValue::Bool(false)), None))
         );
-        let a4 = Statement::Assignment(
-            Box::new(String::from("x")),
-            Box::new(Expression::Sub(
-                Box::new(Expression::Var(String::from("x"))),
-                Box::new(Expression::CInt(1)),
-            )),
+        assert_eq!(
+            search(10),
+            Ok((Some(EnvValue::CInt(0)), Some(EnvValue::Bool(true)), None))
         );
+    }
 
-        let seq1 = Statement::Sequence(Box::new(a3), Box::new(a4));
+    #[test]
+    fn eval_in_and_not_in() {
+        /*
+         * > 2 in [1, 2, 3]            # True
+         * > 2.0 in [1, 2, 3]          # True
+         * > 5 not in [1, 2, 3]        # True
+         * > "ell" in "hello"          # True
+         * > "" in "hello"             # True
+         * > "b" in {"a": 1}           # False
+         * > 1 in 5
+         * > 1 in "abc"
+         */
+        let env = Environment::new();
 
-        let while_statement =
-            Statement::While(Box::new(Expression::Var(String::from("x"))), Box::new(seq1));
+        let op = |f: fn(Box<Expression>, Box<Expression>) -> Expression,
+                  lhs: Expression,
+                  rhs: Expression| {
+            eval(&f(Box::new(lhs), Box::new(rhs)), &env).map_err(|e| e.to_string())
+        };
+        let s = |v: &str| Expression::CString(String::from(v));
+        let list = Expression::List(vec![
+            Expression::CInt(1),
+            Expression::CInt(2),
+            Expression::CInt(3),
+        ]);
 
-        let seq2 = Statement::Sequence(Box::new(a2), Box::new(while_statement));
-        let program = Statement::Sequence(Box::new(a1), Box::new(seq2));
+        assert_eq!(
+            op(Expression::In, Expression::CInt(2), list.clone()),
+            Ok(EvalResult::Bool(true))
+        );
+        assert_eq!(
+            op(Expression::In, Expression::CReal(2.0), list.clone()),
+            Ok(EvalResult::Bool(true))
+        );
+        assert_eq!(
+            op(Expression::NotIn, Expression::CInt(5), list),
+            Ok(EvalResult::Bool(true))
+        );
+        assert_eq!(
+            op(Expression::In, s("ell"), s("hello")),
+            Ok(EvalResult::Bool(true))
+        );
+        assert_eq!(
+            op(Expression::In, s(""), s("hello")),
+            Ok(EvalResult::Bool(true))
+        );
+        assert_eq!(
+            op(
+                Expression::In,
+                s("b"),
+                Expression::Dict(vec![(s("a"), Expression::CInt(1))])
+            ),
+            Ok(EvalResult::Bool(false))
+        );
+        assert_eq!(
+            op(Expression::In, Expression::CInt(1), Expression::CInt(5)),
+            Err(String::from("argument of type 'int' is not iterable"))
+        );
+        assert_eq!(
+            op(Expression::NotIn, Expression::CInt(1), Expression::None),
+            Err(String::from("argument of type 'NoneType' is not iterable"))
+        );
+        assert_eq!(
+            op(Expression::In, Expression::CInt(1), s("abc")),
+            Err(String::from(
+                "'in <string>' requires string as left operand, not int"
+            ))
+        );
+    }
 
-        match execute(&program, env) {
-            Ok(new_env) => {
-                match new_env.get("y") {
-                    Some(EnvValue::CInt(55)) => {}
-                    Some(val) => assert!(false, "Expected 55, got {:?}", val),
-                    None => assert!(false, "Variable y not found"),
-                }
-                match new_env.get("x") {
-                    Some(EnvValue::CInt(0)) => {}
-                    Some(val) => assert!(false, "Expected 0, got {:?}", val),
-                    None => assert!(false, "Variable x not found"),
-                }
-            }
-            Err(s) => assert!(false, "{}", s),
-        }
+    #[test]
+    fn builtin_int_and_float() {
+        /*
+         * > int(3.9)                  # 3
+         * > int(-3.9)                 # -3
+         * > int(True)                 # 1
+         * > int(" 42 ")               # 42
+         * > float(2)                  # 2.0
+         * > float("2.5")              # 2.5
+         * > float(False)              # 0.0
+         * > int("4.2")                # invalid literal
+         * > float("abc")              # invalid literal
+         * > int(1e10)                 # Integer overflow
+         * > int([1])
+         */
+        let env = Environment::new();
+
+        let call = |name: &str, arg: Expression| {
+            eval(
+                &Expression::FuncCall(String::from(name), Some(vec![arg])),
+                &env,
+            )
+            .map_err(|e| e.to_string())
+        };
+        let s = |v: &str| Expression::CString(String::from(v));
+
+        assert_eq!(call("int", Expression::CReal(3.9)), Ok(EvalResult::CInt(3)));
+        assert_eq!(
+            call("int", Expression::CReal(-3.9)),
+            Ok(EvalResult::CInt(-3))
+        );
+        assert_eq!(call("int", Expression::Bool(true)), Ok(EvalResult::CInt(1)));
+        assert_eq!(call("int", s(" 42 ")), Ok(EvalResult::CInt(42)));
+        assert_eq!(call("int", s("-7")), Ok(EvalResult::CInt(-7)));
+        assert_eq!(
+            call("float", Expression::CInt(2)),
+            Ok(EvalResult::CReal(2.0))
+        );
+        assert_eq!(call("float", s("2.5")), Ok(EvalResult::CReal(2.5)));
+        assert_eq!(
+            call("float", Expression::Bool(false)),
+            Ok(EvalResult::CReal(0.0))
+        );
+        assert_eq!(
+            call("int", s("4.2")),
+            Err(String::from("invalid literal for int(): '4.2'"))
+        );
+        assert_eq!(
+            call("float", s("abc")),
+            Err(String::from("invalid literal for float(): 'abc'"))
+        );
+        assert_eq!(
+            call("int", Expression::CReal(1e10)),
+            Err(String::from("Integer overflow"))
+        );
+        assert_eq!(
+            call("int", s("99999999999")),
+            Err(String::from("Integer overflow"))
+        );
+        assert_eq!(
+            call("int", Expression::List(vec![Expression::CInt(1)])),
+            Err(String::from(
+                "int() argument must be a string or a number, not 'list'"
+            ))
+        );
     }
 
     #[test]
-    fn eval_simple_if_then_else() {
+    fn builtin_str() {
         /*
-         * Test for simple if-then-else statement
-         *
-         * > x = 10
-         * > if x > 5:
-         * >   y = 1
-         * > else:
-         * >   y = 0
-         *
-         * After executing, 'y' should be 1.
+         * > str(42)                   # "42"
+         * > str(2.5)                  # "2.5"
+         * > str(3.0)                  # "3.0"
+         * > str(True)                 # "True"
+         * > str("abc")                # "abc"
+         * > str(None)                 # "None"
+         * > str([1, 2])               # "[1, 2]"
+         * > str([["a"], []])          # "[['a'], []]"
+         * > str((1,))                 # "(1,)"
+         * > str()                     # ""
          */
-        let env = HashMap::new();
-
-        let condition = Expression::Var(String::from("x"));
-        let then_stmt =
-            Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(1)));
-        let else_stmt =
-            Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
+        let env = Environment::new();
 
-        let if_statement = Statement::IfThenElse(
-            Box::new(condition),
-            Box::new(then_stmt),
-            Box::new(else_stmt),
-        );
+        let to_str = |args: Option<Vec<Expression>>| {
+            eval(&Expression::FuncCall(String::from("str"), args), &env)
+        };
+        let cases = [
+            (Expression::CInt(42), "42"),
+            (Expression::CReal(2.5), "2.5"),
+            (Expression::CReal(3.0), "3.0"),
+            (Expression::Bool(true), "True"),
+            (Expression::Bool(false), "False"),
+            (Expression::CString(String::from("abc")), "abc"),
+            (Expression::None, "None"),
+            (
+                Expression::List(vec![Expression::CInt(1), Expression::CInt(2)]),
+                "[1, 2]",
+            ),
+            (
+                Expression::List(vec![
+                    Expression::List(vec![Expression::CString(String::from("a"))]),
+                    Expression::List(vec![]),
+                ]),
+                "[['a'], []]",
+            ),
+            (Expression::Tuple(vec![Expression::CInt(1)]), "(1,)"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(
+                to_str(Some(vec![value])),
+                Ok(EvalResult::CString(String::from(expected)))
+            );
+        }
+        assert_eq!(to_str(None), Ok(EvalResult::CString(String::new())));
+    }
 
-        let setup_stmt =
-            Statement::Assignment(Box::new(String::from("x")), Box::new(Expression::CInt(10)));
-        let program = Statement::Sequence(Box::new(setup_stmt), Box::new(if_statement));
+    #[test]
+    fn builtin_bool() {
+        /*
+         * > bool(0)                   # False
+         * > bool(-1)                  # True
+         * > bool(0.0)                 # False
+         * > bool("")                  # False
+         * > bool("a")                 # True
+         * > bool([])                  # False
+         * > bool([1])                 # True
+         * > bool({})                  # False
+         * > bool(None)                # False
+         * > bool()                    # False
+         */
+        let env = Environment::new();
 
-        match execute(&program, env) {
-            Ok(new_env) => match new_env.get("y") {
-                Some(EnvValue::CInt(1)) => {}
-                Some(val) => assert!(false, "Expected 1, got {:?}", val),
-                None => assert!(false, "Variable y not found"),
-            },
-            Err(s) => assert!(false, "{}", s),
+        let to_bool = |args: Option<Vec<Expression>>| {
+            eval(&Expression::FuncCall(String::from("bool"), args), &env)
+        };
+        let cases = [
+            (Expression::CInt(0), false),
+            (Expression::CInt(-1), true),
+            (Expression::CReal(0.0), false),
+            (Expression::CReal(0.5), true),
+            (Expression::Bool(true), true),
+            (Expression::CString(String::new()), false),
+            (Expression::CString(String::from("a")), true),
+            (Expression::List(vec![]), false),
+            (Expression::List(vec![Expression::CInt(1)]), true),
+            (Expression::Tuple(vec![]), false),
+            (Expression::Dict(vec![]), false),
+            (Expression::Set(vec![Expression::CInt(0)]), true),
+            (Expression::None, false),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(
+                to_bool(Some(vec![value.clone()])),
+                Ok(EvalResult::Bool(expected)),
+                "bool({:?})",
+                value
+            );
         }
+        assert_eq!(to_bool(None), Ok(EvalResult::Bool(false)));
     }
 
     #[test]
-    fn eval_while_loop_decrement() {
+    fn eval_mod_follows_python_signs() {
         /*
-         * Test for while loop that decrements a variable
-         *
-         * > x = 3
-         * > y = 10
-         * > while x:
-         * >   y = y - 1
-         * >   x = x - 1
-         *
-         * After executing, 'y' should be 7 and 'x' should be 0.
+         * > -7 % 3                    # 2
+         * > 7 % -3                    # -2
+         * > -7 % -3                   # -1
+         * > 7 % 3                     # 1
+         * > -6 % 3                    # 0
+         * > True % 2                  # 1
+         * > -7.5 % 2                  # 0.5
+         * > 7.5 % -2.0                # -0.5
+         * > 7 % 0                     # Division by zero
+         * > "a" % 2
          */
-        let env = HashMap::new();
+        let env = Environment::new();
 
-        let a1 = Statement::Assignment(Box::new(String::from("x")), Box::new(Expression::CInt(3)));
-        let a2 = Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(10)));
-        let a3 = Statement::Assignment(
-            Box::new(String::from("y")),
-            Box::new(Expression::Sub(
-                Box::new(Expression::Var(String::from("y"))),
-                Box::new(Expression::CInt(1)),
-            )),
+        let rem = |lhs: Expression, rhs: Expression| {
+            eval(&Expression::Mod(Box::new(lhs), Box::new(rhs)), &env).map_err(|e| e.to_string())
+        };
+        let int = Expression::CInt;
+
+        assert_eq!(rem(int(-7), int(3)), Ok(EvalResult::CInt(2)));
+        assert_eq!(rem(int(7), int(-3)), Ok(EvalResult::CInt(-2)));
+        assert_eq!(rem(int(-7), int(-3)), Ok(EvalResult::CInt(-1)));
+        assert_eq!(rem(int(7), int(3)), Ok(EvalResult::CInt(1)));
+        assert_eq!(rem(int(-6), int(3)), Ok(EvalResult::CInt(0)));
+        assert_eq!(rem(int(i32::MIN), int(-1)), Ok(EvalResult::CInt(0)));
+        assert_eq!(rem(Expression::Bool(true), int(2)), Ok(EvalResult::CInt(1)));
+        assert_eq!(
+            rem(Expression::CReal(-7.5), int(2)),
+            Ok(EvalResult::CReal(0.5))
         );
-        let a4 = Statement::Assignment(
-            Box::new(String::from("x")),
-            Box::new(Expression::Sub(
-                Box::new(Expression::Var(String::from("x"))),
-                Box::new(Expression::CInt(1)),
-            )),
+        assert_eq!(
+            rem(Expression::CReal(7.5), Expression::CReal(-2.0)),
+            Ok(EvalResult::CReal(-0.5))
         );
-
-        let seq1 = Statement::Sequence(Box::new(a3), Box::new(a4));
-        let while_statement =
-            Statement::While(Box::new(Expression::Var(String::from("x"))), Box::new(seq1));
-        let program = Statement::Sequence(
-            Box::new(a1),
-            Box::new(Statement::Sequence(Box::new(a2), Box::new(while_statement))),
+        assert_eq!(rem(int(7), int(0)), Err(String::from("Division by zero")));
+        assert_eq!(
+            rem(int(7), Expression::CReal(0.0)),
+            Err(String::from("Division by zero"))
+        );
+        assert_eq!(
+            rem(Expression::CString(String::from("a")), int(2)),
+            Err(String::from(
+                "unsupported operand type(s) for %: 'str' and 'int'"
+            ))
         );
-
-        match execute(&program, env) {
-            Ok(new_env) => {
-                match new_env.get("y") {
-                    Some(EnvValue::CInt(7)) => {}
-                    Some(val) => assert!(false, "Expected 7, got {:?}", val),
-                    None => assert!(false, "Variable y not found"),
-                }
-                match new_env.get("x") {
-                    Some(EnvValue::CInt(0)) => {}
-                    Some(val) => assert!(false, "Expected 0, got {:?}", val),
-                    None => assert!(false, "Variable x not found"),
-                }
-            }
-            Err(s) => assert!(false, "{}", s),
-        }
     }
 
     #[test]
-    fn eval_for_loop_increment() {
+    fn assert_statement() {
         /*
-         * For loop test for variable increment
-         *
-         * > y = 0
-         *
-         * > for i in range(0, 5, 2):
-         * >    y = y + i
-         *
-         * After executing, 'y' should be 6 and 'i' should not be accessible.
+         * > assert x > 1              # passes, 'x' is still 3
+         * > assert x > 5, "x is " + str(x)
+         * > assert []
          */
-        let env = HashMap::new();
-
-        let a1 = Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
-        let for_exec = Statement::Assignment(
-            Box::new(String::from("y")),
-            Box::new(Expression::Add(
-                Box::new(Expression::Var(String::from("y"))),
-                Box::new(Expression::Var(String::from("i"))),
-            )),
-        );
+        let env = Environment::from([(String::from("x"), EnvValue::CInt(3))]);
+        let x = || Box::new(Expression::Var(String::from("x")));
 
-        let range = Expression::Range(
-            Some(Box::new(Expression::CInt(0))),
-            Box::new(Expression::CInt(5)),
-            Some(Box::new(Expression::CInt(2))),
+        let passing = Statement::Assert(
+            Box::new(Expression::Gt(x(), Box::new(Expression::CInt(1)))),
+            None,
         );
+        match execute(&passing, env.clone()) {
+            Ok(new_env) => assert_eq!(new_env, env),
+            Err(s) => assert!(false, "{}", s),
+        }
 
-        let for_stmt = Statement::For(
-            Box::new(String::from("i")),
-            Box::new(range),
-            Box::new(for_exec),
+        let failing = Statement::Assert(
+            Box::new(Expression::Gt(x(), Box::new(Expression::CInt(5)))),
+            Some(Box::new(Expression::Add(
+                Box::new(Expression::CString(String::from("x is "))),
+                Box::new(Expression::FuncCall(String::from("str"), Some(vec![*x()]))),
+            ))),
         );
-
-        let program = Statement::Sequence(Box::new(a1), Box::new(for_stmt));
-
-        match execute(&program, env) {
-            Ok(new_env) => {
-                match new_env.get("y") {
-                    Some(EnvValue::CInt(6)) => {}
-                    Some(val) => assert!(false, "Expected 6, got {:?}", val),
-                    None => assert!(false, "Variable y not found"),
-                }
-                match new_env.get("i") {
-                    None => {}
-                    Some(val) => assert!(false, "Expected None, got {:?}", val),
-                }
+        match execute(&failing, env.clone()) {
+            Ok(_) => assert!(false, "The assert should fail"),
+            Err(s) => {
+                assert_eq!(
+                    s,
+                    InterpreterError::AssertionError(Some(String::from("x is 3")))
+                );
+                assert_eq!(s.to_string(), "AssertionError: x is 3");
             }
-            Err(s) => assert!(false, "{}", s),
+        }
+
+        let bare = Statement::Assert(Box::new(Expression::List(vec![])), None);
+        match execute(&bare, env) {
+            Ok(_) => assert!(false, "The assert should fail"),
+            Err(s) => assert_eq!(s.to_string(), "AssertionError"),
         }
     }
 
     #[test]
-    fn eval_for_loop_decrement() {
+    fn pass_inside_if() {
         /*
-         * For loop test for variable decrement
-         *
-         * > y = 0
-         *
-         * > for i in range(10, 3, -1):
-         * >    y = y + i
-         *
-         * After executing, 'y' should be 49 and 'i' should not be accessible.
+         * > if x > 0:
+         * >     pass
+         * > else:
+         * >     pass
          */
-        let env = HashMap::new();
-
-        let a1 = Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
-        let for_exec = Statement::Assignment(
-            Box::new(String::from("y")),
-            Box::new(Expression::Add(
-                Box::new(Expression::Var(String::from("y"))),
-                Box::new(Expression::Var(String::from("i"))),
+        let env = Environment::from([(String::from("x"), EnvValue::CInt(1))]);
+        let program = Statement::IfThenElse(
+            Box::new(Expression::Gt(
+                Box::new(Expression::Var(String::from("x"))),
+                Box::new(Expression::CInt(0)),
             )),
+            Box::new(Statement::Pass),
+            Box::new(Statement::Pass),
         );
 
-        let range = Expression::Range(
-            Some(Box::new(Expression::CInt(10))),
-            Box::new(Expression::CInt(3)),
-            Some(Box::new(Expression::CInt(-1))),
-        );
+        match execute(&program, env.clone()) {
+            Ok(new_env) => assert_eq!(new_env, env),
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
 
-        let for_stmt = Statement::For(
-            Box::new(String::from("i")),
-            Box::new(range),
-            Box::new(for_exec),
+    #[test]
+    fn func_global_against_local_assignment() {
+        /*
+         * > x = 1
+         * > def shadow() -> int:
+         * >    x = 5
+         * >    return x
+         * >
+         * > def overwrite() -> int:
+         * >    global x, y
+         * >    x = 7
+         * >    y = x + 1
+         * >    return x
+         * >
+         * > a = shadow()
+         * > b = x
+         * > c = overwrite()
+         *
+         * After executing, 'a' should be 5 and 'b' should be 1, since
+         * the assignment in 'shadow' only binds a local. 'overwrite'
+         * declares its names global, so 'x' becomes 7 and the new 'y'
+         * is 8 at module level.
+         */
+        let assign = |name: &str, exp: Expression| {
+            Statement::Assignment(Box::new(String::from(name)), Box::new(exp))
+        };
+        let var = |name: &str| Expression::Var(String::from(name));
+        let func = |name: &str, body: Statement| {
+            Statement::Func(
+                Box::new(String::from(name)),
+                Box::new(Type::TInteger),
+                None,
+                Some(Box::new(body)),
+                Box::new(var("x")),
+            )
+        };
+
+        let shadow = func("shadow", assign("x", Expression::CInt(5)));
+        let overwrite = func(
+            "overwrite",
+            Statement::Sequence(
+                Box::new(Statement::Global(vec![
+                    String::from("x"),
+                    String::from("y"),
+                ])),
+                Box::new(Statement::Sequence(
+                    Box::new(assign("x", Expression::CInt(7))),
+                    Box::new(assign(
+                        "y",
+                        Expression::Add(Box::new(var("x")), Box::new(Expression::CInt(1))),
+                    )),
+                )),
+            ),
         );
 
-        let program = Statement::Sequence(Box::new(a1), Box::new(for_stmt));
-
-        match execute(&program, env) {
+        let program = [
+            shadow,
+            overwrite,
+            assign("a", Expression::FuncCall(String::from("shadow"), None)),
+            assign("b", var("x")),
+            assign("c", Expression::FuncCall(String::from("overwrite"), None)),
+        ]
+        .into_iter()
+        .fold(assign("x", Expression::CInt(1)), |rest, stmt| {
+            Statement::Sequence(Box::new(rest), Box::new(stmt))
+        });
+
+        match execute(&program, Environment::new()) {
             Ok(new_env) => {
-                match new_env.get("y") {
-                    Some(EnvValue::CInt(49)) => {}
-                    Some(val) => assert!(false, "Expected 49, got {:?}", val),
-                    None => assert!(false, "Variable y not found"),
-                }
-                match new_env.get("i") {
-                    None => {}
-                    Some(val) => assert!(false, "Expected None, got {:?}", val),
+                for (name, expected) in [("a", 5), ("b", 1), ("c", 7), ("x", 7), ("y", 8)] {
+                    match new_env.get(name) {
+                        Some(EnvValue::CInt(n)) => {
+                            assert_eq!(n, expected, "Wrong value for {}", name)
+                        }
+                        Some(val) => assert!(false, "Expected {}, got {:?}", expected, val),
+                        None => assert!(false, "Variable {} not found", name),
+                    }
                 }
             }
             Err(s) => assert!(false, "{}", s),
@@ -1091,540 +7510,915 @@ mod tests {
     }
 
     #[test]
-    fn eval_for_loop_no_values() {
+    fn eval_range_inclusive() {
         /*
-         * For loop test for a loop specified by stop only
-         *
-         * > y = 0
-         *
-         * > for i in range(5):
-         * >    y = y + i
-         *
-         * After executing, 'y' should be 10 and 'i' should not be accessible.
+         * > range_inclusive(1, 5)           # [1, 2, 3, 4, 5]
+         * > range_inclusive(3)              # [0, 1, 2, 3]
+         * > range_inclusive(0, 9, 3)        # [0, 3, 6, 9]
+         * > range_inclusive(0, 8, 3)        # [0, 3, 6]
+         * > range_inclusive(5, 1, -1)       # [5, 4, 3, 2, 1]
+         * > range_inclusive(1, 0)           # []
+         * > range_inclusive(2147483645, 2147483647)
+         * >                                 # [2147483645, 2147483646, 2147483647]
          */
-        let env = HashMap::new();
+        let env = Environment::new();
+        let int = |v: i32| Box::new(Expression::CInt(v));
 
-        let a1 = Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
-        let for_exec = Statement::Assignment(
-            Box::new(String::from("y")),
-            Box::new(Expression::Add(
-                Box::new(Expression::Var(String::from("y"))),
-                Box::new(Expression::Var(String::from("i"))),
-            )),
-        );
+        let cases = [
+            (
+                Expression::RangeInclusive(Some(int(1)), int(5), None),
+                vec![1, 2, 3, 4, 5],
+            ),
+            (
+                Expression::RangeInclusive(None, int(3), None),
+                vec![0, 1, 2, 3],
+            ),
+            (
+                Expression::RangeInclusive(Some(int(0)), int(9), Some(int(3))),
+                vec![0, 3, 6, 9],
+            ),
+            (
+                Expression::RangeInclusive(Some(int(0)), int(8), Some(int(3))),
+                vec![0, 3, 6],
+            ),
+            (
+                Expression::RangeInclusive(Some(int(5)), int(1), Some(int(-1))),
+                vec![5, 4, 3, 2, 1],
+            ),
+            (
+                Expression::RangeInclusive(Some(int(1)), int(0), None),
+                vec![],
+            ),
+        ];
 
-        let range = Expression::Range(None, Box::new(Expression::CInt(5)), None);
+        for (range, expected) in cases {
+            let expected = expected.into_iter().map(EvalResult::CInt).collect();
+            assert_eq!(eval(&range, &env), Ok(EvalResult::List(expected)));
+        }
 
-        let for_stmt = Statement::For(
-            Box::new(String::from("i")),
-            Box::new(range),
-            Box::new(for_exec),
+        assert_eq!(
+            eval(
+                &Expression::RangeInclusive(Some(int(1)), int(5), Some(int(0))),
+                &env
+            ),
+            Err(InterpreterError::ValueError(String::from(
+                "Increment cannot be zero"
+            )))
         );
 
-        let program = Statement::Sequence(Box::new(a1), Box::new(for_stmt));
-
-        match execute(&program, env) {
-            Ok(new_env) => {
-                match new_env.get("y") {
-                    Some(EnvValue::CInt(10)) => {}
-                    Some(val) => assert!(false, "Expected 10, got {:?}", val),
-                    None => assert!(false, "Variable y not found"),
-                }
-                match new_env.get("i") {
-                    None => {}
-                    Some(val) => assert!(false, "Expected None, got {:?}", val),
-                }
-            }
-            Err(s) => assert!(false, "{}", s),
+        /* The ends of the int range can be reached, though one step past
+         * them cannot. */
+        let edges = [
+            (
+                Expression::RangeInclusive(Some(int(i32::MAX - 2)), int(i32::MAX), None),
+                vec![i32::MAX - 2, i32::MAX - 1, i32::MAX],
+            ),
+            (
+                Expression::RangeInclusive(Some(int(i32::MAX - 4)), int(i32::MAX), Some(int(3))),
+                vec![i32::MAX - 4, i32::MAX - 1],
+            ),
+            (
+                Expression::RangeInclusive(Some(int(i32::MIN + 2)), int(i32::MIN), Some(int(-1))),
+                vec![i32::MIN + 2, i32::MIN + 1, i32::MIN],
+            ),
+            (
+                Expression::RangeInclusive(Some(int(i32::MAX)), int(i32::MIN), Some(int(i32::MIN))),
+                vec![i32::MAX, -1],
+            ),
+        ];
+        for (range, expected) in edges {
+            let expected = expected.into_iter().map(EvalResult::CInt).collect();
+            assert_eq!(eval(&range, &env), Ok(EvalResult::List(expected)));
         }
     }
 
     #[test]
-    fn eval_for_loop_no_range() {
+    fn eval_range_descending_steps() {
         /*
-         * For loop test for condition never reached
-         *
-         * > y = 0
-         *
-         * > for i in range(0, 1, -1):
-         * >    y = y + i
-         *
+         * > range(10, 0, -2)        # [10, 8, 6, 4, 2]
+         * > range(5, 0, -1)         # [5, 4, 3, 2, 1]
+         * > range(10, 0, -3)        # [10, 7, 4, 1]
+         * > range(10, 3, -4)        # [10, 6]
+         * > range(7, -7, -5)        # [7, 2, -3]
+         * > range(3, 3, -1)         # []
+         * > range(3, 5, -1)         # []
          */
-        let env = HashMap::new();
-
-        let a1 = Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
-        let for_exec = Statement::Assignment(
-            Box::new(String::from("y")),
-            Box::new(Expression::Add(
-                Box::new(Expression::Var(String::from("y"))),
-                Box::new(Expression::Var(String::from("i"))),
-            )),
-        );
+        let env = Environment::new();
+        let int = |v: i32| Box::new(Expression::CInt(v));
+
+        let cases = [
+            ((10, 0, -2), vec![10, 8, 6, 4, 2]),
+            ((5, 0, -1), vec![5, 4, 3, 2, 1]),
+            ((10, 0, -3), vec![10, 7, 4, 1]),
+            ((10, 3, -4), vec![10, 6]),
+            ((7, -7, -5), vec![7, 2, -3]),
+            ((3, 3, -1), vec![]),
+            ((3, 5, -1), vec![]),
+            ((i32::MAX, i32::MAX, -1), vec![]),
+            (
+                (i32::MIN + 2, i32::MIN, -1),
+                vec![i32::MIN + 2, i32::MIN + 1],
+            ),
+        ];
 
-        let range = Expression::Range(
-            Some(Box::new(Expression::CInt(0))),
-            Box::new(Expression::CInt(1)),
-            Some(Box::new(Expression::CInt(-1))),
-        );
+        for ((start, end, step), expected) in cases {
+            let range = Expression::Range(Some(int(start)), int(end), Some(int(step)));
+            let expected = expected.into_iter().map(EvalResult::CInt).collect();
+            assert_eq!(eval(&range, &env), Ok(EvalResult::List(expected)));
+        }
 
-        let for_stmt = Statement::For(
-            Box::new(String::from("i")),
-            Box::new(range),
-            Box::new(for_exec),
+        /* The inclusive form relies on the same descending steps. */
+        assert_eq!(
+            eval(
+                &Expression::RangeInclusive(Some(int(10)), int(2), Some(int(-2))),
+                &env
+            ),
+            Ok(EvalResult::List(
+                [10, 8, 6, 4, 2].into_iter().map(EvalResult::CInt).collect()
+            ))
         );
-
-        let program = Statement::Sequence(Box::new(a1), Box::new(for_stmt));
-
-        match execute(&program, env) {
-            Ok(new_env) => match new_env.get("y") {
-                Some(EnvValue::CInt(0)) => (),
-                Some(val) => assert!(false, "Expected 0, got {:?}", val),
-                None => assert!(false, "Variable y not found"),
-            },
-            Err(s) => assert!(false, "{}", s),
-        }
     }
 
     #[test]
-    fn eval_for_loop_list() {
+    fn builtin_sorted() {
         /*
-         * For loop test for a list of objects
-         *
-         * > y = 0
-         *
-         * > for i in [1, 3, 5]:
-         * >    y = y + i
-         *
-         * After executing, 'y' should be 9  and 'i' should not be accessible.
+         * > xs = [3, 1, 2, 1]
+         * > ys = sorted(xs)         # [1, 1, 2, 3], 'xs' is unchanged
+         * > zs = sorted(xs, True)   # [3, 2, 1, 1]
+         * > sorted((2.0, 1, 2))     # [1, 2.0, 2], equal items keep their order
+         * > sorted((2.0, 1, 2), True)   # [2.0, 2, 1]
+         * > sorted("bca")           # ["a", "b", "c"]
          */
-        let env = HashMap::new();
+        let xs = vec![
+            EvalResult::CInt(3),
+            EvalResult::CInt(1),
+            EvalResult::CInt(2),
+            EvalResult::CInt(1),
+        ];
+        let env = Environment::from([(
+            String::from("xs"),
+            EnvValue::from(EvalResult::List(xs.clone())),
+        )]);
+        let sorted = |args: Vec<Expression>| {
+            eval(
+                &Expression::FuncCall(String::from("sorted"), Some(args)),
+                &env,
+            )
+        };
+        let var = || Expression::Var(String::from("xs"));
 
-        let a1 = Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
-
-        let for_exec = Statement::Assignment(
-            Box::new(String::from("y")),
-            Box::new(Expression::Add(
-                Box::new(Expression::Var(String::from("y"))),
-                Box::new(Expression::Var(String::from("i"))),
-            )),
+        assert_eq!(
+            sorted(vec![var()]),
+            Ok(EvalResult::List(vec![
+                EvalResult::CInt(1),
+                EvalResult::CInt(1),
+                EvalResult::CInt(2),
+                EvalResult::CInt(3),
+            ]))
+        );
+        assert_eq!(eval(&var(), &env), Ok(EvalResult::List(xs)));
+        assert_eq!(
+            sorted(vec![var(), Expression::Bool(true)]),
+            Ok(EvalResult::List(vec![
+                EvalResult::CInt(3),
+                EvalResult::CInt(2),
+                EvalResult::CInt(1),
+                EvalResult::CInt(1),
+            ]))
         );
 
-        let l1 = Expression::List(vec![
-            Expression::CInt(1),
-            Expression::CInt(3),
-            Expression::CInt(5),
-        ]);
+        let ties = || {
+            Expression::Tuple(vec![
+                Expression::CReal(2.0),
+                Expression::CInt(1),
+                Expression::CInt(2),
+            ])
+        };
+        assert_eq!(
+            sorted(vec![ties()]),
+            Ok(EvalResult::List(vec![
+                EvalResult::CInt(1),
+                EvalResult::CReal(2.0),
+                EvalResult::CInt(2),
+            ]))
+        );
+        assert_eq!(
+            sorted(vec![ties(), Expression::Bool(true)]),
+            Ok(EvalResult::List(vec![
+                EvalResult::CReal(2.0),
+                EvalResult::CInt(2),
+                EvalResult::CInt(1),
+            ]))
+        );
 
-        let for_stmt = Statement::For(
-            Box::new(String::from("i")),
-            Box::new(l1),
-            Box::new(for_exec),
+        assert_eq!(
+            sorted(vec![Expression::CString(String::from("bca"))]),
+            Ok(EvalResult::List(vec![
+                EvalResult::CString(String::from("a")),
+                EvalResult::CString(String::from("b")),
+                EvalResult::CString(String::from("c")),
+            ]))
         );
+    }
 
-        let program = Statement::Sequence(Box::new(a1), Box::new(for_stmt));
+    #[test]
+    fn builtin_sorted_errors() {
+        let env = Environment::new();
+        let sorted = |args: Vec<Expression>| {
+            eval(
+                &Expression::FuncCall(String::from("sorted"), Some(args)),
+                &env,
+            )
+            .map_err(|e| e.to_string())
+        };
 
-        match execute(&program, env) {
-            Ok(new_env) => match new_env.get("y") {
-                Some(EnvValue::CInt(9)) => (),
-                Some(val) => assert!(false, "Expected 9, got {:?}", val),
-                None => assert!(false, "Variable y not found"),
-            },
-            Err(s) => assert!(false, "{}", s),
-        }
+        assert_eq!(
+            sorted(vec![Expression::Tuple(vec![
+                Expression::CInt(1),
+                Expression::CString(String::from("a")),
+            ])]),
+            Err(String::from(
+                "'<' not supported between instances of 'str' and 'int'"
+            ))
+        );
+        assert_eq!(
+            sorted(vec![Expression::CInt(1)]),
+            Err(String::from("'int' object is not iterable"))
+        );
+        assert_eq!(
+            sorted(vec![]),
+            Err(String::from("sorted expected 1 or 2 arguments, got 0"))
+        );
     }
 
     #[test]
-    fn eval_nested_if_statements() {
+    fn builtin_reversed() {
         /*
-         * Test for nested if-then-else statements
-         *
-         * > x = 10
-         * > if x > 5:
-         * >   if x > 8:
-         * >     y = 1
-         * >   else:
-         * >     y = 2
-         * > else:
-         * >   y = 0
-         *
-         * After executing, 'y' should be 1.
+         * > reversed([1, 2, 3])     # [3, 2, 1]
+         * > reversed((1, "a"))      # ["a", 1]
+         * > reversed("abc")         # "cba"
+         * > reversed(12)            # TypeError
          */
-        let env = HashMap::new();
+        let env = Environment::new();
+        let reversed = |arg: Expression| {
+            eval(
+                &Expression::FuncCall(String::from("reversed"), Some(vec![arg])),
+                &env,
+            )
+        };
 
-        let inner_then_stmt =
-            Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(1)));
-        let inner_else_stmt =
-            Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(2)));
-        let inner_if_statement = Statement::IfThenElse(
-            Box::new(Expression::Var(String::from("x"))),
-            Box::new(inner_then_stmt),
-            Box::new(inner_else_stmt),
+        assert_eq!(
+            reversed(Expression::List(vec![
+                Expression::CInt(1),
+                Expression::CInt(2),
+                Expression::CInt(3),
+            ])),
+            Ok(EvalResult::List(vec![
+                EvalResult::CInt(3),
+                EvalResult::CInt(2),
+                EvalResult::CInt(1),
+            ]))
         );
-
-        let outer_else_stmt =
-            Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
-        let outer_if_statement = Statement::IfThenElse(
-            Box::new(Expression::Var(String::from("x"))),
-            Box::new(inner_if_statement),
-            Box::new(outer_else_stmt),
+        assert_eq!(
+            reversed(Expression::Tuple(vec![
+                Expression::CInt(1),
+                Expression::CString(String::from("a")),
+            ])),
+            Ok(EvalResult::List(vec![
+                EvalResult::CString(String::from("a")),
+                EvalResult::CInt(1),
+            ]))
+        );
+        assert_eq!(
+            reversed(Expression::CString(String::from("abc"))),
+            Ok(EvalResult::CString(String::from("cba")))
+        );
+        assert_eq!(
+            reversed(Expression::CInt(12)),
+            Err(InterpreterError::TypeError(String::from(
+                "argument to reversed() must be a sequence"
+            )))
         );
-
-        let setup_stmt =
-            Statement::Assignment(Box::new(String::from("x")), Box::new(Expression::CInt(10)));
-        let program = Statement::Sequence(Box::new(setup_stmt), Box::new(outer_if_statement));
-
-        match execute(&program, env) {
-            Ok(new_env) => match new_env.get("y") {
-                Some(EnvValue::CInt(1)) => {}
-                Some(val) => assert!(false, "Expected 1, got {:?}", val),
-                None => assert!(false, "Variable y not found"),
-            },
-            Err(s) => assert!(false, "{}", s),
-        }
     }
 
     #[test]
-    fn eval_complex_sequence() {
+    fn eval_for_over_dict_keys() {
         /*
-         * Sequence with multiple assignments and expressions
-         *
-         * > x = 5
-         * > y = 0
-         * > z = 2 * x + 3
+         * > ages = {"bia": 25, "ana": 30, "caio": 41}
+         * > names = ""
+         * > total = 0
+         * > for name in ages:
+         * >    names = names + name
+         * >    total = total + ages[name]
+         * > for x in 3:
+         * >    total = 0
          *
-         * After executing, 'x' should be 5, 'y' should be 0, and 'z' should be 13.
+         * The keys come in insertion order, so 'names' should be
+         * "biaanacaio" and 'total' should be 96. The second loop fails,
+         * since an int can't be iterated.
          */
-        let env = HashMap::new();
-
-        let a1 = Statement::Assignment(Box::new(String::from("x")), Box::new(Expression::CInt(5)));
-        let a2 = Statement::Assignment(Box::new(String::from("y")), Box::new(Expression::CInt(0)));
-        let a3 = Statement::Assignment(
-            Box::new(String::from("z")),
-            Box::new(Expression::Add(
-                Box::new(Expression::Mul(
-                    Box::new(Expression::CInt(2)),
-                    Box::new(Expression::Var(String::from("x"))),
+        let s = |v: &str| Expression::CString(String::from(v));
+        let var = |name: &str| Box::new(Expression::Var(String::from(name)));
+        let assign = |name: &str, exp: Expression| {
+            Statement::Assignment(Box::new(String::from(name)), Box::new(exp))
+        };
+
+        let program = [
+            assign("names", s("")),
+            assign("total", Expression::CInt(0)),
+            Statement::For(
+                Box::new(String::from("name")),
+                var("ages"),
+                Box::new(Statement::Sequence(
+                    Box::new(assign("names", Expression::Add(var("names"), var("name")))),
+                    Box::new(assign(
+                        "total",
+                        Expression::Add(
+                            var("total"),
+                            Box::new(Expression::Index(var("ages"), var("name"))),
+                        ),
+                    )),
                 )),
-                Box::new(Expression::CInt(3)),
-            )),
-        );
-
-        let program = Statement::Sequence(
-            Box::new(a1),
-            Box::new(Statement::Sequence(Box::new(a2), Box::new(a3))),
+            ),
+        ]
+        .into_iter()
+        .fold(
+            assign(
+                "ages",
+                Expression::Dict(vec![
+                    (s("bia"), Expression::CInt(25)),
+                    (s("ana"), Expression::CInt(30)),
+                    (s("caio"), Expression::CInt(41)),
+                ]),
+            ),
+            |rest, stmt| Statement::Sequence(Box::new(rest), Box::new(stmt)),
         );
 
-        match execute(&program, env) {
+        match execute(&program, Environment::new()) {
             Ok(new_env) => {
-                match new_env.get("x") {
-                    Some(EnvValue::CInt(5)) => {}
-                    Some(val) => assert!(false, "Expected 5, got {:?}", val),
-                    None => assert!(false, "Variable x not found"),
-                }
-                match new_env.get("y") {
-                    Some(EnvValue::CInt(0)) => {}
-                    Some(val) => assert!(false, "Expected 0, got {:?}", val),
-                    None => assert!(false, "Variable y not found"),
+                match new_env.get("names") {
+                    Some(EnvValue::CString(names)) => assert_eq!(names, "biaanacaio"),
+                    other => assert!(false, "Expected a str, found {:?}", other),
                 }
-                match new_env.get("z") {
-                    Some(EnvValue::CInt(13)) => {}
-                    Some(val) => assert!(false, "Expected 13, got {:?}", val),
-                    None => assert!(false, "Variable z not found"),
+                match new_env.get("total") {
+                    Some(EnvValue::CInt(96)) => (),
+                    other => assert!(false, "Expected 96, found {:?}", other),
                 }
             }
             Err(s) => assert!(false, "{}", s),
         }
+
+        let not_iterable = Statement::For(
+            Box::new(String::from("x")),
+            Box::new(Expression::CInt(3)),
+            Box::new(assign("total", Expression::CInt(0))),
+        );
+        assert_eq!(
+            execute(&not_iterable, Environment::new()),
+            Err(InterpreterError::TypeError(String::from(
+                "Expression must be an iterable object"
+            )))
+        );
     }
 
     #[test]
-    fn func_decl_call() {
+    fn eval_lambda_with_map() {
         /*
-         * Test for declaration and call of a function
-         *
-         * > def add(a: CInt, b: CInt) -> CInt:
-         * >    t = a + b
-         * >    return t
-         * >
-         * > sum = add(5, 7)
-         *
-         * After executing, 'sum' should be 12.
+         * > k = 3
+         * > map(lambda x: x * k, [1, 2, 3])     # [3, 6, 9]
          */
-        let env = Environment::new();
-
-        let mut args = HashMap::new();
-        args.insert(String::from("a"), Box::new(EvalResult::CInt(0)));
-        args.insert(String::from("b"), Box::new(EvalResult::CInt(0)));
-
-        let program = Statement::Sequence(
-            Box::new(Statement::Func(
-                Box::new(String::from("add")),
-                Box::new(EvalResult::CInt(0)),
-                Some(args),
-                Some(Box::new(Statement::Assignment(
-                    Box::new(String::from("t")),
-                    Box::new(Expression::Add(
-                        Box::new(Expression::Var(String::from("a"))),
-                        Box::new(Expression::Var(String::from("b"))),
-                    )),
-                ))),
-                Box::new(Expression::Var(String::from("t"))),
-            )),
-            Box::new(Statement::Assignment(
-                Box::new(String::from("sum")),
-                Box::new(Expression::FuncCall(
-                    String::from("add"),
-                    Some(vec![Expression::CInt(5), Expression::CInt(7)]),
-                )),
+        let env = Environment::from([(String::from("k"), EnvValue::CInt(3))]);
+        let scale = Expression::Lambda(
+            vec![String::from("x")],
+            Box::new(Expression::Mul(
+                Box::new(Expression::Var(String::from("x"))),
+                Box::new(Expression::Var(String::from("k"))),
             )),
         );
+        let call = Expression::FuncCall(
+            String::from("map"),
+            Some(vec![
+                scale,
+                Expression::List(vec![
+                    Expression::CInt(1),
+                    Expression::CInt(2),
+                    Expression::CInt(3),
+                ]),
+            ]),
+        );
+
+        assert_eq!(
+            eval(&call, &env),
+            Ok(EvalResult::List(vec![
+                EvalResult::CInt(3),
+                EvalResult::CInt(6),
+                EvalResult::CInt(9),
+            ]))
+        );
+    }
 
-        match execute(&program, env) {
-            Ok(new_env) => match new_env.get("sum") {
-                Some(EnvValue::CInt(12)) => {}
-                Some(val) => assert!(false, "Expected 12, got {:?}", val),
-                None => assert!(false, "Variable sum not found"),
-            },
-            Err(s) => assert!(false, "{}", s),
+    #[test]
+    fn eval_arithmetic_with_none() {
+        /*
+         * > 1 + None        # unsupported operand type(s) for +: 'int' and 'NoneType'
+         * > None - 2.5      # unsupported operand type(s) for -: 'NoneType' and 'real'
+         * > "ab" * None     # unsupported operand type(s) for *: 'str' and 'NoneType'
+         * > None / [1]      # unsupported operand type(s) for /: 'NoneType' and 'list'
+         */
+        let env = Environment::new();
+        let none = || Box::new(Expression::None);
+        let int = || Box::new(Expression::CInt(1));
+        let real = || Box::new(Expression::CReal(2.5));
+        let string = || Box::new(Expression::CString(String::from("ab")));
+        let list = || Box::new(Expression::List(vec![Expression::CInt(1)]));
+
+        let cases = [
+            (Expression::Add(int(), none()), "+: 'int' and 'NoneType'"),
+            (Expression::Add(none(), int()), "+: 'NoneType' and 'int'"),
+            (Expression::Add(string(), none()), "+: 'str' and 'NoneType'"),
+            (Expression::Add(none(), list()), "+: 'NoneType' and 'list'"),
+            (Expression::Sub(int(), none()), "-: 'int' and 'NoneType'"),
+            (Expression::Sub(none(), real()), "-: 'NoneType' and 'real'"),
+            (Expression::Mul(string(), none()), "*: 'str' and 'NoneType'"),
+            (Expression::Mul(none(), int()), "*: 'NoneType' and 'int'"),
+            (Expression::Div(real(), none()), "/: 'real' and 'NoneType'"),
+            (Expression::Div(none(), list()), "/: 'NoneType' and 'list'"),
+            (
+                Expression::FloorDiv(none(), none()),
+                "//: 'NoneType' and 'NoneType'",
+            ),
+        ];
+
+        for (exp, message) in cases {
+            assert_eq!(
+                eval(&exp, &env),
+                Err(InterpreterError::TypeError(format!(
+                    "unsupported operand type(s) for {}",
+                    message
+                )))
+            );
         }
     }
 
     #[test]
-    fn func_decl_call_without_stmt() {
+    fn eval_expression_statement() {
         /*
-         * Test for declaration and call of a function with no statement
-         *
-         * > def add(a: CInt, b: CInt) -> CInt:
-         * >    return a + b
+         * > def ping() -> int:
+         * >    return 1
          * >
-         * > sum = add(1, 2)
+         * > x = 2
+         * > ping()
          *
-         * After executing, 'sum' should be 3.
+         * The call runs and its result is dropped, leaving only 'ping'
+         * and 'x' behind.
          */
-        let env = Environment::new();
-
-        let mut args = HashMap::new();
-        args.insert(String::from("a"), Box::new(EvalResult::CInt(0)));
-        args.insert(String::from("b"), Box::new(EvalResult::CInt(0)));
-
+        let ping = Statement::Func(
+            Box::new(String::from("ping")),
+            Box::new(Type::TInteger),
+            None,
+            None,
+            Box::new(Expression::CInt(1)),
+        );
         let program = Statement::Sequence(
-            Box::new(Statement::Func(
-                Box::new(String::from("add")),
-                Box::new(EvalResult::CInt(0)),
-                Some(args),
-                None,
-                Box::new(Expression::Add(
-                    Box::new(Expression::Var(String::from("a"))),
-                    Box::new(Expression::Var(String::from("b"))),
-                )),
-            )),
-            Box::new(Statement::Assignment(
-                Box::new(String::from("sum")),
-                Box::new(Expression::FuncCall(
-                    String::from("add"),
-                    Some(vec![Expression::CInt(1), Expression::CInt(2)]),
+            Box::new(ping),
+            Box::new(Statement::Sequence(
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("x")),
+                    Box::new(Expression::CInt(2)),
                 )),
+                Box::new(Statement::Expr(Box::new(Expression::FuncCall(
+                    String::from("ping"),
+                    None,
+                )))),
             )),
         );
 
-        match execute(&program, env) {
-            Ok(new_env) => match new_env.get("sum") {
-                Some(EnvValue::CInt(3)) => {}
-                Some(val) => assert!(false, "Expected 3, got {:?}", val),
-                None => assert!(false, "Variable sum not found"),
-            },
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert!(matches!(new_env.get("ping"), Some(EnvValue::Func(_))));
+                assert_eq!(new_env.get("x"), Some(EnvValue::CInt(2)));
+            }
             Err(s) => assert!(false, "{}", s),
         }
+
+        let failing = Statement::Expr(Box::new(Expression::FuncCall(String::from("pong"), None)));
+        assert_eq!(
+            execute(&failing, Environment::new()),
+            Err(InterpreterError::UndefinedFunction(String::from("pong")))
+        );
     }
 
     #[test]
-    fn func_decl_call_without_args() {
+    fn eval_deep_equality() {
         /*
-         * Test for declaration and call of a function with no arguments
-         *
-         * > def two_plus_two() -> CInt:
-         * >    return 4
-         * >
-         * > value = two_plus_two()
-         *
-         * After executing, 'sum' should be 4.
+         * > [[1, 2], [3]] == [[1, 2], [3]]          # True
+         * > [[1, 2], [3]] == [[1, 2], [3.0]]        # True, 3 == 3.0
+         * > [[1, 2], [3]] == [[1, 2], [3, 4]]       # False
+         * > [[1, 2], [3]] == [[1, 2], ["3"]]        # False
+         * > [(1, [2])] != [(1, [2])]                # False
+         * > nan == nan                              # False
+         * > [nan] == [nan]                          # False
+         * > [nan] != [nan]                          # True
          */
         let env = Environment::new();
+        let list = |items: Vec<Expression>| Expression::List(items);
+        let int = |v: i32| Expression::CInt(v);
+        let nested = |last: Vec<Expression>| list(vec![list(vec![int(1), int(2)]), list(last)]);
+        let nan = || Expression::CReal(f32::NAN);
 
-        let program = Statement::Sequence(
-            Box::new(Statement::Func(
-                Box::new(String::from("two_plus_two")),
-                Box::new(EvalResult::CInt(0)),
+        let cases = [
+            (
+                Expression::Eq(
+                    Box::new(nested(vec![int(3)])),
+                    Box::new(nested(vec![int(3)])),
+                ),
+                true,
+            ),
+            (
+                Expression::Eq(
+                    Box::new(nested(vec![int(3)])),
+                    Box::new(nested(vec![Expression::CReal(3.0)])),
+                ),
+                true,
+            ),
+            (
+                Expression::Eq(
+                    Box::new(nested(vec![int(3)])),
+                    Box::new(nested(vec![int(3), int(4)])),
+                ),
+                false,
+            ),
+            (
+                Expression::Eq(
+                    Box::new(nested(vec![int(3)])),
+                    Box::new(nested(vec![Expression::CString(String::from("3"))])),
+                ),
+                false,
+            ),
+            (
+                Expression::NotEq(
+                    Box::new(list(vec![Expression::Tuple(vec![
+                        int(1),
+                        list(vec![int(2)]),
+                    ])])),
+                    Box::new(list(vec![Expression::Tuple(vec![
+                        int(1),
+                        list(vec![int(2)]),
+                    ])])),
+                ),
+                false,
+            ),
+            (Expression::Eq(Box::new(nan()), Box::new(nan())), false),
+            (
+                Expression::Eq(Box::new(list(vec![nan()])), Box::new(list(vec![nan()]))),
+                false,
+            ),
+            (
+                Expression::NotEq(Box::new(list(vec![nan()])), Box::new(list(vec![nan()]))),
+                true,
+            ),
+        ];
+
+        for (exp, expected) in cases {
+            assert_eq!(
+                eval(&exp, &env),
+                Ok(EvalResult::Bool(expected)),
+                "{:?}",
+                exp
+            );
+        }
+    }
+
+    #[test]
+    fn eval_function_equality() {
+        /*
+         * > def f() -> int:
+         * >    return 1
+         * > def g() -> int:
+         * >    return 1
+         * > same = (f,) == (f,)     # True
+         * > other = f == g          # False
+         */
+        let func = |name: &str| {
+            Statement::Func(
+                Box::new(String::from(name)),
+                Box::new(Type::TInteger),
                 None,
                 None,
-                Box::new(Expression::CInt(4)),
-            )),
-            Box::new(Statement::Assignment(
-                Box::new(String::from("value")),
-                Box::new(Expression::FuncCall(String::from("two_plus_two"), None)),
-            )),
-        );
+                Box::new(Expression::CInt(1)),
+            )
+        };
+        let var = |name: &str| Box::new(Expression::Var(String::from(name)));
+        let program = [
+            func("g"),
+            Statement::Assignment(
+                Box::new(String::from("same")),
+                Box::new(Expression::Eq(
+                    Box::new(Expression::Tuple(vec![*var("f")])),
+                    Box::new(Expression::Tuple(vec![*var("f")])),
+                )),
+            ),
+            Statement::Assignment(
+                Box::new(String::from("other")),
+                Box::new(Expression::Eq(var("f"), var("g"))),
+            ),
+        ]
+        .into_iter()
+        .fold(func("f"), |rest, stmt| {
+            Statement::Sequence(Box::new(rest), Box::new(stmt))
+        });
 
-        match execute(&program, env) {
-            Ok(new_env) => match new_env.get("value") {
-                Some(EnvValue::CInt(4)) => {}
-                Some(val) => assert!(false, "Expected 4, got {:?}", val),
-                None => assert!(false, "Variable value not found"),
-            },
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("same"), Some(EnvValue::Bool(true)));
+                assert_eq!(new_env.get("other"), Some(EnvValue::Bool(false)));
+            }
             Err(s) => assert!(false, "{}", s),
         }
     }
 
     #[test]
-    fn num_arguments_error_func_call() {
+    fn builtin_count_and_index() {
         /*
-         * Test for declaration and call of a function where the passed
-         * arguments don't match the functions definition
-         *
-         * > def add(a: CInt, b: CInt) -> CInt:
-         * >    return a + b
-         * >
-         * > sum = add(1, 2, 3)
-         *
+         * > xs = [3, 1, 3, 2]
+         * > count(xs, 3)            # 2
+         * > count(xs, 7)            # 0
+         * > index(xs, 3)            # 0
+         * > index(xs, 2.0)          # 3
+         * > index(xs, 7)            # ValueError: 7 is not in list
+         * > index(("a",), "b")      # ValueError: 'b' is not in list
          */
-        let env = Environment::new();
-
-        let mut args = HashMap::new();
-        args.insert(String::from("a"), Box::new(EvalResult::CInt(0)));
-        args.insert(String::from("b"), Box::new(EvalResult::CInt(0)));
+        let xs = EvalResult::List(vec![
+            EvalResult::CInt(3),
+            EvalResult::CInt(1),
+            EvalResult::CInt(3),
+            EvalResult::CInt(2),
+        ]);
+        let env = Environment::from([(String::from("xs"), EnvValue::from(xs))]);
+        let call = |name: &str, target: Expression, value: Expression| {
+            eval(
+                &Expression::FuncCall(String::from(name), Some(vec![target, value])),
+                &env,
+            )
+        };
+        let xs = || Expression::Var(String::from("xs"));
 
-        let program = Statement::Sequence(
-            Box::new(Statement::Func(
-                Box::new(String::from("add")),
-                Box::new(EvalResult::CInt(0)),
-                Some(args),
-                None,
-                Box::new(Expression::Add(
-                    Box::new(Expression::Var(String::from("a"))),
-                    Box::new(Expression::Var(String::from("b"))),
-                )),
-            )),
-            Box::new(Statement::Assignment(
-                Box::new(String::from("sum")),
-                Box::new(Expression::FuncCall(
-                    String::from("add"),
-                    Some(vec![
-                        Expression::CInt(1),
-                        Expression::CInt(2),
-                        Expression::CInt(3),
-                    ]),
-                )),
-            )),
+        assert_eq!(
+            call("count", xs(), Expression::CInt(3)),
+            Ok(EvalResult::CInt(2))
+        );
+        assert_eq!(
+            call("count", xs(), Expression::CInt(7)),
+            Ok(EvalResult::CInt(0))
+        );
+        assert_eq!(
+            call("index", xs(), Expression::CInt(3)),
+            Ok(EvalResult::CInt(0))
+        );
+        assert_eq!(
+            call("index", xs(), Expression::CReal(2.0)),
+            Ok(EvalResult::CInt(3))
+        );
+        assert_eq!(
+            call("index", xs(), Expression::CInt(7)),
+            Err(InterpreterError::ValueError(String::from(
+                "7 is not in list"
+            )))
+        );
+        assert_eq!(
+            call(
+                "index",
+                Expression::Tuple(vec![Expression::CString(String::from("a"))]),
+                Expression::CString(String::from("b"))
+            ),
+            Err(InterpreterError::ValueError(String::from(
+                "'b' is not in list"
+            )))
         );
+    }
 
-        match execute(&program, env) {
-            Ok(_) => assert!(false, "Function should generate an error"),
-            Err(s) => assert_eq!(s, "add requires 2 arguments, got 3"),
-        }
+    #[test]
+    fn builtin_upper_lower_strip() {
+        /*
+         * > upper("Olá, Mundo")     # "OLÁ, MUNDO"
+         * > lower("Olá, Mundo")     # "olá, mundo"
+         * > strip("  \t hi there\n ")   # "hi there"
+         * > upper(1)                # TypeError
+         */
+        let env = Environment::new();
+        let call = |name: &str, arg: Expression| {
+            eval(
+                &Expression::FuncCall(String::from(name), Some(vec![arg])),
+                &env,
+            )
+        };
+        let s = |v: &str| Expression::CString(String::from(v));
+        let r = |v: &str| Ok(EvalResult::CString(String::from(v)));
+
+        assert_eq!(call("upper", s("Olá, Mundo")), r("OLÁ, MUNDO"));
+        assert_eq!(call("lower", s("Olá, Mundo")), r("olá, mundo"));
+        assert_eq!(call("strip", s("  \t hi there\n ")), r("hi there"));
+        assert_eq!(call("strip", s("   ")), r(""));
+        assert_eq!(
+            call("upper", Expression::CInt(1)),
+            Err(InterpreterError::TypeError(String::from(
+                "upper expected str, got int"
+            )))
+        );
     }
 
     #[test]
-    fn arguments_type_error_func_call() {
+    fn builtin_split() {
         /*
-         * Test for declaration and call of a function where the passed
-         * arguments don't match their defined types on the function
-         *
-         * > def add(a: CInt, b: CReal) -> CReal:
-         * >    return a + b
-         * >
-         * > sum = add(1, 2)
-         *
+         * > split("a,b,,c", ",")        # ["a", "b", "", "c"]
+         * > split("a, b", ", ")         # ["a", "b"]
+         * > split("  one two\t three\n")    # ["one", "two", "three"]
+         * > split("")                   # []
+         * > split("abc", "")            # ValueError: empty separator
          */
         let env = Environment::new();
+        let s = |v: &str| Expression::CString(String::from(v));
+        let split = |args: Vec<Expression>| {
+            eval(
+                &Expression::FuncCall(String::from("split"), Some(args)),
+                &env,
+            )
+        };
+        let strings = |items: &[&str]| {
+            Ok(EvalResult::List(
+                items
+                    .iter()
+                    .map(|item| EvalResult::CString(item.to_string()))
+                    .collect(),
+            ))
+        };
 
-        let mut args = HashMap::new();
-        args.insert(String::from("a"), Box::new(EvalResult::CInt(0)));
-        args.insert(String::from("b"), Box::new(EvalResult::CReal(0.0)));
+        assert_eq!(
+            split(vec![s("a,b,,c"), s(",")]),
+            strings(&["a", "b", "", "c"])
+        );
+        assert_eq!(split(vec![s("a, b"), s(", ")]), strings(&["a", "b"]));
+        assert_eq!(
+            split(vec![s("  one two\t three\n")]),
+            strings(&["one", "two", "three"])
+        );
+        assert_eq!(split(vec![s("")]), strings(&[]));
+        assert_eq!(
+            split(vec![s("abc"), s("")]),
+            Err(InterpreterError::ValueError(String::from(
+                "empty separator"
+            )))
+        );
+        assert_eq!(
+            split(vec![Expression::CInt(1)]),
+            Err(InterpreterError::TypeError(String::from(
+                "split expected str, got int"
+            )))
+        );
+    }
 
+    #[test]
+    fn runaway_recursion_hits_depth_limit() {
+        /*
+         * > def forever(n: int) -> int:
+         * >    return forever(n + 1)
+         * >
+         * > x = forever(0)
+         *
+         * The call never bottoms out, so it must stop with a recursion
+         * error once the depth limit is reached instead of overflowing
+         * the stack, on an ordinary thread and in a debug build too.
+         */
+        let forever = Statement::Func(
+            Box::new(String::from("forever")),
+            Box::new(Type::TInteger),
+            Some(vec![(String::from("n"), Box::new(Type::TInteger))]),
+            Some(Box::new(Statement::Return(Box::new(Expression::FuncCall(
+                String::from("forever"),
+                Some(vec![Expression::Add(
+                    Box::new(Expression::Var(String::from("n"))),
+                    Box::new(Expression::CInt(1)),
+                )]),
+            ))))),
+            Box::new(Expression::None),
+        );
         let program = Statement::Sequence(
-            Box::new(Statement::Func(
-                Box::new(String::from("add")),
-                Box::new(EvalResult::CReal(0.0)),
-                Some(args),
-                None,
-                Box::new(Expression::Add(
-                    Box::new(Expression::Var(String::from("a"))),
-                    Box::new(Expression::Var(String::from("b"))),
-                )),
-            )),
+            Box::new(forever),
             Box::new(Statement::Assignment(
-                Box::new(String::from("sum")),
+                Box::new(String::from("x")),
                 Box::new(Expression::FuncCall(
-                    String::from("add"),
-                    Some(vec![Expression::CInt(1), Expression::CInt(2)]),
+                    String::from("forever"),
+                    Some(vec![Expression::CInt(0)]),
                 )),
             )),
         );
 
-        match execute(&program, env) {
-            Ok(_) => assert!(false, "Function should generate an error"),
-            Err(s) => assert_eq!(s, "Mismatched types for CReal(0.0)"),
+        match Interpreter::new()
+            .with_max_depth(20)
+            .execute(&program, Environment::new())
+        {
+            Ok(_) => assert!(false, "Expected the recursion limit to be exceeded"),
+            Err(s) => {
+                assert_eq!(s.root_cause(), &InterpreterError::RecursionLimitExceeded);
+                assert_eq!(
+                    s.root_cause().to_string(),
+                    "maximum recursion depth exceeded"
+                );
+            }
+        }
+
+        match execute(&program, Environment::new()) {
+            Ok(_) => assert!(false, "Expected the recursion limit to be exceeded"),
+            Err(s) => assert_eq!(s.root_cause(), &InterpreterError::RecursionLimitExceeded),
         }
     }
 
     #[test]
-    fn func_return_type_error() {
+    fn infinite_while_hits_step_budget() {
         /*
-         * Test for declaration and call of a function where the return type
-         * is different from the one defined by the function
-         *
-         * > def add(a: CReal, b: CReal) -> CInt:
-         * >    return a + b
-         * >
-         * > sum = add(1.5, 2.5)
+         * > count = 0
+         * > while True:
+         * >    count = count + 1
          *
+         * The loop never ends on its own, so with a budget of 1000
+         * statements it must stop with the step limit error. Every
+         * iteration spends from the budget, so the count it reached
+         * is bounded by it.
          */
-        let env = Environment::new();
-
-        let mut args = HashMap::new();
-        args.insert(String::from("a"), Box::new(EvalResult::CReal(1.5)));
-        args.insert(String::from("b"), Box::new(EvalResult::CReal(2.5)));
-
+        let count = || Box::new(String::from("count"));
         let program = Statement::Sequence(
-            Box::new(Statement::Func(
-                Box::new(String::from("add")),
-                Box::new(EvalResult::CInt(0)),
-                Some(args),
-                None,
-                Box::new(Expression::Add(
-                    Box::new(Expression::Var(String::from("a"))),
-                    Box::new(Expression::Var(String::from("b"))),
-                )),
-            )),
             Box::new(Statement::Assignment(
-                Box::new(String::from("sum")),
-                Box::new(Expression::FuncCall(
-                    String::from("add"),
-                    Some(vec![Expression::CReal(1.5), Expression::CReal(2.5)]),
+                count(),
+                Box::new(Expression::CInt(0)),
+            )),
+            Box::new(Statement::While(
+                Box::new(Expression::Bool(true)),
+                Box::new(Statement::Assignment(
+                    count(),
+                    Box::new(Expression::Add(
+                        Box::new(Expression::Var(String::from("count"))),
+                        Box::new(Expression::CInt(1)),
+                    )),
                 )),
             )),
         );
 
-        match execute(&program, env) {
-            Ok(_) => assert!(false, "Function should generate an error"),
-            Err(s) => assert_eq!(s, "add returned a value different from specified type"),
+        let env = Environment::new();
+        match Interpreter::new()
+            .with_max_steps(1000)
+            .execute(&program, env.clone())
+        {
+            Ok(_) => assert!(false, "Expected the step budget to be exceeded"),
+            Err(s) => {
+                assert_eq!(s, InterpreterError::StepLimitExceeded);
+                assert_eq!(s.to_string(), "execution step limit exceeded");
+            }
+        }
+        match env.get("count") {
+            Some(EnvValue::CInt(n)) => assert!(0 < n && n < 1000, "count was {}", n),
+            other => assert!(false, "Expected an int, found {:?}", other),
         }
     }
 
     #[test]
-    fn undefined_func_call() {
-        let env = Environment::new();
-
-        let program = Box::new(Statement::Assignment(
-            Box::new(String::from("sum")),
-            Box::new(Expression::FuncCall(
-                String::from("add"),
-                Some(vec![Expression::CInt(1), Expression::CInt(2)]),
-            )),
-        ));
-
-        match execute(&program, env) {
-            Ok(_) => assert!(false, "Function not supposed to execute"),
-            Err(s) => assert_eq!(s, "add is not defined"),
+    fn eval_large_list_and_range_read_the_environment() {
+        /*
+         * > x = 7
+         * > [x + 0, x + 1, ..., x + 9999]
+         * > range(x, x * 3, x)      # [7, 14]
+         */
+        let env = Environment::from([(String::from("x"), EnvValue::CInt(7))]);
+        let var = || Box::new(Expression::Var(String::from("x")));
+
+        let items: Vec<Expression> = (0..10_000)
+            .map(|i| Expression::Add(var(), Box::new(Expression::CInt(i))))
+            .collect();
+        match eval(&Expression::List(items), &env) {
+            Ok(EvalResult::List(values)) => {
+                assert_eq!(values.len(), 10_000);
+                assert!(values
+                    .iter()
+                    .enumerate()
+                    .all(|(i, value)| *value == EvalResult::CInt(7 + i as i32)));
+            }
+            Ok(value) => assert!(false, "Expected a list, got {:?}", value),
+            Err(s) => assert!(false, "{}", s),
         }
+
+        assert_eq!(
+            eval(
+                &Expression::Range(
+                    Some(var()),
+                    Box::new(Expression::Mul(var(), Box::new(Expression::CInt(3)))),
+                    Some(var()),
+                ),
+                &env,
+            ),
+            Ok(EvalResult::List(vec![
+                EvalResult::CInt(7),
+                EvalResult::CInt(14)
+            ]))
+        );
+        assert_eq!(env.get("x"), Some(EnvValue::CInt(7)));
     }
 }
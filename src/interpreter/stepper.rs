@@ -0,0 +1,154 @@
+use crate::interpreter::interpreter::{Environment, Interpreter, InterpreterError};
+use crate::ir::ast::Statement;
+
+/* Runs a program one top-level statement at a time, for tools that want
+ * to look at the environment in between, like a debugger.
+ *
+ * Sequences are flattened as they are reached, however they nest, so
+ * every step runs exactly one statement that is not a sequence itself.
+ * Each step hands back a snapshot of the environment, which later steps
+ * leave untouched. Stepping stops for good after the first error. */
+pub struct Stepper<'a> {
+    interpreter: Interpreter,
+    pending: Vec<&'a Statement>,
+    env: Option<Environment>,
+}
+
+impl<'a> Stepper<'a> {
+    pub fn new(stmt: &'a Statement, env: Environment) -> Stepper<'a> {
+        Stepper::with_interpreter(Interpreter::new(), stmt, env)
+    }
+
+    /* Steps with the given interpreter, so its budgets cover the whole
+     * run rather than a single step. */
+    pub fn with_interpreter(
+        interpreter: Interpreter,
+        stmt: &'a Statement,
+        env: Environment,
+    ) -> Stepper<'a> {
+        Stepper {
+            interpreter,
+            pending: vec![stmt],
+            env: Some(env),
+        }
+    }
+
+    /* Runs the next statement and returns the environment it leaves, or
+     * None once the program is done or has failed. */
+    pub fn next_step(&mut self) -> Option<Result<Environment, InterpreterError>> {
+        let env = self.env.take()?;
+        let stmt = self.next_statement()?;
+        match self.interpreter.execute(stmt, env) {
+            Ok(env) => {
+                let snapshot = env.snapshot();
+                self.env = Some(env);
+                Some(Ok(snapshot))
+            }
+            Err(err) => Some(Err(err)),
+        }
+    }
+
+    fn next_statement(&mut self) -> Option<&'a Statement> {
+        let mut stmt = self.pending.pop()?;
+        loop {
+            match stmt {
+                Statement::Spanned(_, inner) if matches!(**inner, Statement::Sequence(_, _)) => {
+                    stmt = inner
+                }
+                Statement::Sequence(first, rest) => {
+                    self.pending.push(rest);
+                    stmt = first;
+                }
+                _ => return Some(stmt),
+            }
+        }
+    }
+}
+
+impl Iterator for Stepper<'_> {
+    type Item = Result<Environment, InterpreterError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_step()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
+mod tests {
+    use super::*;
+    use crate::interpreter::interpreter::EnvValue;
+    use crate::ir::ast::Expression;
+
+    fn assign(name: &str, exp: Expression) -> Statement {
+        Statement::Assignment(Box::new(String::from(name)), Box::new(exp))
+    }
+
+    #[test]
+    fn stepper_yields_every_assignment() {
+        /*
+         * > x = 1
+         * > y = x + 1
+         * > x = y * 10
+         */
+        let program = Statement::Sequence(
+            Box::new(Statement::Sequence(
+                Box::new(assign("x", Expression::CInt(1))),
+                Box::new(assign(
+                    "y",
+                    Expression::Add(
+                        Box::new(Expression::Var(String::from("x"))),
+                        Box::new(Expression::CInt(1)),
+                    ),
+                )),
+            )),
+            Box::new(assign(
+                "x",
+                Expression::Mul(
+                    Box::new(Expression::Var(String::from("y"))),
+                    Box::new(Expression::CInt(10)),
+                ),
+            )),
+        );
+
+        let steps: Result<Vec<Environment>, _> =
+            Stepper::new(&program, Environment::new()).collect();
+        match steps {
+            Ok(steps) => {
+                assert_eq!(steps.len(), 3);
+                let get = |step: usize, name: &str| steps[step].get(name);
+                assert_eq!(get(0, "x"), Some(EnvValue::CInt(1)));
+                assert_eq!(get(0, "y"), None);
+                assert_eq!(get(1, "x"), Some(EnvValue::CInt(1)));
+                assert_eq!(get(1, "y"), Some(EnvValue::CInt(2)));
+                assert_eq!(get(2, "x"), Some(EnvValue::CInt(20)));
+                assert_eq!(get(2, "y"), Some(EnvValue::CInt(2)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn stepper_stops_after_an_error() {
+        /*
+         * > x = 1
+         * > y = z
+         * > x = 2
+         */
+        let program = Statement::Sequence(
+            Box::new(assign("x", Expression::CInt(1))),
+            Box::new(Statement::Sequence(
+                Box::new(assign("y", Expression::Var(String::from("z")))),
+                Box::new(assign("x", Expression::CInt(2))),
+            )),
+        );
+
+        let mut stepper = Stepper::new(&program, Environment::new());
+        assert!(matches!(stepper.next_step(), Some(Ok(_))));
+        assert_eq!(
+            stepper.next_step(),
+            Some(Err(InterpreterError::UndefinedVariable(String::from("z"))))
+        );
+        assert_eq!(stepper.next_step(), None);
+    }
+}
@@ -1 +1,4 @@
 pub mod ast;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod printer;
@@ -1,9 +1,18 @@
-use std::collections::HashMap;
-
-use crate::interpreter::interpreter::EvalResult;
+use std::fmt;
 
 pub type Name = String;
 
+/* Where a node starts in the source text, counting lines and columns
+ * from 1. */
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Type {
     TInteger,
     TBool,
@@ -11,42 +20,204 @@ pub enum Type {
     TString,
     TList(Box<Type>),
     TTuple(Vec<Type>),
+    TFunction,
+    TNone,
+    TOptional(Box<Type>),
+    TAny,
+}
+
+/* Types are written with the same names the interpreter uses for the
+ * values that inhabit them. */
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::TInteger => write!(f, "int"),
+            Type::TBool => write!(f, "bool"),
+            Type::TReal => write!(f, "real"),
+            Type::TString => write!(f, "str"),
+            Type::TList(item) => write!(f, "list[{}]", item),
+            Type::TTuple(items) => {
+                let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+                write!(f, "tuple[{}]", items.join(", "))
+            }
+            Type::TFunction => write!(f, "function"),
+            Type::TNone => write!(f, "None"),
+            Type::TOptional(kind) => write!(f, "Optional[{}]", kind),
+            Type::TAny => write!(f, "Any"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expression {
     CInt(i32),
     CReal(f32),
     Bool(bool),
+    CString(String),
+    FString(Vec<Expression>),
     None,
     Var(String),
     Add(Box<Expression>, Box<Expression>),
     Sub(Box<Expression>, Box<Expression>),
     Mul(Box<Expression>, Box<Expression>),
     Div(Box<Expression>, Box<Expression>),
+    FloorDiv(Box<Expression>, Box<Expression>),
+    Mod(Box<Expression>, Box<Expression>),
+    Pow(Box<Expression>, Box<Expression>),
+    /* Unary minus. Unlike '0 - x' it keeps the sign of a zero, so -x is
+     * -0.0 when x is 0.0. */
+    Neg(Box<Expression>),
+    BitAnd(Box<Expression>, Box<Expression>),
+    BitOr(Box<Expression>, Box<Expression>),
+    BitXor(Box<Expression>, Box<Expression>),
+    Shl(Box<Expression>, Box<Expression>),
+    Shr(Box<Expression>, Box<Expression>),
+    Eq(Box<Expression>, Box<Expression>),
+    NotEq(Box<Expression>, Box<Expression>),
+    Lt(Box<Expression>, Box<Expression>),
+    LtE(Box<Expression>, Box<Expression>),
+    Gt(Box<Expression>, Box<Expression>),
+    GtE(Box<Expression>, Box<Expression>),
+    Is(Box<Expression>, Box<Expression>),
+    IsNot(Box<Expression>, Box<Expression>),
+    In(Box<Expression>, Box<Expression>),
+    NotIn(Box<Expression>, Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
     FuncCall(String, Option<Vec<Expression>>),
+    Call(Box<Expression>, Option<Vec<Expression>>),
     List(Vec<Expression>),
+    Tuple(Vec<Expression>),
+    Dict(Vec<(Expression, Expression)>),
+    Set(Vec<Expression>),
+    Index(Box<Expression>, Box<Expression>),
+    ListComp {
+        element: Box<Expression>,
+        var: Name,
+        iterable: Box<Expression>,
+        condition: Option<Box<Expression>>,
+    },
+    /* Meant to produce its items lazily; for now it is evaluated
+     * eagerly into a list, exactly like ListComp. */
+    GenExp {
+        element: Box<Expression>,
+        var: Name,
+        iterable: Box<Expression>,
+        condition: Option<Box<Expression>>,
+    },
     Range(
         Option<Box<Expression>>,
         Box<Expression>,
         Option<Box<Expression>>,
     ),
+    /* Lambda(params, body): an anonymous function of untyped parameters
+     * that returns the value of its body. */
+    Lambda(Vec<Name>, Box<Expression>),
+    /* Like Range, but the end is included when the steps reach it. */
+    RangeInclusive(
+        Option<Box<Expression>>,
+        Box<Expression>,
+        Option<Box<Expression>>,
+    ),
+    /* Compare(first, [(op, operand), ...]): a chain like 'a < b < c',
+     * which holds when every link does. Each operand is evaluated at most
+     * once, and the chain stops at the first link that fails. The parser
+     * builds it only for two or more operators; a single comparison keeps
+     * its own node. */
+    Compare(Box<Expression>, Vec<(CmpOp, Expression)>),
+    Spanned(Span, Box<Expression>),
+}
+
+/* The operators that can be chained in a comparison. */
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CmpOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtE,
+    Gt,
+    GtE,
+    Is,
+    IsNot,
+    In,
+    NotIn,
+}
+
+impl CmpOp {
+    /* The operator as written in the source. */
+    pub fn symbol(self) -> &'static str {
+        match self {
+            CmpOp::Eq => "==",
+            CmpOp::NotEq => "!=",
+            CmpOp::Lt => "<",
+            CmpOp::LtE => "<=",
+            CmpOp::Gt => ">",
+            CmpOp::GtE => ">=",
+            CmpOp::Is => "is",
+            CmpOp::IsNot => "is not",
+            CmpOp::In => "in",
+            CmpOp::NotIn => "not in",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Statement {
     VarDeclaration(Box<Name>),
     ValDeclaration(Box<Name>),
     Assignment(Box<Name>, Box<Expression>),
+    MultiAssign(Vec<Name>, Vec<Expression>),
+    IndexAssign(Box<Expression>, Box<Expression>, Box<Expression>),
     IfThenElse(Box<Expression>, Box<Statement>, Box<Statement>),
+    If(Box<Expression>, Box<Statement>),
     While(Box<Expression>, Box<Statement>),
+    WhileElse(Box<Expression>, Box<Statement>, Box<Statement>),
     For(Box<Name>, Box<Expression>, Box<Statement>),
+    ForElse(Box<Name>, Box<Expression>, Box<Statement>, Box<Statement>),
+    ForUnpack(Vec<Name>, Box<Expression>, Box<Statement>),
     Sequence(Box<Statement>, Box<Statement>),
+    /* Func(name, return type, parameters, body, trailing return expression).
+     * Parameters keep their declaration order, each paired with its
+     * declared type; None stands for a function without parameters. */
     Func(
         Box<Name>,
-        Box<EvalResult>,
-        Option<HashMap<Name, Box<EvalResult>>>,
+        Box<Type>,
+        Option<Vec<(Name, Box<Type>)>>,
         Option<Box<Statement>>,
         Box<Expression>,
     ),
+    Return(Box<Expression>),
+    Nonlocal(Vec<Name>),
+    Global(Vec<Name>),
+    /* Assert(condition, message): fails when the condition is falsy. */
+    Assert(Box<Expression>, Option<Box<Expression>>),
+    /* Expr(exp): an expression evaluated for its effects alone. */
+    Expr(Box<Expression>),
+    Pass,
+    Break,
+    Continue,
+    Spanned(Span, Box<Statement>),
+}
+
+/* Nodes built by hand carry no position; the parser attaches one with
+ * these helpers when it knows where the node came from. */
+impl Expression {
+    pub fn spanned(self, span: Option<Span>) -> Expression {
+        match span {
+            Some(span) => Expression::Spanned(span, Box::new(self)),
+            None => self,
+        }
+    }
+}
+
+impl Statement {
+    pub fn spanned(self, span: Option<Span>) -> Statement {
+        match span {
+            Some(span) => Statement::Spanned(span, Box::new(self)),
+            None => self,
+        }
+    }
 }
@@ -0,0 +1,66 @@
+use crate::ir::ast::Statement;
+
+/* Programs written as JSON, for tools that want a parsed program without
+ * linking the parser. Every node keeps its variant name, spans included,
+ * so reading the text back gives the same tree.
+ *
+ * JSON has no NaN or infinity, and serde_json writes them as null, so
+ * a program holding such a real constant does not come back. The parser
+ * never produces one. */
+pub fn to_json(stmt: &Statement) -> String {
+    /* The tree holds no maps, whose keys could fail to be strings, so
+     * writing it out always succeeds. */
+    serde_json::to_string(stmt).expect("the AST always serializes")
+}
+
+pub fn from_json(s: &str) -> Result<Statement, String> {
+    serde_json::from_str(s).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
+mod tests {
+    use super::*;
+    use crate::ir::ast::{Expression, Type};
+    use crate::parser::parser::parse;
+
+    #[test]
+    fn json_round_trip_summation() {
+        let source = "
+def summation(n: int) -> int:
+    total = 0
+    i = 1
+    while i <= n:
+        total = total + i
+        i = i + 1
+    return total
+
+x = summation(10) * 0.5
+";
+        let program = parse(source).unwrap();
+        let text = to_json(&program);
+
+        assert!(text.contains("\"Func\""));
+        assert_eq!(from_json(&text), Ok(program));
+    }
+
+    #[test]
+    fn json_keeps_every_type() {
+        let program = Statement::Func(
+            Box::new(String::from("f")),
+            Box::new(Type::TOptional(Box::new(Type::TTuple(vec![
+                Type::TList(Box::new(Type::TReal)),
+                Type::TAny,
+            ])))),
+            None,
+            None,
+            Box::new(Expression::Lambda(
+                vec![String::from("x")],
+                Box::new(Expression::CReal(-0.25)),
+            )),
+        );
+
+        assert_eq!(from_json(&to_json(&program)), Ok(program));
+        assert!(from_json("{\"Pass\": 1}").is_err());
+    }
+}
@@ -0,0 +1,528 @@
+use crate::ir::ast::Expression;
+use crate::ir::ast::Name;
+use crate::ir::ast::Statement;
+use crate::ir::ast::Type;
+
+/* Turns a program back into source text the parser accepts, with blocks
+ * indented by four spaces and every statement on a line of its own.
+ * Spans are dropped, so parsing the result gives back the same tree
+ * without them. Declarations have no syntax of their own and are written
+ * as 'var x' and 'val x'. */
+pub fn unparse(stmt: &Statement) -> String {
+    let mut out = String::new();
+    statement(stmt, 0, &mut out);
+    out
+}
+
+/* Writes an expression with only the parentheses its precedence needs,
+ * so 'Mul(Add(1, 2), 3)' comes out as '(1 + 2) * 3'. */
+pub fn unparse_expression(exp: &Expression) -> String {
+    match exp {
+        Expression::Spanned(_, exp) => unparse_expression(exp),
+        Expression::CInt(v) => v.to_string(),
+        Expression::CReal(v) => real(*v),
+        Expression::Bool(true) => String::from("True"),
+        Expression::Bool(false) => String::from("False"),
+        Expression::CString(v) => format!("\"{}\"", escape(v)),
+        /* The lexer does not read f-strings yet; they are written the way
+         * Python spells them. */
+        Expression::FString(parts) => {
+            let parts: String = parts
+                .iter()
+                .map(|part| match part {
+                    Expression::CString(v) => escape(v).replace('{', "{{").replace('}', "}}"),
+                    part => format!("{{{}}}", unparse_expression(part)),
+                })
+                .collect();
+            format!("f\"{}\"", parts)
+        }
+        Expression::None => String::from("None"),
+        Expression::Var(name) => name.clone(),
+        Expression::Or(lhs, rhs) => binary(lhs, "or", rhs, 1),
+        Expression::And(lhs, rhs) => binary(lhs, "and", rhs, 2),
+        Expression::Eq(lhs, rhs) => comparison(lhs, "==", rhs),
+        Expression::NotEq(lhs, rhs) => comparison(lhs, "!=", rhs),
+        Expression::Lt(lhs, rhs) => comparison(lhs, "<", rhs),
+        Expression::LtE(lhs, rhs) => comparison(lhs, "<=", rhs),
+        Expression::Gt(lhs, rhs) => comparison(lhs, ">", rhs),
+        Expression::GtE(lhs, rhs) => comparison(lhs, ">=", rhs),
+        Expression::Is(lhs, rhs) => comparison(lhs, "is", rhs),
+        Expression::IsNot(lhs, rhs) => comparison(lhs, "is not", rhs),
+        Expression::In(lhs, rhs) => comparison(lhs, "in", rhs),
+        Expression::NotIn(lhs, rhs) => comparison(lhs, "not in", rhs),
+        Expression::Compare(first, links) => {
+            let mut text = operand(first, 4);
+            for (op, rhs) in links {
+                text.push_str(&format!(" {} {}", op.symbol(), operand(rhs, 4)));
+            }
+            text
+        }
+        Expression::BitOr(lhs, rhs) => binary(lhs, "|", rhs, 4),
+        Expression::BitXor(lhs, rhs) => binary(lhs, "^", rhs, 5),
+        Expression::BitAnd(lhs, rhs) => binary(lhs, "&", rhs, 6),
+        Expression::Shl(lhs, rhs) => binary(lhs, "<<", rhs, 7),
+        Expression::Shr(lhs, rhs) => binary(lhs, ">>", rhs, 7),
+        Expression::Add(lhs, rhs) => binary(lhs, "+", rhs, 8),
+        Expression::Sub(lhs, rhs) => binary(lhs, "-", rhs, 8),
+        Expression::Mul(lhs, rhs) => binary(lhs, "*", rhs, 9),
+        Expression::Div(lhs, rhs) => binary(lhs, "/", rhs, 9),
+        Expression::FloorDiv(lhs, rhs) => binary(lhs, "//", rhs, 9),
+        Expression::Mod(lhs, rhs) => binary(lhs, "%", rhs, 9),
+        Expression::Neg(exp) => format!("-{}", operand(exp, 10)),
+        /* '**' groups to the right and its exponent may be negated. */
+        Expression::Pow(lhs, rhs) => format!("{} ** {}", operand(lhs, 12), operand(rhs, 10)),
+        Expression::FuncCall(name, args) => format!("{}({})", name, arguments(args)),
+        Expression::Call(callee, args) => {
+            format!("{}({})", operand(callee, 12), arguments(args))
+        }
+        Expression::Lambda(params, body) if params.is_empty() => {
+            format!("lambda: {}", unparse_expression(body))
+        }
+        Expression::Lambda(params, body) => {
+            format!("lambda {}: {}", params.join(", "), unparse_expression(body))
+        }
+        Expression::Index(target, index) => {
+            format!("{}[{}]", operand(target, 12), unparse_expression(index))
+        }
+        Expression::List(items) => format!("[{}]", items_list(items)),
+        Expression::Tuple(items) => match items.as_slice() {
+            [item] => format!("({},)", unparse_expression(item)),
+            _ => format!("({})", items_list(items)),
+        },
+        Expression::Dict(entries) => {
+            let entries: Vec<String> = entries
+                .iter()
+                .map(|(key, value)| {
+                    format!("{}: {}", unparse_expression(key), unparse_expression(value))
+                })
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+        /* '{}' is an empty dict, so the empty set is spelled out. */
+        Expression::Set(items) if items.is_empty() => String::from("set()"),
+        Expression::Set(items) => format!("{{{}}}", items_list(items)),
+        Expression::ListComp {
+            element,
+            var,
+            iterable,
+            condition,
+        } => format!(
+            "[{}]",
+            comprehension(element, var, iterable, condition.as_deref())
+        ),
+        Expression::GenExp {
+            element,
+            var,
+            iterable,
+            condition,
+        } => format!(
+            "({})",
+            comprehension(element, var, iterable, condition.as_deref())
+        ),
+        /* The parser has no form for a step without a start, so the
+         * default start is written out. */
+        Expression::Range(start, end, step) | Expression::RangeInclusive(start, end, step) => {
+            let name = match exp {
+                Expression::Range(..) => "range",
+                _ => "range_inclusive",
+            };
+            match (start, step) {
+                (None, None) => format!("{}({})", name, unparse_expression(end)),
+                (Some(start), None) => format!(
+                    "{}({}, {})",
+                    name,
+                    unparse_expression(start),
+                    unparse_expression(end)
+                ),
+                (start, Some(step)) => format!(
+                    "{}({}, {}, {})",
+                    name,
+                    start
+                        .as_deref()
+                        .map_or(String::from("0"), unparse_expression),
+                    unparse_expression(end),
+                    unparse_expression(step)
+                ),
+            }
+        }
+    }
+}
+
+/* How tightly an expression binds, from 'or' (1) up to the primaries and
+ * postfix forms (12). Negative literals sit at the level of unary minus. */
+fn precedence(exp: &Expression) -> u8 {
+    match exp {
+        Expression::Spanned(_, exp) => precedence(exp),
+        Expression::Lambda(_, _) => 0,
+        Expression::Or(_, _) => 1,
+        Expression::And(_, _) => 2,
+        Expression::Eq(_, _)
+        | Expression::NotEq(_, _)
+        | Expression::Lt(_, _)
+        | Expression::LtE(_, _)
+        | Expression::Gt(_, _)
+        | Expression::GtE(_, _)
+        | Expression::Is(_, _)
+        | Expression::IsNot(_, _)
+        | Expression::In(_, _)
+        | Expression::NotIn(_, _)
+        | Expression::Compare(_, _) => 3,
+        Expression::BitOr(_, _) => 4,
+        Expression::BitXor(_, _) => 5,
+        Expression::BitAnd(_, _) => 6,
+        Expression::Shl(_, _) | Expression::Shr(_, _) => 7,
+        Expression::Add(_, _) | Expression::Sub(_, _) => 8,
+        Expression::Mul(_, _)
+        | Expression::Div(_, _)
+        | Expression::FloorDiv(_, _)
+        | Expression::Mod(_, _) => 9,
+        Expression::Neg(_) => 10,
+        Expression::CInt(v) if *v < 0 => 10,
+        Expression::CReal(v) if v.is_sign_negative() => 10,
+        Expression::Pow(_, _) => 11,
+        _ => 12,
+    }
+}
+
+/* An operand that must bind at least as tightly as 'min', parenthesized
+ * when it does not. */
+fn operand(exp: &Expression, min: u8) -> String {
+    if precedence(exp) < min {
+        format!("({})", unparse_expression(exp))
+    } else {
+        unparse_expression(exp)
+    }
+}
+
+/* Binary operators group to the left, so only the right operand needs
+ * parentheses when it binds as loosely as the operator itself. */
+fn binary(lhs: &Expression, op: &str, rhs: &Expression, level: u8) -> String {
+    format!("{} {} {}", operand(lhs, level), op, operand(rhs, level + 1))
+}
+
+/* Comparisons do not chain, so neither side may be a comparison. */
+fn comparison(lhs: &Expression, op: &str, rhs: &Expression) -> String {
+    format!("{} {} {}", operand(lhs, 4), op, operand(rhs, 4))
+}
+
+fn items_list(items: &[Expression]) -> String {
+    let items: Vec<String> = items.iter().map(unparse_expression).collect();
+    items.join(", ")
+}
+
+fn arguments(args: &Option<Vec<Expression>>) -> String {
+    args.as_deref().map_or(String::new(), items_list)
+}
+
+fn comprehension(
+    element: &Expression,
+    var: &Name,
+    iterable: &Expression,
+    condition: Option<&Expression>,
+) -> String {
+    let mut text = format!(
+        "{} for {} in {}",
+        unparse_expression(element),
+        var,
+        unparse_expression(iterable)
+    );
+    if let Some(condition) = condition {
+        text.push_str(&format!(" if {}", unparse_expression(condition)));
+    }
+    text
+}
+
+/* Reals always keep a decimal point, and never use an exponent, which
+ * the lexer does not read. */
+fn real(v: f32) -> String {
+    let text = v.to_string();
+    if text.contains('.') {
+        text
+    } else {
+        format!("{}.0", text)
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/* Types are written with the names the parser reads in annotations. */
+fn annotation(kind: &Type) -> String {
+    match kind {
+        Type::TInteger => String::from("int"),
+        Type::TBool => String::from("bool"),
+        Type::TReal => String::from("float"),
+        Type::TString => String::from("str"),
+        Type::TList(item) if **item == Type::TAny => String::from("list"),
+        Type::TList(item) => format!("list[{}]", annotation(item)),
+        Type::TTuple(items) => {
+            let items: Vec<String> = items.iter().map(annotation).collect();
+            format!("tuple[{}]", items.join(", "))
+        }
+        Type::TFunction => String::from("Callable"),
+        Type::TNone => String::from("None"),
+        Type::TOptional(kind) => format!("Optional[{}]", annotation(kind)),
+        Type::TAny => String::from("Any"),
+    }
+}
+
+fn line(depth: usize, text: &str, out: &mut String) {
+    out.push_str(&"    ".repeat(depth));
+    out.push_str(text);
+    out.push('\n');
+}
+
+/* A header line ending in ':' followed by its indented block. */
+fn block(depth: usize, header: &str, body: &Statement, out: &mut String) {
+    line(depth, &format!("{}:", header), out);
+    statement(body, depth + 1, out);
+}
+
+fn statement(stmt: &Statement, depth: usize, out: &mut String) {
+    match stmt {
+        Statement::Spanned(_, stmt) => statement(stmt, depth, out),
+        Statement::Sequence(first, rest) => {
+            statement(first, depth, out);
+            statement(rest, depth, out);
+        }
+        Statement::VarDeclaration(name) => line(depth, &format!("var {}", name), out),
+        Statement::ValDeclaration(name) => line(depth, &format!("val {}", name), out),
+        Statement::Assignment(name, exp) => line(
+            depth,
+            &format!("{} = {}", name, unparse_expression(exp)),
+            out,
+        ),
+        Statement::MultiAssign(names, exps) => line(
+            depth,
+            &format!("{} = {}", names.join(", "), items_list(exps)),
+            out,
+        ),
+        Statement::IndexAssign(target, index, exp) => line(
+            depth,
+            &format!(
+                "{}[{}] = {}",
+                operand(target, 12),
+                unparse_expression(index),
+                unparse_expression(exp)
+            ),
+            out,
+        ),
+        Statement::If(cond, stmt_then) => block(
+            depth,
+            &format!("if {}", unparse_expression(cond)),
+            stmt_then,
+            out,
+        ),
+        Statement::IfThenElse(cond, stmt_then, stmt_else) => {
+            block(
+                depth,
+                &format!("if {}", unparse_expression(cond)),
+                stmt_then,
+                out,
+            );
+            block(depth, "else", stmt_else, out);
+        }
+        Statement::While(cond, stmt) => block(
+            depth,
+            &format!("while {}", unparse_expression(cond)),
+            stmt,
+            out,
+        ),
+        Statement::WhileElse(cond, stmt, stmt_else) => {
+            block(
+                depth,
+                &format!("while {}", unparse_expression(cond)),
+                stmt,
+                out,
+            );
+            block(depth, "else", stmt_else, out);
+        }
+        Statement::For(var, exp, stmt) => block(
+            depth,
+            &format!("for {} in {}", var, unparse_expression(exp)),
+            stmt,
+            out,
+        ),
+        Statement::ForElse(var, exp, stmt, stmt_else) => {
+            block(
+                depth,
+                &format!("for {} in {}", var, unparse_expression(exp)),
+                stmt,
+                out,
+            );
+            block(depth, "else", stmt_else, out);
+        }
+        Statement::ForUnpack(vars, exp, stmt) => block(
+            depth,
+            &format!("for {} in {}", vars.join(", "), unparse_expression(exp)),
+            stmt,
+            out,
+        ),
+        Statement::Func(name, kind, params, body, retrn) => {
+            let params: Vec<String> = params
+                .iter()
+                .flatten()
+                .map(|(param, kind)| format!("{}: {}", param, annotation(kind)))
+                .collect();
+            let mut header = format!("def {}({})", name, params.join(", "));
+            if **kind != Type::TNone {
+                header.push_str(&format!(" -> {}", annotation(kind)));
+            }
+            line(depth, &format!("{}:", header), out);
+
+            /* The trailing return expression is only written when it says
+             * something, or when the function has no body at all. */
+            let retrn = match retrn.as_ref() {
+                Expression::Spanned(_, exp) => exp,
+                exp => exp,
+            };
+            if let Some(body) = body {
+                statement(body, depth + 1, out);
+            }
+            if body.is_none() || *retrn != Expression::None {
+                line(
+                    depth + 1,
+                    &format!("return {}", unparse_expression(retrn)),
+                    out,
+                );
+            }
+        }
+        Statement::Return(exp) => line(depth, &format!("return {}", unparse_expression(exp)), out),
+        Statement::Nonlocal(names) => line(depth, &format!("nonlocal {}", names.join(", ")), out),
+        Statement::Global(names) => line(depth, &format!("global {}", names.join(", ")), out),
+        Statement::Assert(cond, None) => {
+            line(depth, &format!("assert {}", unparse_expression(cond)), out)
+        }
+        Statement::Assert(cond, Some(message)) => line(
+            depth,
+            &format!(
+                "assert {}, {}",
+                unparse_expression(cond),
+                unparse_expression(message)
+            ),
+            out,
+        ),
+        Statement::Expr(exp) => line(depth, &unparse_expression(exp), out),
+        Statement::Pass => line(depth, "pass", out),
+        Statement::Break => line(depth, "break", out),
+        Statement::Continue => line(depth, "continue", out),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
+mod tests {
+    use super::*;
+    use crate::interpreter::interpreter::{execute, EnvValue, Environment};
+    use crate::parser::lexer::tokenize;
+    use crate::parser::parser::{parse, parse_program};
+
+    #[test]
+    fn unparse_summation_round_trip() {
+        let source = "
+x = 10
+y = 0
+while x:
+  y = y + x
+  x = x - 1
+";
+        let program = parse_program(&tokenize(source).unwrap()).unwrap();
+        let text = unparse(&program);
+
+        assert_eq!(
+            text,
+            "x = 10\ny = 0\nwhile x:\n    y = y + x\n    x = x - 1\n"
+        );
+        assert_eq!(parse_program(&tokenize(&text).unwrap()), Ok(program));
+    }
+
+    #[test]
+    fn unparse_minimal_parentheses() {
+        let cases = [
+            "1 + 2 * 3",
+            "(1 + 2) * 3",
+            "1 - (2 - 3)",
+            "1 - 2 - 3",
+            "2 ** 3 ** 2",
+            "(2 ** 3) ** 2",
+            "(-2) ** 2",
+            "2 ** -1",
+            "0 - 2 ** 2",
+            "-2 ** 2",
+            "-(a + b) * -x",
+            "--x",
+            "a or b and c",
+            "(a or b) and c",
+            "x & 1 == 0",
+            "(x == 1) == True",
+            "0 <= i < n != m",
+            "a < (b < c) in xs",
+            "a is not None",
+            "x in [1, 2] or \"b\" not in s",
+            "1 << 2 + 3",
+            "-7 % 3 * (a % (b // 2))",
+            "f(x)[0](y)",
+            "[i * i for i in range(1, 10, 2) if i > 3]",
+            "sum((i for i in xs))",
+            "range_inclusive(0, n, 2)",
+            "map(lambda x, y: x * y, (lambda: xs)())",
+            "{\"a\": (1,), \"b\": set(), \"c\": {1, 2}}",
+            "\"say \\\"hi\\\"\\n\"",
+            "1.5 + 3.0 - -0.25",
+        ];
+        for source in cases {
+            let exp = crate::parser::parser::parse_expression(source).unwrap();
+            assert_eq!(unparse_expression(&exp), source);
+        }
+    }
+
+    #[test]
+    fn unparse_blocks_round_trip() {
+        let source = "
+def first_even(xs: list[int]) -> Optional[int]:
+    for x in xs:
+        if x & 1 == 0:
+            return x
+    else:
+        return None
+
+def count(n: int) -> int:
+    total = 0
+    i = 0
+    while i < n:
+        i = i + 1
+        if i == 3:
+            continue
+        else:
+            total = total + i
+    return total
+
+pairs = {}
+for k, v in zip([\"a\", \"b\"], [1, 2]):
+    pairs[k] = v
+a, b = first_even([1, 3, 4]), count(5)
+";
+        let text = unparse(&parse(source).unwrap());
+        let program = parse(&text).unwrap();
+
+        assert_eq!(unparse(&program), text);
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("a"), Some(EnvValue::CInt(4)));
+                assert_eq!(new_env.get("b"), Some(EnvValue::CInt(12)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod interpreter;
+pub mod ir;
+pub mod parser;
+pub mod repl;
+pub mod typechecker;
@@ -2,9 +2,6 @@
 //use crate::ir::ast::Statement;
 //use crate::interpreter::interpreter::eval;
 
-pub mod interpreter;
-pub mod ir;
-
 fn main() {
     println!("Hello, world!");
 }
@@ -0,0 +1,3 @@
+pub mod lexer;
+#[allow(clippy::module_inception)]
+pub mod parser;
@@ -0,0 +1,454 @@
+use std::fmt;
+
+use crate::ir::ast::Name;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Int(i32),
+    Real(f32),
+    StringLit(String),
+    Ident(Name),
+    If,
+    Else,
+    While,
+    For,
+    In,
+    Is,
+    Not,
+    And,
+    Or,
+    Def,
+    Return,
+    Break,
+    Continue,
+    Nonlocal,
+    Global,
+    Assert,
+    Pass,
+    Lambda,
+    True,
+    False,
+    None,
+    Plus,
+    Minus,
+    Star,
+    DoubleStar,
+    Slash,
+    DoubleSlash,
+    Percent,
+    Assign,
+    Ampersand,
+    Pipe,
+    Caret,
+    ShiftLeft,
+    ShiftRight,
+    Eq,
+    NotEq,
+    Lt,
+    LtE,
+    Gt,
+    GtE,
+    Arrow,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Comma,
+    Colon,
+    Newline,
+    Indent,
+    Dedent,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Int(v) => write!(f, "{}", v),
+            Token::Real(v) => write!(f, "{:?}", v),
+            Token::StringLit(v) => write!(f, "{:?}", v),
+            Token::Ident(name) => write!(f, "{}", name),
+            Token::If => write!(f, "if"),
+            Token::Else => write!(f, "else"),
+            Token::While => write!(f, "while"),
+            Token::For => write!(f, "for"),
+            Token::In => write!(f, "in"),
+            Token::Is => write!(f, "is"),
+            Token::Not => write!(f, "not"),
+            Token::And => write!(f, "and"),
+            Token::Or => write!(f, "or"),
+            Token::Def => write!(f, "def"),
+            Token::Return => write!(f, "return"),
+            Token::Break => write!(f, "break"),
+            Token::Continue => write!(f, "continue"),
+            Token::Nonlocal => write!(f, "nonlocal"),
+            Token::Global => write!(f, "global"),
+            Token::Assert => write!(f, "assert"),
+            Token::Pass => write!(f, "pass"),
+            Token::Lambda => write!(f, "lambda"),
+            Token::True => write!(f, "True"),
+            Token::False => write!(f, "False"),
+            Token::None => write!(f, "None"),
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Star => write!(f, "*"),
+            Token::DoubleStar => write!(f, "**"),
+            Token::Slash => write!(f, "/"),
+            Token::Percent => write!(f, "%"),
+            Token::DoubleSlash => write!(f, "//"),
+            Token::Assign => write!(f, "="),
+            Token::Ampersand => write!(f, "&"),
+            Token::Pipe => write!(f, "|"),
+            Token::Caret => write!(f, "^"),
+            Token::ShiftLeft => write!(f, "<<"),
+            Token::ShiftRight => write!(f, ">>"),
+            Token::Eq => write!(f, "=="),
+            Token::NotEq => write!(f, "!="),
+            Token::Lt => write!(f, "<"),
+            Token::LtE => write!(f, "<="),
+            Token::Gt => write!(f, ">"),
+            Token::GtE => write!(f, ">="),
+            Token::Arrow => write!(f, "->"),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::LBracket => write!(f, "["),
+            Token::RBracket => write!(f, "]"),
+            Token::LBrace => write!(f, "{{"),
+            Token::RBrace => write!(f, "}}"),
+            Token::Comma => write!(f, ","),
+            Token::Colon => write!(f, ":"),
+            Token::Newline => write!(f, "newline"),
+            Token::Indent => write!(f, "indent"),
+            Token::Dedent => write!(f, "dedent"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let tokens = tokenize_with_positions(input)?;
+    Ok(tokens.into_iter().map(|(token, _)| token).collect())
+}
+
+/* Splits the source into tokens, each paired with the line and column
+ * (both starting at 1) where it begins.
+ *
+ * Blocks follow Python's layout rules: every logical line ends with a
+ * Newline, a deeper indentation opens a block with Indent and returning
+ * to an outer level closes blocks with one Dedent each. Blank lines,
+ * comments and line breaks inside brackets do not affect the layout. */
+pub fn tokenize_with_positions(input: &str) -> Result<Vec<(Token, Position)>, String> {
+    let mut tokens: Vec<(Token, Position)> = Vec::new();
+    let mut indents: Vec<usize> = vec![0];
+    let mut nesting = 0;
+
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let chars: Vec<char> = line.chars().collect();
+
+        let mut width = 0;
+        let mut pos = 0;
+        while pos < chars.len() && (chars[pos] == ' ' || chars[pos] == '\t') {
+            width += if chars[pos] == '\t' { 4 } else { 1 };
+            pos += 1;
+        }
+
+        if pos == chars.len() || chars[pos] == '#' {
+            continue;
+        }
+
+        if nesting == 0 {
+            let current = *indents.last().unwrap_or(&0);
+            let position = Position {
+                line: line_number,
+                column: pos + 1,
+            };
+            if width > current {
+                indents.push(width);
+                tokens.push((Token::Indent, position));
+            } else {
+                while width < *indents.last().unwrap_or(&0) {
+                    indents.pop();
+                    tokens.push((Token::Dedent, position));
+                }
+                if width != *indents.last().unwrap_or(&0) {
+                    return Err(format!(
+                        "Unindent does not match any outer indentation level at line {}",
+                        line_number
+                    ));
+                }
+            }
+        }
+
+        while pos < chars.len() {
+            let c = chars[pos];
+            let position = Position {
+                line: line_number,
+                column: pos + 1,
+            };
+
+            if c == ' ' || c == '\t' {
+                pos += 1;
+                continue;
+            }
+            if c == '#' {
+                break;
+            }
+
+            if c.is_ascii_digit() {
+                let start = pos;
+                while pos < chars.len() && chars[pos].is_ascii_digit() {
+                    pos += 1;
+                }
+                let is_real = pos < chars.len() && chars[pos] == '.';
+                if is_real {
+                    pos += 1;
+                    while pos < chars.len() && chars[pos].is_ascii_digit() {
+                        pos += 1;
+                    }
+                }
+                let text: String = chars[start..pos].iter().collect();
+                let token = if is_real {
+                    text.parse::<f32>().map(Token::Real).ok()
+                } else {
+                    text.parse::<i32>().map(Token::Int).ok()
+                };
+                match token {
+                    Some(token) => tokens.push((token, position)),
+                    None => {
+                        return Err(format!(
+                            "Invalid number literal '{}' at line {}, column {}",
+                            text, position.line, position.column
+                        ))
+                    }
+                }
+                continue;
+            }
+
+            if c == '"' {
+                pos += 1;
+                let mut text = String::new();
+                loop {
+                    match (chars.get(pos), chars.get(pos + 1)) {
+                        (None, _) => {
+                            return Err(format!(
+                                "unterminated string literal at line {}, column {}",
+                                position.line, position.column
+                            ))
+                        }
+                        (Some('"'), _) => break,
+                        (Some('\\'), Some(escaped)) => {
+                            match escaped {
+                                'n' => text.push('\n'),
+                                't' => text.push('\t'),
+                                '\\' => text.push('\\'),
+                                '"' => text.push('"'),
+                                other => {
+                                    text.push('\\');
+                                    text.push(*other);
+                                }
+                            }
+                            pos += 2;
+                        }
+                        (Some(other), _) => {
+                            text.push(*other);
+                            pos += 1;
+                        }
+                    }
+                }
+                pos += 1;
+                tokens.push((Token::StringLit(text), position));
+                continue;
+            }
+
+            if c.is_alphabetic() || c == '_' {
+                let start = pos;
+                while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_') {
+                    pos += 1;
+                }
+                let word: String = chars[start..pos].iter().collect();
+                let token = match word.as_str() {
+                    "if" => Token::If,
+                    "else" => Token::Else,
+                    "while" => Token::While,
+                    "for" => Token::For,
+                    "in" => Token::In,
+                    "is" => Token::Is,
+                    "not" => Token::Not,
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "def" => Token::Def,
+                    "return" => Token::Return,
+                    "break" => Token::Break,
+                    "continue" => Token::Continue,
+                    "nonlocal" => Token::Nonlocal,
+                    "global" => Token::Global,
+                    "assert" => Token::Assert,
+                    "pass" => Token::Pass,
+                    "lambda" => Token::Lambda,
+                    "True" => Token::True,
+                    "False" => Token::False,
+                    "None" => Token::None,
+                    _ => Token::Ident(word),
+                };
+                tokens.push((token, position));
+                continue;
+            }
+
+            let next = chars.get(pos + 1).copied();
+            let (token, length) = match (c, next) {
+                ('/', Some('/')) => (Token::DoubleSlash, 2),
+                ('*', Some('*')) => (Token::DoubleStar, 2),
+                ('=', Some('=')) => (Token::Eq, 2),
+                ('!', Some('=')) => (Token::NotEq, 2),
+                ('<', Some('<')) => (Token::ShiftLeft, 2),
+                ('>', Some('>')) => (Token::ShiftRight, 2),
+                ('<', Some('=')) => (Token::LtE, 2),
+                ('>', Some('=')) => (Token::GtE, 2),
+                ('-', Some('>')) => (Token::Arrow, 2),
+                ('+', _) => (Token::Plus, 1),
+                ('-', _) => (Token::Minus, 1),
+                ('*', _) => (Token::Star, 1),
+                ('/', _) => (Token::Slash, 1),
+                ('%', _) => (Token::Percent, 1),
+                ('=', _) => (Token::Assign, 1),
+                ('&', _) => (Token::Ampersand, 1),
+                ('|', _) => (Token::Pipe, 1),
+                ('^', _) => (Token::Caret, 1),
+                ('<', _) => (Token::Lt, 1),
+                ('>', _) => (Token::Gt, 1),
+                ('(', _) => (Token::LParen, 1),
+                (')', _) => (Token::RParen, 1),
+                ('[', _) => (Token::LBracket, 1),
+                (']', _) => (Token::RBracket, 1),
+                ('{', _) => (Token::LBrace, 1),
+                ('}', _) => (Token::RBrace, 1),
+                (',', _) => (Token::Comma, 1),
+                (':', _) => (Token::Colon, 1),
+                _ => {
+                    return Err(format!(
+                        "Unexpected character '{}' at line {}, column {}",
+                        c, position.line, position.column
+                    ))
+                }
+            };
+            match token {
+                Token::LParen | Token::LBracket | Token::LBrace => nesting += 1,
+                Token::RParen | Token::RBracket | Token::RBrace if nesting > 0 => nesting -= 1,
+                _ => (),
+            }
+            tokens.push((token, position));
+            pos += length;
+        }
+
+        if nesting == 0 {
+            tokens.push((
+                Token::Newline,
+                Position {
+                    line: line_number,
+                    column: chars.len() + 1,
+                },
+            ));
+        }
+    }
+
+    let end = Position {
+        line: input.lines().count() + 1,
+        column: 1,
+    };
+    while indents.len() > 1 {
+        indents.pop();
+        tokens.push((Token::Dedent, end));
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize_assignment() {
+        assert_eq!(
+            tokenize("x = 10"),
+            Ok(vec![
+                Token::Ident(String::from("x")),
+                Token::Assign,
+                Token::Int(10),
+                Token::Newline,
+            ])
+        );
+    }
+
+    #[test]
+    fn tokenize_while_loop() {
+        /*
+         * > while x > 0:
+         * >     x = x - 1.5
+         * > y = x
+         */
+        let source = "while x > 0:\n    x = x - 1.5\ny = x\n";
+
+        assert_eq!(
+            tokenize(source),
+            Ok(vec![
+                Token::While,
+                Token::Ident(String::from("x")),
+                Token::Gt,
+                Token::Int(0),
+                Token::Colon,
+                Token::Newline,
+                Token::Indent,
+                Token::Ident(String::from("x")),
+                Token::Assign,
+                Token::Ident(String::from("x")),
+                Token::Minus,
+                Token::Real(1.5),
+                Token::Newline,
+                Token::Dedent,
+                Token::Ident(String::from("y")),
+                Token::Assign,
+                Token::Ident(String::from("x")),
+                Token::Newline,
+            ])
+        );
+    }
+
+    #[test]
+    fn tokenize_unexpected_character() {
+        assert_eq!(
+            tokenize("x = 1\ny = $"),
+            Err(String::from("Unexpected character '$' at line 2, column 5"))
+        );
+    }
+
+    #[test]
+    fn tokenize_string_escapes() {
+        assert_eq!(
+            tokenize(r#"s = "a\nb \"q\" \\ \t""#),
+            Ok(vec![
+                Token::Ident(String::from("s")),
+                Token::Assign,
+                Token::StringLit(String::from("a\nb \"q\" \\ \t")),
+                Token::Newline,
+            ])
+        );
+    }
+
+    #[test]
+    fn tokenize_unterminated_string() {
+        assert_eq!(
+            tokenize("x = 1\ns = \"abc\n"),
+            Err(String::from(
+                "unterminated string literal at line 2, column 5"
+            ))
+        );
+    }
+}
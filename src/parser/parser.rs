@@ -0,0 +1,2081 @@
+use crate::ir::ast::CmpOp;
+use crate::ir::ast::Expression;
+use crate::ir::ast::Name;
+use crate::ir::ast::Span;
+use crate::ir::ast::Statement;
+use crate::ir::ast::Type;
+use crate::parser::lexer::{tokenize_with_positions, Position, Token};
+
+/* Builds a program from a token stream. Plain tokens carry no source
+ * positions, so syntax errors point at the offending token by its index. */
+pub fn parse_program(tokens: &[Token]) -> Result<Statement, String> {
+    Parser::new(tokens, &[]).program()
+}
+
+/* Tokenizes and parses source text, reporting syntax errors by line and
+ * column. */
+pub fn parse(input: &str) -> Result<Statement, String> {
+    let (tokens, positions): (Vec<Token>, Vec<Position>) =
+        tokenize_with_positions(input)?.into_iter().unzip();
+    Parser::new(&tokens, &positions).program()
+}
+
+/* Tokenizes and parses a single expression, such as one typed at a
+ * prompt. Anything left over after it is a syntax error. */
+pub fn parse_expression(input: &str) -> Result<Expression, String> {
+    let (tokens, positions): (Vec<Token>, Vec<Position>) =
+        tokenize_with_positions(input)?.into_iter().unzip();
+    let mut parser = Parser::new(&tokens, &positions);
+    let exp = parser.expression()?;
+    parser.eat(&Token::Newline);
+    match parser.peek() {
+        Some(_) => Err(parser.expected("end of input")),
+        None => Ok(exp),
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    positions: &'a [Position],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(tokens: &'a [Token], positions: &'a [Position]) -> Parser<'a> {
+        Parser {
+            tokens,
+            positions,
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn check(&self, token: &Token) -> bool {
+        self.peek() == Some(token)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.check(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<(), String> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.expected(&format!("'{}'", token)))
+        }
+    }
+
+    /* Where the current token starts, when the tokens came with positions. */
+    fn span(&self) -> Option<Span> {
+        self.positions.get(self.pos).map(|position| Span {
+            line: position.line,
+            column: position.column,
+        })
+    }
+
+    fn error(&self, message: &str) -> String {
+        let location = match self.positions.get(self.pos).or(self.positions.last()) {
+            Some(position) => format!("line {}, column {}", position.line, position.column),
+            None => format!("token {}", self.pos + 1),
+        };
+        format!("Syntax error at {}: {}", location, message)
+    }
+
+    fn expected(&self, what: &str) -> String {
+        let found = match self.peek() {
+            Some(token @ (Token::Newline | Token::Indent | Token::Dedent)) => token.to_string(),
+            Some(token) => format!("'{}'", token),
+            None => String::from("end of input"),
+        };
+        self.error(&format!("expected {}, found {}", what, found))
+    }
+
+    fn program(&mut self) -> Result<Statement, String> {
+        let mut statements = Vec::new();
+        while self.peek().is_some() {
+            statements.push(self.statement()?);
+        }
+        sequence(statements).ok_or_else(|| self.expected("a statement"))
+    }
+
+    /* An indented block introduced by ':' at the end of a header line. */
+    fn block(&mut self) -> Result<Statement, String> {
+        self.expect(&Token::Colon)?;
+        self.expect(&Token::Newline)?;
+        self.expect(&Token::Indent)?;
+        let mut statements = vec![self.statement()?];
+        while self.peek().is_some() && !self.eat(&Token::Dedent) {
+            statements.push(self.statement()?);
+        }
+        sequence(statements).ok_or_else(|| self.expected("a statement"))
+    }
+
+    fn statement(&mut self) -> Result<Statement, String> {
+        let span = self.span();
+        Ok(self.bare_statement()?.spanned(span))
+    }
+
+    fn bare_statement(&mut self) -> Result<Statement, String> {
+        match self.peek() {
+            Some(Token::Def) => self.func_def(),
+            Some(Token::If) => {
+                self.pos += 1;
+                let cond = self.expression()?;
+                let stmt_then = self.block()?;
+                if self.eat(&Token::Else) {
+                    let stmt_else = self.block()?;
+                    Ok(Statement::IfThenElse(
+                        Box::new(cond),
+                        Box::new(stmt_then),
+                        Box::new(stmt_else),
+                    ))
+                } else {
+                    Ok(Statement::If(Box::new(cond), Box::new(stmt_then)))
+                }
+            }
+            Some(Token::While) => {
+                self.pos += 1;
+                let cond = self.expression()?;
+                let stmt = self.block()?;
+                if self.eat(&Token::Else) {
+                    let stmt_else = self.block()?;
+                    Ok(Statement::WhileElse(
+                        Box::new(cond),
+                        Box::new(stmt),
+                        Box::new(stmt_else),
+                    ))
+                } else {
+                    Ok(Statement::While(Box::new(cond), Box::new(stmt)))
+                }
+            }
+            Some(Token::For) => {
+                self.pos += 1;
+                let mut vars = vec![self.identifier()?];
+                while self.eat(&Token::Comma) {
+                    vars.push(self.identifier()?);
+                }
+                self.expect(&Token::In)?;
+                let exp = self.expression()?;
+                let stmt = self.block()?;
+                match vars.len() {
+                    1 if self.eat(&Token::Else) => {
+                        let stmt_else = self.block()?;
+                        Ok(Statement::ForElse(
+                            Box::new(vars.remove(0)),
+                            Box::new(exp),
+                            Box::new(stmt),
+                            Box::new(stmt_else),
+                        ))
+                    }
+                    1 => Ok(Statement::For(
+                        Box::new(vars.remove(0)),
+                        Box::new(exp),
+                        Box::new(stmt),
+                    )),
+                    _ => Ok(Statement::ForUnpack(vars, Box::new(exp), Box::new(stmt))),
+                }
+            }
+            Some(Token::Return) => {
+                self.pos += 1;
+                let exp = self.expression()?;
+                self.expect(&Token::Newline)?;
+                Ok(Statement::Return(Box::new(exp)))
+            }
+            Some(Token::Nonlocal) => {
+                self.pos += 1;
+                let mut names = vec![self.identifier()?];
+                while self.eat(&Token::Comma) {
+                    names.push(self.identifier()?);
+                }
+                self.expect(&Token::Newline)?;
+                Ok(Statement::Nonlocal(names))
+            }
+            Some(Token::Global) => {
+                self.pos += 1;
+                let mut names = vec![self.identifier()?];
+                while self.eat(&Token::Comma) {
+                    names.push(self.identifier()?);
+                }
+                self.expect(&Token::Newline)?;
+                Ok(Statement::Global(names))
+            }
+            Some(Token::Assert) => {
+                self.pos += 1;
+                let cond = self.expression()?;
+                let message = if self.eat(&Token::Comma) {
+                    Some(Box::new(self.expression()?))
+                } else {
+                    None
+                };
+                self.expect(&Token::Newline)?;
+                Ok(Statement::Assert(Box::new(cond), message))
+            }
+            Some(Token::Pass) => {
+                self.pos += 1;
+                self.expect(&Token::Newline)?;
+                Ok(Statement::Pass)
+            }
+            Some(Token::Break) => {
+                self.pos += 1;
+                self.expect(&Token::Newline)?;
+                Ok(Statement::Break)
+            }
+            Some(Token::Continue) => {
+                self.pos += 1;
+                self.expect(&Token::Newline)?;
+                Ok(Statement::Continue)
+            }
+            Some(Token::Ident(name)) if self.tokens.get(self.pos + 1) == Some(&Token::Assign) => {
+                self.pos += 2;
+                let exp = self.expression()?;
+                self.expect(&Token::Newline)?;
+                Ok(Statement::Assignment(Box::new(name.clone()), Box::new(exp)))
+            }
+            Some(Token::Ident(_)) if self.tokens.get(self.pos + 1) == Some(&Token::LBracket) => {
+                let start = self.pos;
+                let target = match self.postfix()? {
+                    Expression::Spanned(_, exp) => *exp,
+                    exp => exp,
+                };
+                let Expression::Index(target, index) = target else {
+                    self.pos = start;
+                    return self.expression_statement();
+                };
+                if !self.eat(&Token::Assign) {
+                    self.pos = start;
+                    return self.expression_statement();
+                }
+                let exp = self.expression()?;
+                self.expect(&Token::Newline)?;
+                Ok(Statement::IndexAssign(target, index, Box::new(exp)))
+            }
+            Some(Token::Ident(_)) if self.tokens.get(self.pos + 1) == Some(&Token::Comma) => {
+                let mut names = vec![self.identifier()?];
+                while self.eat(&Token::Comma) {
+                    names.push(self.identifier()?);
+                }
+                self.expect(&Token::Assign)?;
+                let mut exps = vec![self.expression()?];
+                while self.eat(&Token::Comma) {
+                    exps.push(self.expression()?);
+                }
+                self.expect(&Token::Newline)?;
+                Ok(Statement::MultiAssign(names, exps))
+            }
+            _ => self.expression_statement(),
+        }
+    }
+
+    /* A line holding just an expression, run for its effects. A line that
+     * doesn't even start an expression is reported as a bad statement. */
+    fn expression_statement(&mut self) -> Result<Statement, String> {
+        let start = self.pos;
+        let exp = match self.expression() {
+            Ok(exp) => exp,
+            Err(_) if self.pos == start => return Err(self.expected("a statement")),
+            Err(e) => return Err(e),
+        };
+        self.expect(&Token::Newline)?;
+        Ok(Statement::Expr(Box::new(exp)))
+    }
+
+    /* def name(param: type, ...) -> type: block
+     *
+     * The body reports its result through 'return' statements, so the
+     * trailing return expression of the Func node is None. A missing
+     * return annotation declares a function returning None. */
+    fn func_def(&mut self) -> Result<Statement, String> {
+        self.expect(&Token::Def)?;
+        let name = self.identifier()?;
+
+        self.expect(&Token::LParen)?;
+        let mut params = Vec::new();
+        while !self.eat(&Token::RParen) {
+            let param = self.identifier()?;
+            self.expect(&Token::Colon)?;
+            params.push((param, Box::new(self.type_annotation()?)));
+            if !self.eat(&Token::Comma) {
+                self.expect(&Token::RParen)?;
+                break;
+            }
+        }
+
+        let kind = if self.eat(&Token::Arrow) {
+            self.type_annotation()?
+        } else {
+            Type::TNone
+        };
+        let body = self.block()?;
+
+        Ok(Statement::Func(
+            Box::new(name),
+            Box::new(kind),
+            if params.is_empty() {
+                None
+            } else {
+                Some(params)
+            },
+            Some(Box::new(body)),
+            Box::new(Expression::None),
+        ))
+    }
+
+    /* Types are written with their Python names; a bare 'list' holds
+     * items of any type. */
+    fn type_annotation(&mut self) -> Result<Type, String> {
+        let kind = match self.peek() {
+            Some(Token::Ident(name)) => match name.as_str() {
+                "int" => Type::TInteger,
+                "float" => Type::TReal,
+                "bool" => Type::TBool,
+                "str" => Type::TString,
+                "Callable" => Type::TFunction,
+                "Any" => Type::TAny,
+                "list" => {
+                    self.pos += 1;
+                    if !self.eat(&Token::LBracket) {
+                        return Ok(Type::TList(Box::new(Type::TAny)));
+                    }
+                    let item_kind = self.type_annotation()?;
+                    self.expect(&Token::RBracket)?;
+                    return Ok(Type::TList(Box::new(item_kind)));
+                }
+                "Optional" => {
+                    self.pos += 1;
+                    self.expect(&Token::LBracket)?;
+                    let kind = self.type_annotation()?;
+                    self.expect(&Token::RBracket)?;
+                    return Ok(Type::TOptional(Box::new(kind)));
+                }
+                _ => return Err(self.expected("a type")),
+            },
+            Some(Token::None) => Type::TNone,
+            _ => return Err(self.expected("a type")),
+        };
+        self.pos += 1;
+        Ok(kind)
+    }
+
+    fn identifier(&mut self) -> Result<Name, String> {
+        match self.peek() {
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                Ok(name.clone())
+            }
+            _ => Err(self.expected("an identifier")),
+        }
+    }
+
+    /* 'or' binds looser than 'and', which binds looser than the
+     * comparisons; both group to the left. */
+    fn expression(&mut self) -> Result<Expression, String> {
+        let span = self.span();
+        if self.eat(&Token::Lambda) {
+            let mut params = Vec::new();
+            if !self.eat(&Token::Colon) {
+                params.push(self.identifier()?);
+                while self.eat(&Token::Comma) {
+                    params.push(self.identifier()?);
+                }
+                self.expect(&Token::Colon)?;
+            }
+            let body = self.expression()?;
+            return Ok(Expression::Lambda(params, Box::new(body)).spanned(span));
+        }
+        let mut lhs = self.conjunction()?;
+        while self.eat(&Token::Or) {
+            let rhs = self.conjunction()?;
+            lhs = Expression::Or(Box::new(lhs), Box::new(rhs)).spanned(span);
+        }
+        Ok(lhs)
+    }
+
+    fn conjunction(&mut self) -> Result<Expression, String> {
+        let span = self.span();
+        let mut lhs = self.comparison()?;
+        while self.eat(&Token::And) {
+            let rhs = self.comparison()?;
+            lhs = Expression::And(Box::new(lhs), Box::new(rhs)).spanned(span);
+        }
+        Ok(lhs)
+    }
+
+    /* A single comparison becomes its own binary node. Two or more, as
+     * in 'a < b < c', become one Compare so the inner operands are only
+     * evaluated once. */
+    fn comparison(&mut self) -> Result<Expression, String> {
+        let span = self.span();
+        let lhs = self.bitwise()?;
+        let mut links = Vec::new();
+        while let Some(op) = self.comparison_operator() {
+            links.push((op, self.bitwise()?));
+        }
+        let exp = match links.len() {
+            0 => return Ok(lhs),
+            1 => {
+                let (op, rhs) = links.remove(0);
+                let node: fn(Box<Expression>, Box<Expression>) -> Expression = match op {
+                    CmpOp::Eq => Expression::Eq,
+                    CmpOp::NotEq => Expression::NotEq,
+                    CmpOp::Lt => Expression::Lt,
+                    CmpOp::LtE => Expression::LtE,
+                    CmpOp::Gt => Expression::Gt,
+                    CmpOp::GtE => Expression::GtE,
+                    CmpOp::Is => Expression::Is,
+                    CmpOp::IsNot => Expression::IsNot,
+                    CmpOp::In => Expression::In,
+                    CmpOp::NotIn => Expression::NotIn,
+                };
+                node(Box::new(lhs), Box::new(rhs))
+            }
+            _ => Expression::Compare(Box::new(lhs), links),
+        };
+        Ok(exp.spanned(span))
+    }
+
+    fn comparison_operator(&mut self) -> Option<CmpOp> {
+        let op = match self.peek() {
+            Some(Token::Eq) => CmpOp::Eq,
+            Some(Token::NotEq) => CmpOp::NotEq,
+            Some(Token::Lt) => CmpOp::Lt,
+            Some(Token::LtE) => CmpOp::LtE,
+            Some(Token::Gt) => CmpOp::Gt,
+            Some(Token::GtE) => CmpOp::GtE,
+            Some(Token::Is) if self.tokens.get(self.pos + 1) == Some(&Token::Not) => {
+                self.pos += 1;
+                CmpOp::IsNot
+            }
+            Some(Token::Is) => CmpOp::Is,
+            Some(Token::In) => CmpOp::In,
+            Some(Token::Not) if self.tokens.get(self.pos + 1) == Some(&Token::In) => {
+                self.pos += 1;
+                CmpOp::NotIn
+            }
+            _ => return None,
+        };
+        self.pos += 1;
+        Some(op)
+    }
+
+    /* The bitwise operators sit between the comparisons and '+', from
+     * loosest to tightest: '|', '^', '&' and then the shifts. */
+    fn bitwise(&mut self) -> Result<Expression, String> {
+        let span = self.span();
+        let mut lhs = self.bit_xor()?;
+        while self.eat(&Token::Pipe) {
+            let rhs = self.bit_xor()?;
+            lhs = Expression::BitOr(Box::new(lhs), Box::new(rhs)).spanned(span);
+        }
+        Ok(lhs)
+    }
+
+    fn bit_xor(&mut self) -> Result<Expression, String> {
+        let span = self.span();
+        let mut lhs = self.bit_and()?;
+        while self.eat(&Token::Caret) {
+            let rhs = self.bit_and()?;
+            lhs = Expression::BitXor(Box::new(lhs), Box::new(rhs)).spanned(span);
+        }
+        Ok(lhs)
+    }
+
+    fn bit_and(&mut self) -> Result<Expression, String> {
+        let span = self.span();
+        let mut lhs = self.shift()?;
+        while self.eat(&Token::Ampersand) {
+            let rhs = self.shift()?;
+            lhs = Expression::BitAnd(Box::new(lhs), Box::new(rhs)).spanned(span);
+        }
+        Ok(lhs)
+    }
+
+    fn shift(&mut self) -> Result<Expression, String> {
+        let span = self.span();
+        let mut lhs = self.additive()?;
+        loop {
+            let op: fn(Box<Expression>, Box<Expression>) -> Expression = match self.peek() {
+                Some(Token::ShiftLeft) => Expression::Shl,
+                Some(Token::ShiftRight) => Expression::Shr,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            let rhs = self.additive()?;
+            lhs = op(Box::new(lhs), Box::new(rhs)).spanned(span);
+        }
+    }
+
+    fn additive(&mut self) -> Result<Expression, String> {
+        let span = self.span();
+        let mut lhs = self.multiplicative()?;
+        loop {
+            let op: fn(Box<Expression>, Box<Expression>) -> Expression = match self.peek() {
+                Some(Token::Plus) => Expression::Add,
+                Some(Token::Minus) => Expression::Sub,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            let rhs = self.multiplicative()?;
+            lhs = op(Box::new(lhs), Box::new(rhs)).spanned(span);
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expression, String> {
+        let span = self.span();
+        let mut lhs = self.unary()?;
+        loop {
+            let op: fn(Box<Expression>, Box<Expression>) -> Expression = match self.peek() {
+                Some(Token::Star) => Expression::Mul,
+                Some(Token::Slash) => Expression::Div,
+                Some(Token::DoubleSlash) => Expression::FloorDiv,
+                Some(Token::Percent) => Expression::Mod,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            let rhs = self.unary()?;
+            lhs = op(Box::new(lhs), Box::new(rhs)).spanned(span);
+        }
+    }
+
+    /* There is no negation node: negative literals are folded and any
+     * other operand becomes a subtraction from zero. Negation applies
+     * after '**', so '-2 ** 2' is -4. */
+    fn unary(&mut self) -> Result<Expression, String> {
+        let span = self.span();
+        if !self.eat(&Token::Minus) {
+            return self.power();
+        }
+        match self.unary()? {
+            Expression::CInt(v) => Ok(Expression::CInt(-v)),
+            Expression::CReal(v) => Ok(Expression::CReal(-v)),
+            exp => Ok(Expression::Neg(Box::new(exp)).spanned(span)),
+        }
+    }
+
+    /* '**' groups to the right, and its exponent may itself be negated,
+     * as in '2 ** -1'. */
+    fn power(&mut self) -> Result<Expression, String> {
+        let span = self.span();
+        let base = self.postfix()?;
+        if !self.eat(&Token::DoubleStar) {
+            return Ok(base);
+        }
+        let exponent = self.unary()?;
+        Ok(Expression::Pow(Box::new(base), Box::new(exponent)).spanned(span))
+    }
+
+    /* Subscripts and calls chain onto any primary, as in 'm[i][j]' or
+     * 'adder(5)(3)'. */
+    fn postfix(&mut self) -> Result<Expression, String> {
+        let span = self.span();
+        let mut exp = self.primary()?;
+        loop {
+            if self.eat(&Token::LBracket) {
+                let index = self.expression()?;
+                self.expect(&Token::RBracket)?;
+                exp = Expression::Index(Box::new(exp), Box::new(index)).spanned(span);
+            } else if self.eat(&Token::LParen) {
+                let args = self.call_arguments()?;
+                exp = Expression::Call(Box::new(exp), args).spanned(span);
+            } else {
+                return Ok(exp);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expression, String> {
+        let span = self.span();
+        let exp = match self.peek() {
+            Some(Token::Int(v)) => Expression::CInt(*v),
+            Some(Token::Real(v)) => Expression::CReal(*v),
+            Some(Token::StringLit(v)) => Expression::CString(v.clone()),
+            Some(Token::True) => Expression::Bool(true),
+            Some(Token::False) => Expression::Bool(false),
+            Some(Token::None) => Expression::None,
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                if !self.eat(&Token::LParen) {
+                    return Ok(Expression::Var(name.clone()).spanned(span));
+                }
+                let args = self.call_arguments()?;
+                return Ok(Expression::FuncCall(name.clone(), args).spanned(span));
+            }
+            Some(Token::LParen) => {
+                self.pos += 1;
+                if self.eat(&Token::RParen) {
+                    return Ok(Expression::Tuple(vec![]));
+                }
+                let exp = self.expression()?;
+                if self.eat(&Token::For) {
+                    return self.generator(exp);
+                }
+                if !self.eat(&Token::Comma) {
+                    self.expect(&Token::RParen)?;
+                    return Ok(exp);
+                }
+                let mut items = vec![exp];
+                items.extend(self.arguments(&Token::RParen)?);
+                return Ok(Expression::Tuple(items));
+            }
+            /* A 'for' after the first item turns the brackets into a list
+             * comprehension, as in '[i * i for i in xs if i > 1]'. */
+            Some(Token::LBracket) => {
+                self.pos += 1;
+                if self.eat(&Token::RBracket) {
+                    return Ok(Expression::List(vec![]));
+                }
+                let first = self.expression()?;
+                if self.eat(&Token::For) {
+                    let (var, iterable, condition) = self.comprehension()?;
+                    self.expect(&Token::RBracket)?;
+                    return Ok(Expression::ListComp {
+                        element: Box::new(first),
+                        var,
+                        iterable: Box::new(iterable),
+                        condition,
+                    });
+                }
+                let mut items = vec![first];
+                if self.eat(&Token::Comma) {
+                    items.extend(self.arguments(&Token::RBracket)?);
+                } else {
+                    self.expect(&Token::RBracket)?;
+                }
+                return Ok(Expression::List(items));
+            }
+            /* Braces hold a dict when the first item is a 'key: value'
+             * pair and a set otherwise; empty braces are a dict. */
+            Some(Token::LBrace) => {
+                self.pos += 1;
+                if self.eat(&Token::RBrace) {
+                    return Ok(Expression::Dict(vec![]));
+                }
+                let first = self.expression()?;
+                if !self.eat(&Token::Colon) {
+                    let mut items = vec![first];
+                    if self.eat(&Token::Comma) {
+                        items.extend(self.arguments(&Token::RBrace)?);
+                    } else {
+                        self.expect(&Token::RBrace)?;
+                    }
+                    return Ok(Expression::Set(items));
+                }
+                let mut entries = vec![(first, self.expression()?)];
+                while self.eat(&Token::Comma) && !self.check(&Token::RBrace) {
+                    let key = self.expression()?;
+                    self.expect(&Token::Colon)?;
+                    entries.push((key, self.expression()?));
+                }
+                self.expect(&Token::RBrace)?;
+                return Ok(Expression::Dict(entries));
+            }
+            _ => return Err(self.expected("an expression")),
+        };
+        self.pos += 1;
+        Ok(exp)
+    }
+
+    /* The 'var in iterable [if condition]' part of a comprehension, after
+     * its 'for'. */
+    fn comprehension(&mut self) -> Result<(Name, Expression, Option<Box<Expression>>), String> {
+        let var = self.identifier()?;
+        self.expect(&Token::In)?;
+        let iterable = self.expression()?;
+        let condition = if self.eat(&Token::If) {
+            Some(Box::new(self.expression()?))
+        } else {
+            None
+        };
+        Ok((var, iterable, condition))
+    }
+
+    /* The rest of '(element for var in iterable [if condition])', after
+     * its 'for'. */
+    fn generator(&mut self, element: Expression) -> Result<Expression, String> {
+        let (var, iterable, condition) = self.comprehension()?;
+        self.expect(&Token::RParen)?;
+        Ok(Expression::GenExp {
+            element: Box::new(element),
+            var,
+            iterable: Box::new(iterable),
+            condition,
+        })
+    }
+
+    /* The arguments of a call, after its '('. A lone generator expression
+     * needs no parentheses of its own, as in 'sum(i * i for i in xs)'. */
+    fn call_arguments(&mut self) -> Result<Option<Vec<Expression>>, String> {
+        if self.eat(&Token::RParen) {
+            return Ok(None);
+        }
+        let first = self.expression()?;
+        if self.eat(&Token::For) {
+            return Ok(Some(vec![self.generator(first)?]));
+        }
+        let mut args = vec![first];
+        if self.eat(&Token::Comma) {
+            args.extend(self.arguments(&Token::RParen)?);
+        } else {
+            self.expect(&Token::RParen)?;
+        }
+        Ok(Some(args))
+    }
+
+    /* A comma separated list of expressions, possibly empty and with an
+     * optional trailing comma, up to and including the closing token. */
+    fn arguments(&mut self, close: &Token) -> Result<Vec<Expression>, String> {
+        let mut args = Vec::new();
+        while !self.eat(close) {
+            args.push(self.expression()?);
+            if !self.eat(&Token::Comma) {
+                self.expect(close)?;
+                break;
+            }
+        }
+        Ok(args)
+    }
+}
+
+fn sequence(statements: Vec<Statement>) -> Option<Statement> {
+    let mut statements = statements.into_iter().rev();
+    let last = statements.next()?;
+    Some(statements.fold(last, |rest, stmt| {
+        Statement::Sequence(Box::new(stmt), Box::new(rest))
+    }))
+}
+
+#[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
+mod tests {
+    use super::*;
+    use crate::interpreter::interpreter::{
+        eval, execute, run, EnvValue, Environment, EvalResult, Interpreter, InterpreterError,
+    };
+    use crate::parser::lexer::tokenize;
+
+    #[test]
+    fn parse_arithmetic_precedence() {
+        let tokens = tokenize("x = 1 + 2 * (3 - 4) // 5").unwrap();
+
+        assert_eq!(
+            parse_program(&tokens),
+            Ok(Statement::Assignment(
+                Box::new(String::from("x")),
+                Box::new(Expression::Add(
+                    Box::new(Expression::CInt(1)),
+                    Box::new(Expression::FloorDiv(
+                        Box::new(Expression::Mul(
+                            Box::new(Expression::CInt(2)),
+                            Box::new(Expression::Sub(
+                                Box::new(Expression::CInt(3)),
+                                Box::new(Expression::CInt(4)),
+                            )),
+                        )),
+                        Box::new(Expression::CInt(5)),
+                    )),
+                )),
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_and_execute_summation() {
+        let source = "
+x = 10
+y = 0
+while x:
+  y = y + x
+  x = x - 1
+";
+        let program = parse_program(&tokenize(source).unwrap()).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                match new_env.get("y") {
+                    Some(EnvValue::CInt(55)) => {}
+                    Some(val) => assert!(false, "Expected 55, got {:?}", val),
+                    None => assert!(false, "Variable y not found"),
+                }
+                match new_env.get("x") {
+                    Some(EnvValue::CInt(0)) => {}
+                    Some(val) => assert!(false, "Expected 0, got {:?}", val),
+                    None => assert!(false, "Variable x not found"),
+                }
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_functions_and_loops() {
+        let source = "
+def add(a: int, b: int) -> int:
+    return a + b
+
+total = 0
+for i in range(1, 11):
+    if i > 5:
+        total = add(total, i)
+    else:
+        total = total - 1
+evens = [0, 2, 4]
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                match new_env.get("total") {
+                    Some(EnvValue::CInt(35)) => {}
+                    Some(val) => assert!(false, "Expected 35, got {:?}", val),
+                    None => assert!(false, "Variable total not found"),
+                }
+                match new_env.get("evens") {
+                    Some(EnvValue::List(items)) => assert_eq!(items.len(), 3),
+                    Some(val) => assert!(false, "Expected a list, got {:?}", val),
+                    None => assert!(false, "Variable evens not found"),
+                }
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_syntax_error_position() {
+        let source = "x = 1\nwhile x\n    x = 0\n";
+
+        assert_eq!(
+            parse(source),
+            Err(String::from(
+                "Syntax error at line 2, column 8: expected ':', found newline"
+            ))
+        );
+        assert_eq!(
+            parse_program(&tokenize(source).unwrap()),
+            Err(String::from(
+                "Syntax error at token 7: expected ':', found newline"
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_runtime_error_position() {
+        let source = "x = 1\ny = 2\nif x:\n   w = z + y\n";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(_) => assert!(false, "Expected an undefined variable error"),
+            Err(err) => {
+                assert_eq!(err.to_string(), "Variable z not found at line 4, col 8");
+                assert_eq!(
+                    err.root_cause(),
+                    &InterpreterError::UndefinedVariable(String::from("z"))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_dict_index() {
+        let source = "
+squares = {1: 1, 2: 4,
+           3: 9}
+x = squares[3] + [10, 20, 30][-1]
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => match new_env.get("x") {
+                Some(EnvValue::CInt(39)) => {}
+                Some(val) => assert!(false, "Expected 39, got {:?}", val),
+                None => assert!(false, "Variable x not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_returned_closures() {
+        let source = "
+def adder(n: int) -> Callable:
+    def add(x: int) -> int:
+        return x + n
+    return add
+
+a = adder(5)(3)
+add1 = adder(1)
+add10 = adder(10)
+b = add1(2)
+c = add10(2)
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("a"), Some(EnvValue::CInt(8)));
+                assert_eq!(new_env.get("b"), Some(EnvValue::CInt(3)));
+                assert_eq!(new_env.get("c"), Some(EnvValue::CInt(12)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_fibonacci_swap() {
+        let source = "
+a, b = 0, 1
+for i in range(10):
+    a, b = b, a + b
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("a"), Some(EnvValue::CInt(55)));
+                assert_eq!(new_env.get("b"), Some(EnvValue::CInt(89)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_strings() {
+        let source = r#"
+ages = {"ana": 30, "bia": 25}
+name = "bia"
+line = name + ": " + str(ages[name])
+"#;
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => match new_env.get("line") {
+                Some(EnvValue::CString(line)) => assert_eq!(line, "bia: 25"),
+                Some(val) => assert!(false, "Expected a str, got {:?}", val),
+                None => assert!(false, "Variable line not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_index_assignment() {
+        let source = r#"
+m = [[0, 0], [0, 0]]
+counts = {}
+for i in range(2):
+    m[i][1 - i] = i + 1
+    counts["row"] = i
+"#;
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                let show = |name: &str| {
+                    eval(&Expression::Var(String::from(name)), &new_env).map(|v| v.to_string())
+                };
+                assert_eq!(show("m"), Ok(String::from("[[0, 1], [2, 0]]")));
+                assert_eq!(show("counts"), Ok(String::from("{'row': 1}")));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_for_over_zip() {
+        let source = r#"
+names = ["ana", "bia", "caio"]
+ages = [30, 25]
+line = ""
+for name, age in zip(names, ages):
+    line = line + name + "=" + str(age) + " "
+"#;
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => match new_env.get("line") {
+                Some(EnvValue::CString(line)) => assert_eq!(line, "ana=30 bia=25 "),
+                Some(val) => assert!(false, "Expected a str, got {:?}", val),
+                None => assert!(false, "Variable line not found"),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_list_comprehension() {
+        let source = r#"
+squares = [i * i for i in range(5) if i > 1]
+pairs = [[x, x + 1] for x in squares]
+"#;
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                let show = |name: &str| {
+                    eval(&Expression::Var(String::from(name)), &new_env).map(|v| v.to_string())
+                };
+                assert_eq!(show("squares"), Ok(String::from("[4, 9, 16]")));
+                assert_eq!(
+                    show("pairs"),
+                    Ok(String::from("[[4, 5], [9, 10], [16, 17]]"))
+                );
+                assert!(new_env.get("i").is_none());
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_append() {
+        let source = r#"
+evens = []
+for i in range(6):
+    if i // 2 * 2 == i:
+        append(evens, i)
+"#;
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => assert_eq!(
+                eval(&Expression::Var(String::from("evens")), &new_env).map(|v| v.to_string()),
+                Ok(String::from("[0, 2, 4]"))
+            ),
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_annotations() {
+        let source = r#"
+def scale(xs: list[float], factor: Optional[int]) -> list[float]:
+    return xs
+
+def log(msg: str):
+    return None
+
+hints = annotations(scale)
+ret = annotations(log)["return"]
+"#;
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                let show = |name: &str| {
+                    eval(&Expression::Var(String::from(name)), &new_env).map(|v| v.to_string())
+                };
+                assert_eq!(
+                    show("hints"),
+                    Ok(String::from(
+                        "{'xs': 'list[real]', 'factor': 'Optional[int]', 'return': 'list[real]'}"
+                    ))
+                );
+                assert_eq!(show("ret"), Ok(String::from("None")));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_closure_ignores_caller_shadowing() {
+        let source = "
+scale = 2
+
+def make() -> Callable:
+    offset = 7
+    def apply(x: int) -> int:
+        return x * scale + offset
+    return apply
+
+def caller(f: Callable) -> int:
+    scale = 100
+    offset = 1000
+    return f(1)
+
+r = caller(make())
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("r"), Some(EnvValue::CInt(9)));
+                assert_eq!(new_env.get("offset"), None);
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_or_precedence() {
+        /* 'or' binds looser than 'and', which binds looser than '<'. */
+        let program = parse_program(&tokenize("y = a or b and x < 1").unwrap());
+        let var = |name: &str| Box::new(Expression::Var(String::from(name)));
+
+        assert_eq!(
+            program,
+            Ok(Statement::Assignment(
+                Box::new(String::from("y")),
+                Box::new(Expression::Or(
+                    var("a"),
+                    Box::new(Expression::And(
+                        var("b"),
+                        Box::new(Expression::Lt(var("x"), Box::new(Expression::CInt(1)))),
+                    )),
+                )),
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_and_execute_short_circuit() {
+        let source = "
+xs = [1, 2]
+i = 5
+ok = i < 2 and xs[i] > 0
+safe = i >= 2 or xs[i] > 0
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("ok"), Some(EnvValue::Bool(false)));
+                assert_eq!(new_env.get("safe"), Some(EnvValue::Bool(true)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_runtime_error_call_site() {
+        let source = "
+def ratio(d: int) -> int:
+    return 10 // d
+
+def both(x: int, y: int) -> int:
+    return ratio(x) + ratio(y)
+
+a = ratio(2)
+b = both(5, 0)
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(_) => assert!(false, "Expected a division by zero"),
+            Err(err) => {
+                assert_eq!(
+                    err.to_string(),
+                    "both called at line 9, col 5 generated an error: \
+                     ratio called at line 6, col 23 generated an error: \
+                     Division by zero at line 3, col 12"
+                );
+                assert_eq!(err.root_cause(), &InterpreterError::DivisionByZero);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_generator_expressions() {
+        let source = "
+total = sum(i * i for i in range(4))
+odd = 0
+for n in (k for k in range(10) if k // 2 * 2 != k):
+    odd = odd + 1
+has_big = any(n > 8 for n in [3, 9])
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("total"), Some(EnvValue::CInt(14)));
+                assert_eq!(new_env.get("odd"), Some(EnvValue::CInt(5)));
+                assert_eq!(new_env.get("has_big"), Some(EnvValue::Bool(true)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_pow_precedence() {
+        let source = "
+a = 2 ** 3 ** 2
+b = -2 ** 2
+c = 2 * 3 ** 2
+d = 2 ** -1
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("a"), Some(EnvValue::CInt(512)));
+                assert_eq!(new_env.get("b"), Some(EnvValue::CInt(-4)));
+                assert_eq!(new_env.get("c"), Some(EnvValue::CInt(18)));
+                assert_eq!(new_env.get("d"), Some(EnvValue::CReal(0.5)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_bitwise_precedence() {
+        let source = "
+a = 1 | 6 & 3
+b = 1 << 2 + 1
+c = 5 ^ 1 | 8
+d = 6 & 3 == 2
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("a"), Some(EnvValue::CInt(3)));
+                assert_eq!(new_env.get("b"), Some(EnvValue::CInt(8)));
+                assert_eq!(new_env.get("c"), Some(EnvValue::CInt(12)));
+                assert_eq!(new_env.get("d"), Some(EnvValue::Bool(true)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_self_recursion() {
+        let source = "
+def fact(n: int) -> int:
+    if n <= 1:
+        return 1
+    return n * fact(n - 1)
+
+a = fact(5)
+g = fact
+b = g(6)
+c = fact(4)
+
+def outer(n: int) -> int:
+    def fib(k: int) -> int:
+        if k < 2:
+            return k
+        return fib(k - 1) + fib(k - 2)
+    return fib(n)
+
+d = outer(10)
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("a"), Some(EnvValue::CInt(120)));
+                assert_eq!(new_env.get("b"), Some(EnvValue::CInt(720)));
+                assert_eq!(new_env.get("c"), Some(EnvValue::CInt(24)));
+                assert_eq!(new_env.get("d"), Some(EnvValue::CInt(55)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_while_else_runs_else() {
+        let source = "
+i = 0
+outcome = \"none\"
+while i < 3:
+    i = i + 1
+else:
+    outcome = \"else ran\"
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("i"), Some(EnvValue::CInt(3)));
+                assert_eq!(
+                    new_env.get("outcome"),
+                    Some(EnvValue::CString(String::from("else ran")))
+                );
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_while_else_skipped_by_break() {
+        let source = "
+i = 0
+outcome = \"none\"
+while i < 10:
+    i = i + 1
+    if i == 4:
+        outcome = \"broke\"
+        break
+else:
+    outcome = \"else ran\"
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("i"), Some(EnvValue::CInt(4)));
+                assert_eq!(
+                    new_env.get("outcome"),
+                    Some(EnvValue::CString(String::from("broke")))
+                );
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_while_else_step_limit() {
+        let source = "
+outcome = \"none\"
+while True:
+    outcome = \"looping\"
+else:
+    outcome = \"else ran\"
+";
+        let program = parse(source).unwrap();
+
+        match Interpreter::new()
+            .with_max_steps(50)
+            .execute(&program, Environment::new())
+        {
+            Ok(new_env) => assert!(
+                false,
+                "Expected the step limit error, got outcome {:?}",
+                new_env.get("outcome")
+            ),
+            Err(s) => assert_eq!(s.root_cause(), &InterpreterError::StepLimitExceeded),
+        }
+    }
+
+    #[test]
+    fn parse_is_not() {
+        let source = "
+def find(xs: list[int], x: int) -> Optional[int]:
+    i = 0
+    for item in xs:
+        if item == x:
+            return i
+        i = i + 1
+    return None
+
+a = find([4, 5, 6], 5) is not None
+b = find([4, 5, 6], 7) is None
+c = 1 + 1 is 2
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("a"), Some(EnvValue::Bool(true)));
+                assert_eq!(new_env.get("b"), Some(EnvValue::Bool(true)));
+                assert_eq!(new_env.get("c"), Some(EnvValue::Bool(true)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_for_else() {
+        let source = "
+def first_even(xs: list[int]) -> Optional[int]:
+    result = None
+    for x in xs:
+        if x & 1 == 0:
+            result = x
+            break
+    else:
+        return -1
+    return result
+
+a = first_even([1, 3, 4, 6])
+b = first_even([1, 3, 5])
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("a"), Some(EnvValue::CInt(4)));
+                assert_eq!(new_env.get("b"), Some(EnvValue::CInt(-1)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_in() {
+        let source = "
+vowels = 0
+for c in \"programming\":
+    if c in \"aeiou\":
+        vowels = vowels + 1
+seen = []
+for x in [3, 1, 3, 2, 1]:
+    if x not in seen:
+        append(seen, x)
+found = [i for i in range(5) if i in {1, 3}]
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("vowels"), Some(EnvValue::CInt(3)));
+                assert_eq!(
+                    new_env.get("seen"),
+                    Some(EnvValue::List(vec![
+                        EvalResult::CInt(3),
+                        EvalResult::CInt(1),
+                        EvalResult::CInt(2),
+                    ]))
+                );
+                assert_eq!(
+                    new_env.get("found"),
+                    Some(EnvValue::List(vec![
+                        EvalResult::CInt(1),
+                        EvalResult::CInt(3)
+                    ]))
+                );
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_assert() {
+        let source = "
+def square(n: int) -> int:
+    return n * n
+
+assert square(3) == 9
+assert square(4) == 16, \"square(4) is wrong\"
+assert square(5) == 24, \"square(5) is \" + str(square(5))
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(_) => assert!(false, "The last assert should fail"),
+            Err(s) => assert_eq!(
+                s.to_string(),
+                "AssertionError: square(5) is 25 at line 7, col 1"
+            ),
+        }
+    }
+
+    #[test]
+    fn parse_pass() {
+        let source = "
+def noop() -> None:
+    pass
+
+i = 0
+while i < 3:
+    i = i + 1
+    if i == 2:
+        pass
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => match new_env.get("i") {
+                Some(EnvValue::CInt(3)) => (),
+                other => assert!(false, "Expected 3, found {:?}", other),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_global_declaration() {
+        let source = "
+total = 0
+def add(n: int) -> int:
+    global total
+    total = total + n
+    return total
+
+def outer() -> int:
+    count = 1
+    def inner() -> int:
+        nonlocal count
+        global count
+        return count
+    return inner()
+
+a = add(3)
+b = add(4)
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => match new_env.get("total") {
+                Some(EnvValue::CInt(7)) => (),
+                other => assert!(false, "Expected 7, found {:?}", other),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+
+        match execute(
+            &parse(&format!("{}c = outer()\n", source)).unwrap(),
+            Environment::new(),
+        ) {
+            Ok(_) => assert!(false, "A name can't be both nonlocal and global"),
+            Err(s) => assert_eq!(
+                s.root_cause(),
+                &InterpreterError::SyntaxError(String::from("name 'count' is nonlocal and global"))
+            ),
+        }
+    }
+
+    #[test]
+    fn parse_range_inclusive() {
+        let source = "
+total = 0
+for i in range_inclusive(1, 5):
+    total = total + i
+";
+        match execute(&parse(source).unwrap(), Environment::new()) {
+            Ok(new_env) => match new_env.get("total") {
+                Some(EnvValue::CInt(15)) => (),
+                other => assert!(false, "Expected 15, found {:?}", other),
+            },
+            Err(s) => assert!(false, "{}", s),
+        }
+
+        /* Like any builtin it is a call, checked when it runs. */
+        match execute(
+            &parse("xs = range_inclusive()\n").unwrap(),
+            Environment::new(),
+        ) {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "range_inclusive expected 1 to 3 arguments, got 0 at line 1, col 6"
+            ),
+            Ok(_) => assert!(false, "range_inclusive() should generate an error"),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_for_over_enumerate() {
+        let source = r#"
+values = [4, 5, 6]
+total = 0
+for index, value in enumerate(values):
+    total = total + index * value
+pairs = enumerate("ab", 1)
+"#;
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                match new_env.get("total") {
+                    Some(EnvValue::CInt(17)) => (),
+                    other => assert!(false, "Expected 17, found {:?}", other),
+                }
+                assert_eq!(
+                    eval(&Expression::Var(String::from("pairs")), &new_env).map(|v| v.to_string()),
+                    Ok(String::from("[(1, 'a'), (2, 'b')]"))
+                );
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_map_filter() {
+        let source = r#"
+def double(x: int) -> int:
+    return x * 2
+
+def is_even(x: int) -> bool:
+    return x % 2 == 0
+
+doubled = map(double, [1, 2, 3])
+evens = filter(is_even, range(7))
+"#;
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                let show = |name: &str| {
+                    eval(&Expression::Var(String::from(name)), &new_env).map(|v| v.to_string())
+                };
+                assert_eq!(show("doubled"), Ok(String::from("[2, 4, 6]")));
+                assert_eq!(show("evens"), Ok(String::from("[0, 2, 4, 6]")));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+
+        match execute(&parse("xs = map(3, [1])\n").unwrap(), Environment::new()) {
+            Ok(_) => assert!(false, "An int can't be called"),
+            Err(s) => assert_eq!(
+                s.root_cause(),
+                &InterpreterError::TypeError(String::from("'int' object is not callable"))
+            ),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_lambda() {
+        let source = r#"
+doubled = map(lambda x: x * 2, [1, 2, 3])
+add = lambda a, b: a + b
+total = add(2, 5)
+answer = (lambda: 42)()
+"#;
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                let show = |name: &str| {
+                    eval(&Expression::Var(String::from(name)), &new_env).map(|v| v.to_string())
+                };
+                assert_eq!(show("doubled"), Ok(String::from("[2, 4, 6]")));
+                assert_eq!(show("total"), Ok(String::from("7")));
+                assert_eq!(show("answer"), Ok(String::from("42")));
+                assert_eq!(show("add"), Ok(String::from("<function <lambda>>")));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+
+        match execute(
+            &parse("f = lambda x: x\ny = f()\n").unwrap(),
+            Environment::new(),
+        ) {
+            Ok(_) => assert!(false, "The lambda takes one argument"),
+            Err(s) => assert_eq!(
+                s.root_cause(),
+                &InterpreterError::ArityMismatch {
+                    name: String::from("f"),
+                    expected: 1,
+                    got: 0,
+                }
+            ),
+        }
+    }
+
+    #[test]
+    fn parse_expression_statements() {
+        let source = "
+count = 0
+xs = [1, 2]
+def bump() -> int:
+    global count
+    count = count + 1
+    return count
+
+bump()
+bump()
+xs[0]
+count
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => assert_eq!(new_env.get("count"), Some(EnvValue::CInt(2))),
+            Err(s) => assert!(false, "{}", s),
+        }
+
+        assert_eq!(
+            parse(")\n"),
+            Err(String::from(
+                "Syntax error at line 1, column 1: expected a statement, found ')'"
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_and_execute_split_join() {
+        let source = r#"
+line = join(", ", split("  a b   c "))
+csv = "x,,y"
+same = join(",", split(csv, ",")) == csv
+"#;
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(
+                    new_env.get("line"),
+                    Some(EnvValue::CString(String::from("a, b, c")))
+                );
+                assert_eq!(new_env.get("same"), Some(EnvValue::Bool(true)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_deep_index_assignment() {
+        let source = r#"
+cube = [[[0, 0], [0, 0]], [[0, 0], [0, 0]]]
+row = cube[1][0]
+cube[1][0][1] = 7
+cube[-1][-1][-2] = cube[1][0][1] * 2
+"#;
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                let show = |name: &str| {
+                    eval(&Expression::Var(String::from(name)), &new_env).map(|v| v.to_string())
+                };
+                assert_eq!(
+                    show("cube"),
+                    Ok(String::from("[[[0, 0], [0, 0]], [[0, 7], [14, 0]]]"))
+                );
+                /* 'row' holds its own copy, taken before the assignments. */
+                assert_eq!(show("row"), Ok(String::from("[0, 0]")));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+
+        let program = parse("m = [[1, 2], [3, 4]]\nm[2][0] = 9\n").unwrap();
+        match execute(&program, Environment::new()) {
+            Ok(_) => assert!(false, "Assignment should generate an error"),
+            Err(s) => assert_eq!(
+                s.root_cause(),
+                &InterpreterError::IndexError(String::from("list index out of range"))
+            ),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_pow() {
+        let source = "
+square = pow(2, 10) == 2 ** 10
+big = pow(2, 10, 1000)
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("square"), Some(EnvValue::Bool(true)));
+                assert_eq!(new_env.get("big"), Some(EnvValue::CInt(24)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+
+        let program = parse("x = pow(2.0, 10, 1000)\n").unwrap();
+        match execute(&program, Environment::new()) {
+            Ok(_) => assert!(false, "pow should reject a real with a modulus"),
+            Err(s) => assert_eq!(
+                s.root_cause(),
+                &InterpreterError::TypeError(String::from(
+                    "pow() 3rd argument not allowed unless all arguments are integers"
+                ))
+            ),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_arguments_are_copies() {
+        let source = "
+def grow(xs: list[int]) -> int:
+    append(xs, 4)
+    xs[0] = 100
+    return xs[0] + xs[3]
+
+nums = [1, 2, 3]
+n = grow(nums)
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("n"), Some(EnvValue::CInt(104)));
+                assert_eq!(
+                    new_env.get("nums"),
+                    Some(EnvValue::List(vec![
+                        EvalResult::CInt(1),
+                        EvalResult::CInt(2),
+                        EvalResult::CInt(3),
+                    ]))
+                );
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_chained_comparison() {
+        let source = "
+calls = 0
+def mid() -> int:
+    global calls
+    calls = calls + 1
+    return 5
+
+inside = 1 < mid() < 10
+outside = 1 < mid() < 3
+skipped = 9 < 5 < mid()
+mixed = 1 < 2 == 2 in [2] is not None
+";
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("inside"), Some(EnvValue::Bool(true)));
+                assert_eq!(new_env.get("outside"), Some(EnvValue::Bool(false)));
+                assert_eq!(new_env.get("skipped"), Some(EnvValue::Bool(false)));
+                assert_eq!(new_env.get("mixed"), Some(EnvValue::Bool(true)));
+                /* Once for each of the first two chains, and not at all for
+                 * the third, which fails before reaching it. */
+                assert_eq!(new_env.get("calls"), Some(EnvValue::CInt(2)));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+
+        let int = |v: i32| Expression::CInt(v);
+        let assign = |exp: Expression| {
+            Ok(Statement::Assignment(
+                Box::new(String::from("y")),
+                Box::new(exp),
+            ))
+        };
+        assert_eq!(
+            parse_program(&tokenize("y = 1 < 5 < 10").unwrap()),
+            assign(Expression::Compare(
+                Box::new(int(1)),
+                vec![(CmpOp::Lt, int(5)), (CmpOp::Lt, int(10))]
+            ))
+        );
+        assert_eq!(
+            parse_program(&tokenize("y = 1 < 5").unwrap()),
+            assign(Expression::Lt(Box::new(int(1)), Box::new(int(5))))
+        );
+    }
+
+    #[test]
+    fn parse_and_execute_runaway_recursion_in_loops() {
+        /* Each call sits inside a while, a for and two ifs, which takes far
+         * more stack per call than a plain recursion. The default limit
+         * must still be reached on this ordinary test thread. */
+        let source = "
+def f(n: int) -> int:
+    total = 0
+    while True:
+        for i in [1]:
+            if n >= 0:
+                if i > 0:
+                    total = total + (1 + [f(n + 1)][0]) * 2
+                    return total
+    return 0
+
+x = f(0)
+";
+        let program = parse(source).unwrap();
+
+        match run(&program) {
+            Ok(_) => assert!(false, "Expected the recursion limit to be exceeded"),
+            Err(s) => assert_eq!(s.root_cause(), &InterpreterError::RecursionLimitExceeded),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_index_assignment_order() {
+        /* As in Python, the value is evaluated first, then the indices
+         * from the outside in, each of them once. */
+        let source = r#"
+calls = ""
+def at(i: int, tag: str) -> int:
+    global calls
+    calls = calls + tag
+    return i
+
+def value() -> int:
+    global calls
+    calls = calls + "v"
+    return 9
+
+m = [[1, 2], [3, 4]]
+m[at(1, "a")][at(0, "b")] = value()
+"#;
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                let show = |name: &str| {
+                    eval(&Expression::Var(String::from(name)), &new_env).map(|v| v.to_string())
+                };
+                assert_eq!(show("m"), Ok(String::from("[[1, 2], [9, 4]]")));
+                assert_eq!(show("calls"), Ok(String::from("vab")));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_append_is_a_builtin() {
+        /* A function named append is called like any other, and the
+         * builtin reports a target that is not a list at run time. */
+        let source = r#"
+def append(a: int, b: int) -> int:
+    return a + b
+
+x = 1
+y = append(x, 2)
+append(1, 2)
+"#;
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => assert_eq!(new_env.get("y"), Some(EnvValue::CInt(3))),
+            Err(s) => assert!(false, "{}", s),
+        }
+
+        let program = parse("xs = [1]\nappend(xs, 2)\nappend(1, 2)\n").unwrap();
+        match execute(&program, Environment::new()) {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "'int' object has no attribute 'append' at line 3, col 1"
+            ),
+            Ok(_) => assert!(false, "append to an int should generate an error"),
+        }
+
+        let program = parse("xs = []\nys = append(xs, 1)\n").unwrap();
+        match execute(&program, Environment::new()) {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "append can only be called as a statement at line 2, col 6"
+            ),
+            Ok(_) => assert!(false, "append in an expression should generate an error"),
+        }
+    }
+
+    #[test]
+    fn parse_range_as_a_call() {
+        let program = parse_program(&tokenize("xs = range(10, 0, -2)").unwrap());
+
+        assert_eq!(
+            program,
+            Ok(Statement::Assignment(
+                Box::new(String::from("xs")),
+                Box::new(Expression::FuncCall(
+                    String::from("range"),
+                    Some(vec![
+                        Expression::CInt(10),
+                        Expression::CInt(0),
+                        Expression::CInt(-2),
+                    ]),
+                )),
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_and_execute_sets_are_iterable() {
+        /*
+         * > total = 0
+         * > for i in {1, 2, 2}:
+         * >     total = total + i
+         *
+         * The duplicate is stored once, so 'total' is 3, like sum({1, 2}).
+         */
+        let source = r#"
+s = {1, 2, 2}
+total = 0
+for i in s:
+    total = total + i
+found = 2 in s
+missing = 5 not in s
+sizes = [len(s), len({}), len({"a": 1}), len("abc"), len((1, 2)), len([])]
+summed = sum(s)
+ordered = sorted({3, 1, 2})
+"#;
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                let show = |name: &str| {
+                    eval(&Expression::Var(String::from(name)), &new_env).map(|v| v.to_string())
+                };
+                assert_eq!(show("total"), Ok(String::from("3")));
+                assert_eq!(show("found"), Ok(String::from("True")));
+                assert_eq!(show("missing"), Ok(String::from("True")));
+                assert_eq!(show("sizes"), Ok(String::from("[2, 0, 1, 3, 2, 0]")));
+                assert_eq!(show("summed"), Ok(String::from("3")));
+                assert_eq!(show("ordered"), Ok(String::from("[1, 2, 3]")));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+
+        match execute(&parse("n = len(5)\n").unwrap(), Environment::new()) {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "object of type 'int' has no len() at line 1, col 5"
+            ),
+            Ok(_) => assert!(false, "len(5) should generate an error"),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_bools_are_int_keys() {
+        /*
+         * > {1: "a", True: "b"}     # {1: 'b'}
+         * > {1: 0}[True]            # 0
+         * > True in {1: 0}          # True
+         * > {True, 1, 0, False}     # {True, 0}
+         */
+        let source = r#"
+merged = {1: "a", True: "b"}
+found = {1: 0}[True]
+member = True in {1: 0}
+items = {True, 1, 0, False}
+"#;
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                let show = |name: &str| {
+                    eval(&Expression::Var(String::from(name)), &new_env).map(|v| v.to_string())
+                };
+                assert_eq!(show("merged"), Ok(String::from("{1: 'b'}")));
+                assert_eq!(show("found"), Ok(String::from("0")));
+                assert_eq!(show("member"), Ok(String::from("True")));
+                assert_eq!(show("items"), Ok(String::from("{True, 0}")));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+    }
+
+    #[test]
+    fn parse_and_execute_unary_minus() {
+        /*
+         * > x = 0.0
+         * > str(-x)      # '-0.0'
+         * > -True        # -1
+         * > -(2 - 5)     # 3
+         */
+        let source = r#"
+x = 0.0
+text = str(-x)
+flipped = -True
+n = -(2 - 5)
+"#;
+        let program = parse(source).unwrap();
+
+        match execute(&program, Environment::new()) {
+            Ok(new_env) => {
+                let show = |name: &str| {
+                    eval(&Expression::Var(String::from(name)), &new_env).map(|v| v.to_string())
+                };
+                assert_eq!(show("text"), Ok(String::from("-0.0")));
+                assert_eq!(show("flipped"), Ok(String::from("-1")));
+                assert_eq!(show("n"), Ok(String::from("3")));
+            }
+            Err(s) => assert!(false, "{}", s),
+        }
+
+        match execute(&parse("s = \"a\"\nt = -s\n").unwrap(), Environment::new()) {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "bad operand type for unary -: 'str' at line 2, col 5"
+            ),
+            Ok(_) => assert!(false, "-'a' should generate an error"),
+        }
+    }
+}
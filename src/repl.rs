@@ -0,0 +1,197 @@
+use crate::interpreter::interpreter::{append_call, eval, execute, Environment, EvalResult};
+use crate::ir::ast::{Expression, Statement};
+use crate::parser::lexer::{tokenize, Token};
+use crate::parser::parser::{parse, parse_expression};
+
+/* An interactive session: lines are fed one at a time and every complete
+ * input runs against an environment that lasts for the whole session.
+ *
+ * An input is complete at the end of a line unless a bracket is still
+ * open, or it began with a block header such as 'def' or 'while', in
+ * which case it runs once an empty line closes the block. */
+pub struct Repl {
+    env: Environment,
+    buffer: String,
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Repl {
+    pub fn new() -> Repl {
+        Repl {
+            env: Environment::new(),
+            buffer: String::new(),
+        }
+    }
+
+    /* Whether the lines fed so far are waiting for more to complete them. */
+    pub fn is_pending(&self) -> bool {
+        !self.buffer.is_empty()
+    }
+
+    /* Feeds one line, without its line break. Once it completes an input,
+     * returns what to show for it: the value of an expression (None is not
+     * shown) or an error. Statements show nothing. */
+    pub fn feed(&mut self, line: &str) -> Option<String> {
+        self.buffer.push_str(line);
+        self.buffer.push('\n');
+        if self.is_incomplete(line) {
+            return None;
+        }
+        self.finish()
+    }
+
+    /* Runs whatever is pending, as when the input ends inside a block. */
+    pub fn finish(&mut self) -> Option<String> {
+        let source = std::mem::take(&mut self.buffer);
+        if source.trim().is_empty() {
+            return None;
+        }
+        self.run(&source)
+    }
+
+    fn is_incomplete(&self, line: &str) -> bool {
+        let opens_block = self
+            .buffer
+            .lines()
+            .next()
+            .is_some_and(|first| first.trim_end().ends_with(':'));
+        if opens_block {
+            return !line.trim().is_empty();
+        }
+
+        /* Errors are left for the parser to report once the input runs. */
+        let Ok(tokens) = tokenize(&self.buffer) else {
+            return false;
+        };
+        let depth = tokens.iter().fold(0, |depth, token| match token {
+            Token::LParen | Token::LBracket | Token::LBrace => depth + 1,
+            Token::RParen | Token::RBracket | Token::RBrace => depth - 1,
+            _ => depth,
+        });
+        depth > 0
+    }
+
+    /* Inputs are tried as statements first; one that is not a statement
+     * is evaluated as an expression. An input that is a single expression
+     * statement is evaluated too, so that its value can be shown, unless
+     * it calls the builtin append, which only runs as a statement. */
+    fn run(&mut self, source: &str) -> Option<String> {
+        match parse(source) {
+            Ok(stmt) => match expression_of(&stmt) {
+                Some(exp) if append_call(exp, &self.env).is_none() => self.show(exp),
+                _ => match execute(&stmt, self.env.clone()) {
+                    Ok(env) => {
+                        self.env = env;
+                        None
+                    }
+                    Err(err) => Some(format!("Error: {}", err)),
+                },
+            },
+            Err(stmt_err) => match parse_expression(source) {
+                Ok(exp) => self.show(&exp),
+                Err(_) => Some(stmt_err),
+            },
+        }
+    }
+
+    fn show(&self, exp: &Expression) -> Option<String> {
+        match eval(exp, &self.env) {
+            Ok(EvalResult::None) => None,
+            Ok(value) => Some(value.to_string()),
+            Err(err) => Some(format!("Error: {}", err)),
+        }
+    }
+}
+
+/* The expression of an input that is nothing but an expression statement. */
+fn expression_of(stmt: &Statement) -> Option<&Expression> {
+    match stmt {
+        Statement::Spanned(_, stmt) => expression_of(stmt),
+        Statement::Expr(exp) => Some(exp),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* Feeds the lines in order, then ends the input, collecting every
+     * output shown along the way. */
+    fn session(lines: &[&str]) -> Vec<String> {
+        let mut repl = Repl::new();
+        let mut outputs: Vec<String> = lines.iter().filter_map(|line| repl.feed(line)).collect();
+        outputs.extend(repl.finish());
+        outputs
+    }
+
+    #[test]
+    fn repl_keeps_environment_between_inputs() {
+        assert_eq!(
+            session(&["x = 20", "y = x + 1", "x * 2 + y", "str(y) + \"!\"", "None"]),
+            vec![String::from("61"), String::from("21!")]
+        );
+    }
+
+    #[test]
+    fn repl_accumulates_blocks() {
+        let outputs = session(&[
+            "def fact(n: int) -> int:",
+            "    if n <= 1:",
+            "        return 1",
+            "    return n * fact(n - 1)",
+            "",
+            "xs = [",
+            "    fact(3),",
+            "    fact(5)]",
+            "xs",
+            "i = 0",
+            "while i < 3:",
+            "    i = i + 1",
+        ]);
+        assert_eq!(outputs, vec![String::from("[6, 120]")]);
+    }
+
+    #[test]
+    fn repl_reports_errors_and_continues() {
+        let outputs = session(&["x = 1", "x = y", "x // 0", "x = = 2", "x"]);
+        assert_eq!(
+            outputs,
+            vec![
+                String::from("Error: Variable y not found at line 1, col 5"),
+                String::from("Error: Division by zero at line 1, col 1"),
+                String::from("Syntax error at line 1, column 5: expected an expression, found '='"),
+                String::from("1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn repl_pending_state() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.feed("while False:"), None);
+        assert!(repl.is_pending());
+        assert_eq!(repl.feed("    x = 1"), None);
+        assert_eq!(repl.feed(""), None);
+        assert!(!repl.is_pending());
+    }
+
+    #[test]
+    fn repl_runs_append_as_a_statement() {
+        let outputs = session(&[
+            "xs = [1]",
+            "append(xs, 2)",
+            "xs",
+            "def append(a: int, b: int) -> int:",
+            "    return a + b",
+            "",
+            "append(1, 2)",
+        ]);
+        assert_eq!(outputs, vec![String::from("[1, 2]"), String::from("3")]);
+    }
+}
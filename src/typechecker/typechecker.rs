@@ -0,0 +1,711 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::ir::ast::Expression;
+use crate::ir::ast::Name;
+use crate::ir::ast::Span;
+use crate::ir::ast::Statement;
+use crate::ir::ast::Type;
+
+/* A mismatch found before running the program, with the position of the
+ * innermost node that carried one. */
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeError {
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.span {
+            Some(span) => write!(
+                f,
+                "{} at line {}, col {}",
+                self.message, span.line, span.column
+            ),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/* Walks the program without running it and reports every obvious type
+ * mismatch it finds, such as adding a list to an int or passing an
+ * argument of the wrong type to a function whose definition it has seen.
+ *
+ * Types are inferred only as far as the program makes them plain: an
+ * expression whose type cannot be told (a parameter of type Any, a call
+ * to a builtin, a name assigned different types on different paths) is
+ * Any, and Any never produces an error. */
+pub fn check(stmt: &Statement) -> Result<(), Vec<TypeError>> {
+    let mut checker = Checker::new();
+    checker.check(stmt);
+    if checker.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(checker.errors)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Binding {
+    Value(Type),
+    Func(Vec<(Name, Type)>, Type),
+}
+
+struct Checker {
+    scopes: Vec<HashMap<Name, Binding>>,
+    /* The name and declared return type of each function whose body is
+     * being checked, innermost last. */
+    functions: Vec<(Name, Type)>,
+    span: Option<Span>,
+    errors: Vec<TypeError>,
+}
+
+impl Checker {
+    fn new() -> Checker {
+        Checker {
+            scopes: vec![HashMap::new()],
+            functions: Vec::new(),
+            span: None,
+            errors: Vec::new(),
+        }
+    }
+
+    fn error(&mut self, message: String) {
+        self.errors.push(TypeError {
+            message,
+            span: self.span,
+        });
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /* A name assigned values of different types may hold either one
+     * afterwards, so it is widened to Any. */
+    fn bind(&mut self, name: &Name, kind: Type) {
+        if name == "_" {
+            return;
+        }
+        if let Some(scope) = self.scopes.last_mut() {
+            let kind = match scope.get(name) {
+                Some(Binding::Value(old)) if *old != kind => Type::TAny,
+                _ => kind,
+            };
+            scope.insert(name.clone(), Binding::Value(kind));
+        }
+    }
+
+    fn check(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Spanned(span, stmt) => {
+                let outer = self.span.replace(*span);
+                self.check(stmt);
+                self.span = outer;
+            }
+            Statement::VarDeclaration(_)
+            | Statement::ValDeclaration(_)
+            | Statement::Nonlocal(_)
+            | Statement::Global(_)
+            | Statement::Pass
+            | Statement::Break
+            | Statement::Continue => (),
+            Statement::Assignment(name, exp) => {
+                let kind = self.infer(exp);
+                self.bind(name, kind);
+            }
+            Statement::MultiAssign(names, exps) => {
+                let kinds: Vec<Type> = exps.iter().map(|exp| self.infer(exp)).collect();
+                let kinds = match kinds.as_slice() {
+                    [Type::TTuple(items)] => items.clone(),
+                    [_] => vec![],
+                    _ => kinds,
+                };
+                self.bind_all(names, kinds);
+            }
+            Statement::IndexAssign(target, index, exp) => {
+                self.infer(target);
+                self.infer(index);
+                self.infer(exp);
+            }
+            Statement::Expr(exp) => match list_append(exp) {
+                Some((name, item))
+                    if !matches!(self.lookup("append"), Some(Binding::Func(_, _))) =>
+                {
+                    self.append(name, item)
+                }
+                _ => {
+                    self.infer(exp);
+                }
+            },
+            Statement::IfThenElse(cond, stmt_then, stmt_else)
+            | Statement::WhileElse(cond, stmt_then, stmt_else) => {
+                self.infer(cond);
+                self.check(stmt_then);
+                self.check(stmt_else);
+            }
+            Statement::If(cond, stmt) | Statement::While(cond, stmt) => {
+                self.infer(cond);
+                self.check(stmt);
+            }
+            Statement::For(var, exp, stmt) => {
+                let kind = item_type(&self.infer(exp));
+                self.bind(var, kind);
+                self.check(stmt);
+            }
+            Statement::ForElse(var, exp, stmt, stmt_else) => {
+                let kind = item_type(&self.infer(exp));
+                self.bind(var, kind);
+                self.check(stmt);
+                self.check(stmt_else);
+            }
+            Statement::ForUnpack(vars, exp, stmt) => {
+                let kinds = match item_type(&self.infer(exp)) {
+                    Type::TTuple(items) => items,
+                    _ => vec![],
+                };
+                self.bind_all(vars, kinds);
+                self.check(stmt);
+            }
+            Statement::Sequence(first, second) => {
+                self.check(first);
+                self.check(second);
+            }
+            Statement::Func(name, kind, params, body, retrn) => {
+                let params: Vec<(Name, Type)> = params
+                    .iter()
+                    .flatten()
+                    .map(|(param, kind)| (param.clone(), *kind.clone()))
+                    .collect();
+                /* Bound before the body is checked so that recursive
+                 * calls are checked too. */
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.insert(*name.clone(), Binding::Func(params.clone(), *kind.clone()));
+                }
+
+                self.scopes.push(
+                    params
+                        .into_iter()
+                        .map(|(param, kind)| (param, Binding::Value(kind)))
+                        .collect(),
+                );
+                self.functions.push((*name.clone(), *kind.clone()));
+                if let Some(body) = body {
+                    self.check(body);
+                }
+                self.infer(retrn);
+                self.functions.pop();
+                self.scopes.pop();
+            }
+            Statement::Assert(cond, message) => {
+                self.infer(cond);
+                if let Some(message) = message {
+                    self.infer(message);
+                }
+            }
+            Statement::Return(exp) => {
+                let kind = self.infer(exp);
+                if let Some((name, expected)) = self.functions.last() {
+                    if !accepts(expected, &kind) {
+                        let message =
+                            format!("'{}' should return {} but returns {}", name, expected, kind);
+                        self.error(message);
+                    }
+                }
+            }
+        }
+    }
+
+    /* Unpacking binds each target to the matching item type when the
+     * count is known to match, and to Any otherwise. */
+    fn bind_all(&mut self, names: &[Name], kinds: Vec<Type>) {
+        let kinds = if kinds.len() == names.len() {
+            kinds
+        } else {
+            vec![Type::TAny; names.len()]
+        };
+        for (name, kind) in names.iter().zip(kinds) {
+            self.bind(name, kind);
+        }
+    }
+
+    fn infer(&mut self, exp: &Expression) -> Type {
+        match exp {
+            Expression::Spanned(span, exp) => {
+                let outer = self.span.replace(*span);
+                let kind = self.infer(exp);
+                self.span = outer;
+                kind
+            }
+            Expression::CInt(_) => Type::TInteger,
+            Expression::CReal(_) => Type::TReal,
+            Expression::Bool(_) => Type::TBool,
+            Expression::CString(_) => Type::TString,
+            Expression::FString(parts) => {
+                for part in parts {
+                    self.infer(part);
+                }
+                Type::TString
+            }
+            Expression::None => Type::TNone,
+            Expression::Var(name) => match self.lookup(name) {
+                Some(Binding::Value(kind)) => kind.clone(),
+                Some(Binding::Func(_, _)) => Type::TFunction,
+                None => Type::TAny,
+            },
+            Expression::Add(lhs, rhs) => self.arithmetic("+", lhs, rhs),
+            Expression::Sub(lhs, rhs) => self.arithmetic("-", lhs, rhs),
+            Expression::Neg(exp) => match self.infer(exp) {
+                Type::TInteger | Type::TBool => Type::TInteger,
+                kind @ (Type::TReal | Type::TAny) => kind,
+                kind => {
+                    self.error(format!("bad operand type for unary -: '{}'", kind));
+                    Type::TAny
+                }
+            },
+            Expression::Mul(lhs, rhs) => self.arithmetic("*", lhs, rhs),
+            Expression::Div(lhs, rhs) => self.arithmetic("/", lhs, rhs),
+            Expression::FloorDiv(lhs, rhs) => self.arithmetic("//", lhs, rhs),
+            Expression::Mod(lhs, rhs) => self.arithmetic("%", lhs, rhs),
+            Expression::Pow(lhs, rhs) => self.arithmetic("**", lhs, rhs),
+            Expression::BitAnd(lhs, rhs) => self.arithmetic("&", lhs, rhs),
+            Expression::BitOr(lhs, rhs) => self.arithmetic("|", lhs, rhs),
+            Expression::BitXor(lhs, rhs) => self.arithmetic("^", lhs, rhs),
+            Expression::Shl(lhs, rhs) => self.arithmetic("<<", lhs, rhs),
+            Expression::Shr(lhs, rhs) => self.arithmetic(">>", lhs, rhs),
+            Expression::Eq(lhs, rhs)
+            | Expression::NotEq(lhs, rhs)
+            | Expression::Lt(lhs, rhs)
+            | Expression::LtE(lhs, rhs)
+            | Expression::Gt(lhs, rhs)
+            | Expression::GtE(lhs, rhs)
+            | Expression::Is(lhs, rhs)
+            | Expression::IsNot(lhs, rhs)
+            | Expression::In(lhs, rhs)
+            | Expression::NotIn(lhs, rhs) => {
+                self.infer(lhs);
+                self.infer(rhs);
+                Type::TBool
+            }
+            Expression::Compare(first, links) => {
+                self.infer(first);
+                for (_, operand) in links {
+                    self.infer(operand);
+                }
+                Type::TBool
+            }
+            Expression::And(lhs, rhs) | Expression::Or(lhs, rhs) => {
+                let lhs = self.infer(lhs);
+                let rhs = self.infer(rhs);
+                if lhs == rhs {
+                    lhs
+                } else {
+                    Type::TAny
+                }
+            }
+            Expression::FuncCall(name, args) => self.func_call(name, args),
+            Expression::Lambda(params, body) => {
+                self.scopes.push(
+                    params
+                        .iter()
+                        .map(|param| (param.clone(), Binding::Value(Type::TAny)))
+                        .collect(),
+                );
+                self.infer(body);
+                self.scopes.pop();
+                Type::TFunction
+            }
+            Expression::Call(callee, args) => {
+                self.infer(callee);
+                for arg in args.iter().flatten() {
+                    self.infer(arg);
+                }
+                Type::TAny
+            }
+            Expression::List(items) => {
+                let kinds: Vec<Type> = items.iter().map(|item| self.infer(item)).collect();
+                Type::TList(Box::new(common_type(&kinds)))
+            }
+            Expression::Tuple(items) => {
+                Type::TTuple(items.iter().map(|item| self.infer(item)).collect())
+            }
+            Expression::Dict(entries) => {
+                for (key, value) in entries {
+                    self.infer(key);
+                    self.infer(value);
+                }
+                Type::TAny
+            }
+            Expression::Set(items) => {
+                for item in items {
+                    self.infer(item);
+                }
+                Type::TAny
+            }
+            Expression::Index(target, index) => {
+                let target = self.infer(target);
+                self.infer(index);
+                match target {
+                    Type::TList(kind) => *kind,
+                    Type::TString => Type::TString,
+                    _ => Type::TAny,
+                }
+            }
+            Expression::ListComp {
+                element,
+                var,
+                iterable,
+                condition,
+            }
+            | Expression::GenExp {
+                element,
+                var,
+                iterable,
+                condition,
+            } => {
+                let kind = item_type(&self.infer(iterable));
+                self.scopes.push(HashMap::new());
+                self.bind(var, kind);
+                if let Some(condition) = condition {
+                    self.infer(condition);
+                }
+                let kind = self.infer(element);
+                self.scopes.pop();
+                Type::TList(Box::new(kind))
+            }
+            Expression::Range(start, end, step) | Expression::RangeInclusive(start, end, step) => {
+                self.infer(end);
+                for bound in [start, step].into_iter().flatten() {
+                    self.infer(bound);
+                }
+                Type::TList(Box::new(Type::TInteger))
+            }
+        }
+    }
+
+    fn arithmetic(&mut self, op: &str, lhs: &Expression, rhs: &Expression) -> Type {
+        let lhs = self.infer(lhs);
+        let rhs = self.infer(rhs);
+        match arithmetic_type(op, &lhs, &rhs) {
+            Some(kind) => kind,
+            None => {
+                let message = format!(
+                    "unsupported operand type(s) for {}: '{}' and '{}'",
+                    op, lhs, rhs
+                );
+                self.error(message);
+                Type::TAny
+            }
+        }
+    }
+
+    /* Calls to functions defined earlier in the program are checked
+     * against their declarations; builtins and anything else return Any. */
+    fn func_call(&mut self, name: &Name, args: &Option<Vec<Expression>>) -> Type {
+        let kinds: Vec<Type> = args.iter().flatten().map(|arg| self.infer(arg)).collect();
+        let (params, kind) = match self.lookup(name) {
+            Some(Binding::Func(params, kind)) => (params.clone(), kind.clone()),
+            _ if name == "range" || name == "range_inclusive" => {
+                return Type::TList(Box::new(Type::TInteger))
+            }
+            _ => return Type::TAny,
+        };
+
+        if params.len() != kinds.len() {
+            let message = format!(
+                "{} requires {} arguments, got {}",
+                name,
+                params.len(),
+                kinds.len()
+            );
+            self.error(message);
+            return kind;
+        }
+        for ((param, expected), actual) in params.iter().zip(&kinds) {
+            if !accepts(expected, actual) {
+                let message = format!(
+                    "argument '{}' of {} expected {} but got {}",
+                    param, name, expected, actual
+                );
+                self.error(message);
+            }
+        }
+        kind
+    }
+
+    /* 'append(xs, v)' on a variable: an item that doesn't fit the list's
+     * type widens it to a list of anything. */
+    fn append(&mut self, name: &Name, item: &Expression) {
+        let item_kind = self.infer(item);
+        match self.lookup(name) {
+            Some(Binding::Value(Type::TList(kind))) => {
+                if !accepts(kind, &item_kind) {
+                    self.bind(name, Type::TList(Box::new(Type::TAny)));
+                }
+            }
+            Some(Binding::Value(Type::TAny | Type::TOptional(_))) | None => (),
+            Some(Binding::Value(kind)) => {
+                let message = format!("'{}' object has no attribute 'append'", kind);
+                self.error(message);
+            }
+            Some(Binding::Func(_, _)) => {
+                self.error(String::from("'function' object has no attribute 'append'"))
+            }
+        }
+    }
+}
+
+/* The variable and item of a line calling append on a variable. */
+fn list_append(exp: &Expression) -> Option<(&Name, &Expression)> {
+    fn unspanned(exp: &Expression) -> &Expression {
+        match exp {
+            Expression::Spanned(_, exp) => unspanned(exp),
+            exp => exp,
+        }
+    }
+    match unspanned(exp) {
+        Expression::FuncCall(name, Some(args)) if name == "append" => match args.as_slice() {
+            [target, item] => match unspanned(target) {
+                Expression::Var(target) => Some((target, item)),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/* Whether a value of type 'actual' may be used where 'expected' is
+ * declared, following the runtime check on arguments: ints are not
+ * accepted for reals, and a list's items must all fit. */
+fn accepts(expected: &Type, actual: &Type) -> bool {
+    match (expected, actual) {
+        (Type::TAny, _) | (_, Type::TAny) => true,
+        (Type::TOptional(_), Type::TNone) => true,
+        (Type::TOptional(expected), Type::TOptional(actual)) => accepts(expected, actual),
+        (Type::TOptional(expected), actual) => accepts(expected, actual),
+        (Type::TList(expected), Type::TList(actual)) => accepts(expected, actual),
+        (Type::TTuple(expected), Type::TTuple(actual)) => {
+            expected.len() == actual.len()
+                && expected
+                    .iter()
+                    .zip(actual)
+                    .all(|(expected, actual)| accepts(expected, actual))
+        }
+        (expected, actual) => expected == actual,
+    }
+}
+
+/* The type of the result of an arithmetic operator, mirroring what the
+ * interpreter accepts, or None when it would raise a TypeError. Bools
+ * count as ints. */
+fn arithmetic_type(op: &str, lhs: &Type, rhs: &Type) -> Option<Type> {
+    let number = |kind: &Type| match kind {
+        Type::TInteger | Type::TBool => Some(false),
+        Type::TReal => Some(true),
+        _ => None,
+    };
+    let integer = |kind: &Type| matches!(kind, Type::TInteger | Type::TBool);
+
+    match (lhs, rhs) {
+        (Type::TAny | Type::TOptional(_), _) | (_, Type::TAny | Type::TOptional(_)) => {
+            Some(Type::TAny)
+        }
+        _ if matches!(op, "&" | "|" | "^" | "<<" | ">>") => {
+            (integer(lhs) && integer(rhs)).then_some(Type::TInteger)
+        }
+        _ => match (op, number(lhs), number(rhs)) {
+            ("/", Some(_), Some(_)) => Some(Type::TReal),
+            /* An int to a negative int power is a real. */
+            ("**", Some(false), Some(false)) => Some(Type::TAny),
+            (_, Some(false), Some(false)) => Some(Type::TInteger),
+            (_, Some(_), Some(_)) => Some(Type::TReal),
+            ("+", _, _) => match (lhs, rhs) {
+                (Type::TString, Type::TString) => Some(Type::TString),
+                (Type::TList(lhs), Type::TList(rhs)) => {
+                    Some(Type::TList(Box::new(common_type(&[
+                        *lhs.clone(),
+                        *rhs.clone(),
+                    ]))))
+                }
+                (Type::TTuple(lhs), Type::TTuple(rhs)) => {
+                    Some(Type::TTuple(lhs.iter().chain(rhs).cloned().collect()))
+                }
+                _ => None,
+            },
+            ("*", _, _) => match (lhs, rhs) {
+                (Type::TList(_), kind) if integer(kind) => Some(lhs.clone()),
+                (kind, Type::TList(_)) if integer(kind) => Some(rhs.clone()),
+                (Type::TString, Type::TInteger) | (Type::TInteger, Type::TString) => {
+                    Some(Type::TString)
+                }
+                _ => None,
+            },
+            _ => None,
+        },
+    }
+}
+
+/* The item type of a list literal: ints are promoted to reals when both
+ * appear, as the interpreter does, and any other mix is Any. */
+fn common_type(kinds: &[Type]) -> Type {
+    let mut kinds = kinds.iter();
+    let Some(first) = kinds.next() else {
+        return Type::TAny;
+    };
+    kinds.fold(first.clone(), |acc, kind| match (&acc, kind) {
+        (acc, kind) if acc == kind => acc.clone(),
+        (Type::TInteger | Type::TReal, Type::TInteger | Type::TReal) => Type::TReal,
+        _ => Type::TAny,
+    })
+}
+
+/* The type a 'for' loop binds when iterating over a value of this type. */
+fn item_type(kind: &Type) -> Type {
+    match kind {
+        Type::TList(item) => *item.clone(),
+        Type::TString => Type::TString,
+        _ => Type::TAny,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::parse;
+
+    fn check_source(source: &str) -> Result<(), Vec<String>> {
+        let program = parse(source).unwrap();
+        check(&program).map_err(|errors| errors.iter().map(|e| e.to_string()).collect())
+    }
+
+    #[test]
+    fn well_typed_program() {
+        let source = "
+def mean(xs: list[float]) -> float:
+    total = 0.0
+    for x in xs:
+        total = total + x
+    return total / 2
+
+def label(name: str, n: Optional[int]) -> str:
+    if n == None:
+        return name
+    return name + \": \" + str(n)
+
+m = mean([1.5, 2.5])
+s = label(\"a\", None) + label(\"b\", 3)
+squares = [i * i for i in range(5)]
+both = squares + [10]
+";
+        assert_eq!(check_source(source), Ok(()));
+    }
+
+    #[test]
+    fn mistyped_program_reports_every_error() {
+        let source = "
+def twice(n: int) -> int:
+    return n * 2
+
+xs = [1, 2]
+y = xs + 1
+z = twice(\"3\")
+w = twice(1, 2)
+s = \"a\" - \"b\"
+";
+        assert_eq!(
+            check_source(source),
+            Err(vec![
+                String::from(
+                    "unsupported operand type(s) for +: 'list[int]' and 'int' at line 6, col 5"
+                ),
+                String::from("argument 'n' of twice expected int but got str at line 7, col 5"),
+                String::from("twice requires 1 arguments, got 2 at line 8, col 5"),
+                String::from("unsupported operand type(s) for -: 'str' and 'str' at line 9, col 5"),
+            ])
+        );
+    }
+
+    #[test]
+    fn return_type_mismatch() {
+        let source = "
+def name(n: int) -> str:
+    return n + 1
+";
+        assert_eq!(
+            check_source(source),
+            Err(vec![String::from(
+                "'name' should return str but returns int at line 3, col 5"
+            )])
+        );
+    }
+
+    #[test]
+    fn reassigned_names_widen_to_any() {
+        /* 'x' may be an int or a str after the 'if', so adding to it is
+         * not an obvious mismatch. */
+        let source = "
+x = 1
+if x > 0:
+    x = \"one\"
+y = x + 1
+";
+        assert_eq!(check_source(source), Ok(()));
+    }
+
+    #[test]
+    fn append_checks_its_target_unless_redefined() {
+        let source = "
+n = 1
+append(n, 2)
+";
+        assert_eq!(
+            check_source(source),
+            Err(vec![String::from(
+                "'int' object has no attribute 'append' at line 3, col 1"
+            )])
+        );
+
+        let source = "
+def append(a: int, b: int) -> int:
+    return a + b
+
+n = 1
+append(n, 2)
+";
+        assert_eq!(check_source(source), Ok(()));
+    }
+
+    #[test]
+    fn range_calls_are_lists_of_ints() {
+        let source = "
+xs = range(3)
+ys = range_inclusive(1, 3)
+y = xs + \"a\"
+z = ys + [4]
+";
+        assert_eq!(
+            check_source(source),
+            Err(vec![String::from(
+                "unsupported operand type(s) for +: 'list[int]' and 'str' at line 4, col 5"
+            )])
+        );
+    }
+
+    #[test]
+    fn unary_minus_needs_a_number() {
+        let source = "
+a = -1.5
+b = -True + a
+c = -\"x\"
+";
+        assert_eq!(
+            check_source(source),
+            Err(vec![String::from(
+                "bad operand type for unary -: 'str' at line 4, col 5"
+            )])
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashSet;
+
+use crate::ir::ast::Expression;
+use crate::ir::ast::Name;
+use crate::ir::ast::Statement;
+
+/* Names that are assigned but never read, in the order they were first
+ * assigned. The module and every function body are scopes of their own,
+ * so a local left unread is reported even when an outer variable of the
+ * same name is read, and once per function it is unread in.
+ *
+ * The analysis looks at the text, not at the paths a run can take: a
+ * read anywhere in a scope counts, even one before the assignment or in
+ * a branch that never runs. A read inside a nested function or lambda
+ * counts for the scopes around it too, since it may see their variables.
+ * Loop variables are reported like any assignment, unless named '_'.
+ * Parameters belong to the signature and are never reported, and names
+ * declared 'global' or 'nonlocal' are writes to another scope, so they
+ * are not reported either. */
+pub fn find_unused(stmt: &Statement) -> Vec<Name> {
+    let mut finder = Finder {
+        scopes: vec![Scope::default()],
+        unused: Vec::new(),
+        order: 0,
+    };
+    finder.statement(stmt);
+    finder.close_scope();
+    finder.unused.sort_by_key(|(order, _)| *order);
+    finder.unused.into_iter().map(|(_, name)| name).collect()
+}
+
+#[derive(Default)]
+struct Scope {
+    /* Each assigned name with the order of its first assignment. */
+    assigned: Vec<(usize, Name)>,
+    read: HashSet<Name>,
+}
+
+struct Finder {
+    scopes: Vec<Scope>,
+    unused: Vec<(usize, Name)>,
+    order: usize,
+}
+
+impl Finder {
+    fn assign(&mut self, name: &Name) {
+        if name == "_" {
+            return;
+        }
+        if let Some(scope) = self.scopes.last_mut() {
+            if !scope.assigned.iter().any(|(_, other)| other == name) {
+                scope.assigned.push((self.order, name.clone()));
+                self.order += 1;
+            }
+        }
+    }
+
+    fn read(&mut self, name: &Name) {
+        for scope in self.scopes.iter_mut() {
+            scope.read.insert(name.clone());
+        }
+    }
+
+    fn close_scope(&mut self) {
+        if let Some(scope) = self.scopes.pop() {
+            self.unused.extend(
+                scope
+                    .assigned
+                    .into_iter()
+                    .filter(|(_, name)| !scope.read.contains(name)),
+            );
+        }
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Spanned(_, stmt) => self.statement(stmt),
+            Statement::VarDeclaration(_)
+            | Statement::ValDeclaration(_)
+            | Statement::Pass
+            | Statement::Break
+            | Statement::Continue => (),
+            Statement::Assignment(name, exp) => {
+                self.expression(exp);
+                self.assign(name);
+            }
+            Statement::MultiAssign(names, exps) => {
+                for exp in exps {
+                    self.expression(exp);
+                }
+                for name in names {
+                    self.assign(name);
+                }
+            }
+            Statement::IndexAssign(target, index, exp) => {
+                self.expression(target);
+                self.expression(index);
+                self.expression(exp);
+            }
+            Statement::IfThenElse(cond, stmt_then, stmt_else)
+            | Statement::WhileElse(cond, stmt_then, stmt_else) => {
+                self.expression(cond);
+                self.statement(stmt_then);
+                self.statement(stmt_else);
+            }
+            Statement::If(cond, stmt) | Statement::While(cond, stmt) => {
+                self.expression(cond);
+                self.statement(stmt);
+            }
+            Statement::For(name, exp, stmt) => {
+                self.expression(exp);
+                self.assign(name);
+                self.statement(stmt);
+            }
+            Statement::ForElse(name, exp, stmt, stmt_else) => {
+                self.expression(exp);
+                self.assign(name);
+                self.statement(stmt);
+                self.statement(stmt_else);
+            }
+            Statement::ForUnpack(names, exp, stmt) => {
+                self.expression(exp);
+                for name in names {
+                    self.assign(name);
+                }
+                self.statement(stmt);
+            }
+            Statement::Sequence(s1, s2) => {
+                self.statement(s1);
+                self.statement(s2);
+            }
+            Statement::Func(_, _, params, body, retrn) => {
+                let mut scope = Scope::default();
+                scope
+                    .read
+                    .extend(params.iter().flatten().map(|(param, _)| param.clone()));
+                self.scopes.push(scope);
+                if let Some(body) = body {
+                    self.statement(body);
+                }
+                self.expression(retrn);
+                self.close_scope();
+            }
+            Statement::Return(exp) | Statement::Expr(exp) => self.expression(exp),
+            Statement::Nonlocal(names) | Statement::Global(names) => {
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.read.extend(names.iter().cloned());
+                }
+            }
+            Statement::Assert(cond, message) => {
+                self.expression(cond);
+                if let Some(message) = message {
+                    self.expression(message);
+                }
+            }
+        }
+    }
+
+    fn expression(&mut self, exp: &Expression) {
+        match exp {
+            Expression::Spanned(_, exp) => self.expression(exp),
+            Expression::CInt(_)
+            | Expression::CReal(_)
+            | Expression::Bool(_)
+            | Expression::CString(_)
+            | Expression::None => (),
+            Expression::Var(name) => self.read(name),
+            Expression::Add(lhs, rhs)
+            | Expression::Sub(lhs, rhs)
+            | Expression::Mul(lhs, rhs)
+            | Expression::Div(lhs, rhs)
+            | Expression::FloorDiv(lhs, rhs)
+            | Expression::Mod(lhs, rhs)
+            | Expression::Pow(lhs, rhs)
+            | Expression::BitAnd(lhs, rhs)
+            | Expression::BitOr(lhs, rhs)
+            | Expression::BitXor(lhs, rhs)
+            | Expression::Shl(lhs, rhs)
+            | Expression::Shr(lhs, rhs)
+            | Expression::Eq(lhs, rhs)
+            | Expression::NotEq(lhs, rhs)
+            | Expression::Lt(lhs, rhs)
+            | Expression::LtE(lhs, rhs)
+            | Expression::Gt(lhs, rhs)
+            | Expression::GtE(lhs, rhs)
+            | Expression::Is(lhs, rhs)
+            | Expression::IsNot(lhs, rhs)
+            | Expression::In(lhs, rhs)
+            | Expression::NotIn(lhs, rhs)
+            | Expression::And(lhs, rhs)
+            | Expression::Or(lhs, rhs)
+            | Expression::Index(lhs, rhs) => {
+                self.expression(lhs);
+                self.expression(rhs);
+            }
+            Expression::Compare(first, links) => {
+                self.expression(first);
+                for (_, operand) in links {
+                    self.expression(operand);
+                }
+            }
+            Expression::FuncCall(name, args) => {
+                self.read(name);
+                for arg in args.iter().flatten() {
+                    self.expression(arg);
+                }
+            }
+            Expression::Call(callee, args) => {
+                self.expression(callee);
+                for arg in args.iter().flatten() {
+                    self.expression(arg);
+                }
+            }
+            Expression::FString(items)
+            | Expression::List(items)
+            | Expression::Tuple(items)
+            | Expression::Set(items) => {
+                for item in items {
+                    self.expression(item);
+                }
+            }
+            Expression::Dict(entries) => {
+                for (key, value) in entries {
+                    self.expression(key);
+                    self.expression(value);
+                }
+            }
+            Expression::ListComp {
+                element,
+                iterable,
+                condition,
+                ..
+            }
+            | Expression::GenExp {
+                element,
+                iterable,
+                condition,
+                ..
+            } => {
+                self.expression(iterable);
+                self.expression(element);
+                if let Some(condition) = condition {
+                    self.expression(condition);
+                }
+            }
+            Expression::Range(start, end, step) | Expression::RangeInclusive(start, end, step) => {
+                self.expression(end);
+                for bound in [start, step].into_iter().flatten() {
+                    self.expression(bound);
+                }
+            }
+            Expression::Neg(exp) | Expression::Lambda(_, exp) => self.expression(exp),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::parse;
+
+    #[test]
+    fn unused_assignments() {
+        let source = "
+total = 0
+unused = 1
+for i in range(3):
+    total = total + 1
+for _ in range(2):
+    pass
+
+def f(n: int) -> int:
+    step = 2
+    scratch = n
+    global counter
+    counter = 0
+    return n + step
+
+x = f(total)
+";
+        assert_eq!(
+            find_unused(&parse(source).unwrap()),
+            vec![
+                String::from("unused"),
+                String::from("i"),
+                String::from("scratch"),
+                String::from("x"),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_reads_count_for_outer_scopes() {
+        let source = "
+base = 10
+scale = lambda v: v * base
+
+def shift(n: int) -> int:
+    return n + offset
+
+offset = 1
+y = shift(2)
+print(scale(y))
+";
+        assert_eq!(find_unused(&parse(source).unwrap()), Vec::<Name>::new());
+    }
+}